@@ -0,0 +1,32 @@
+extern crate proc_macro;
+
+mod shader_layout;
+mod vertex_input;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+//Generates vulkan::VertexInput binding and attribute descriptions from struct fields.
+//Fields get sequential locations, override with #[location = N] and #[format = "R32G32_SFLOAT"].
+//Put #[input_rate = "instance"] on the struct for per-instance data.
+#[proc_macro_derive(VertexInput, attributes(location, format, input_rate))]
+pub fn derive_vertex_input(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match vertex_input::expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+//Computes std140/std430 layouts of a #[repr(C)] struct for vulkan::ShaderLayout.
+//With #[layout = "std140"] or #[layout = "std430"] a mismatching member fails to compile.
+#[proc_macro_derive(ShaderLayout, attributes(layout))]
+pub fn derive_shader_layout(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match shader_layout::expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
@@ -0,0 +1,205 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Attribute, Data, DeriveInput, Error, Expr, Fields, Lit, Meta, NestedMeta, Result, Type};
+
+enum Member<'a> {
+    Field(&'a Type),
+    Array(&'a Type, &'a Expr),
+}
+
+pub fn expand(input: &DeriveInput) -> Result<TokenStream> {
+    let name = &input.ident;
+
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "ShaderLayout can not be derived for generic structs",
+        ));
+    }
+
+    if !is_repr_c(&input.attrs) {
+        return Err(Error::new_spanned(
+            name,
+            "ShaderLayout needs a #[repr(C)] struct",
+        ));
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    name,
+                    "ShaderLayout needs a struct with named fields",
+                ))
+            }
+        },
+        _ => return Err(Error::new_spanned(name, "ShaderLayout needs a struct")),
+    };
+
+    let members: Vec<(String, Member)> = fields
+        .iter()
+        .map(|field| (field.ident.as_ref().unwrap().to_string(), member(&field.ty)))
+        .collect();
+
+    let std140 = layout(&members, true);
+    let std430 = layout(&members, false);
+
+    let check = layout_attribute(&input.attrs)?.map(|std140| layout_check(name, &members, std140));
+
+    Ok(quote! {
+        unsafe impl ::vulkan::ShaderLayout for #name {
+            const STD140: ::vulkan::utilities::LayoutInfo = #std140;
+            const STD430: ::vulkan::utilities::LayoutInfo = #std430;
+        }
+
+        #check
+    })
+}
+
+fn is_repr_c(attrs: &[Attribute]) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("repr"))
+        .filter_map(|attr| attr.parse_meta().ok())
+        .any(|meta| match meta {
+            Meta::List(list) => list.nested.iter().any(|nested| match nested {
+                NestedMeta::Meta(meta) => meta.path().is_ident("C"),
+                _ => false,
+            }),
+            _ => false,
+        })
+}
+
+//#[layout = "std140"] or #[layout = "std430"], returns true for std140
+fn layout_attribute(attrs: &[Attribute]) -> Result<Option<bool>> {
+    let attr = match attrs.iter().find(|attr| attr.path.is_ident("layout")) {
+        Some(attr) => attr,
+        None => return Ok(None),
+    };
+
+    if let Meta::NameValue(meta) = attr.parse_meta()? {
+        if let Lit::Str(value) = &meta.lit {
+            match value.value().as_str() {
+                "std140" => return Ok(Some(true)),
+                "std430" => return Ok(Some(false)),
+                _ => {}
+            }
+        }
+    }
+    Err(Error::new_spanned(
+        attr,
+        "expected #[layout = \"std140\"] or #[layout = \"std430\"]",
+    ))
+}
+
+fn is_scalar(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => match path.path.segments.last() {
+            Some(segment) => {
+                segment.ident == "f32" || segment.ident == "i32" || segment.ident == "u32"
+            }
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+fn literal_length(len: &Expr) -> Option<u32> {
+    if let Expr::Lit(lit) = len {
+        if let Lit::Int(value) = &lit.lit {
+            return value.base10_parse().ok();
+        }
+    }
+    None
+}
+
+//[f32; 2..4] are GLSL vectors and [[f32; 4]; 4] a mat4, every other array is a GLSL array
+fn member(ty: &Type) -> Member<'_> {
+    if let Type::Array(array) = ty {
+        let len = literal_length(&array.len);
+        let is_vector = is_scalar(&array.elem) && matches!(len, Some(2..=4));
+        let is_matrix = len == Some(4)
+            && match &*array.elem {
+                Type::Array(row) => is_scalar(&row.elem) && literal_length(&row.len) == Some(4),
+                _ => false,
+            };
+
+        if !is_vector && !is_matrix {
+            return Member::Array(&array.elem, &array.len);
+        }
+    }
+    Member::Field(ty)
+}
+
+fn layout_constant(std140: bool) -> TokenStream {
+    if std140 {
+        quote!(STD140)
+    } else {
+        quote!(STD430)
+    }
+}
+
+fn member_step(member: &Member, std140: bool) -> TokenStream {
+    let constant = layout_constant(std140);
+    match member {
+        Member::Field(ty) => quote! {
+            .field(
+                <#ty as ::vulkan::ShaderLayout>::#constant,
+                ::std::mem::align_of::<#ty>(),
+                ::std::mem::size_of::<#ty>(),
+            )
+        },
+        Member::Array(elem, len) => quote! {
+            .array(
+                <#elem as ::vulkan::ShaderLayout>::#constant,
+                ::std::mem::align_of::<#elem>(),
+                ::std::mem::size_of::<#elem>(),
+                (#len) as usize,
+            )
+        },
+    }
+}
+
+fn layout(members: &[(String, Member)], std140: bool) -> TokenStream {
+    let steps = members.iter().map(|(_, member)| member_step(member, std140));
+
+    quote! {
+        ::vulkan::utilities::LayoutBuilder::new(#std140)
+            #(#steps)*
+            .finish(::std::mem::size_of::<Self>())
+    }
+}
+
+//Compile time assertions naming the first member that is off
+fn layout_check(name: &syn::Ident, members: &[(String, Member)], std140: bool) -> TokenStream {
+    let layout_name = if std140 { "std140" } else { "std430" };
+
+    let steps = members.iter().map(|(field, member)| {
+        let step = member_step(member, std140);
+        let message = format!(
+            "`{}::{}` is not at its {} offset, reorder members or add padding",
+            name, field, layout_name
+        );
+        quote! {
+            let builder = builder #step;
+            assert!(builder.matches, #message);
+        }
+    });
+
+    let size_message = format!(
+        "`{}` size does not match {}, add padding at the end",
+        name, layout_name
+    );
+
+    quote! {
+        const _: () = {
+            let builder = ::vulkan::utilities::LayoutBuilder::new(#std140);
+            #(#steps)*
+            assert!(
+                builder.finish(::std::mem::size_of::<#name>()).matches,
+                #size_message
+            );
+        };
+    }
+}
@@ -0,0 +1,226 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{
+    Attribute, Data, DeriveInput, Error, Expr, Fields, GenericArgument, Ident, Lit, Meta,
+    PathArguments, Result, Type,
+};
+
+//Size of one vec4 row, used when a matrix spans several locations
+const ROW_SIZE: u32 = 16;
+
+struct VertexFormat {
+    format: Ident,
+    locations: u32,
+}
+
+pub fn expand(input: &DeriveInput) -> Result<TokenStream> {
+    let name = &input.ident;
+
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "VertexInput can not be derived for generic structs",
+        ));
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    name,
+                    "VertexInput needs a struct with named fields",
+                ))
+            }
+        },
+        _ => return Err(Error::new_spanned(name, "VertexInput needs a struct")),
+    };
+
+    let input_rate = match string_attribute(&input.attrs, "input_rate")? {
+        Some((rate, span)) => match rate.as_str() {
+            "vertex" => quote!(::vulkan::prelude::vk::VertexInputRate::VERTEX),
+            "instance" => quote!(::vulkan::prelude::vk::VertexInputRate::INSTANCE),
+            _ => {
+                return Err(Error::new(
+                    span,
+                    "input_rate has to be \"vertex\" or \"instance\"",
+                ))
+            }
+        },
+        None => quote!(::vulkan::prelude::vk::VertexInputRate::VERTEX),
+    };
+
+    let mut next_location = 0;
+    let mut descriptions = Vec::new();
+
+    for field in fields.iter() {
+        let field_name = field.ident.as_ref().unwrap();
+
+        let attribute = match string_attribute(&field.attrs, "format")? {
+            Some((format, span)) => VertexFormat {
+                format: Ident::new(&format, span),
+                locations: 1,
+            },
+            None => infer_format(&field.ty)?,
+        };
+
+        if let Some(location) = location_attribute(&field.attrs)? {
+            next_location = location;
+        }
+
+        for row in 0..attribute.locations {
+            let location = next_location + row;
+            let row_offset = row * ROW_SIZE;
+            let format = &attribute.format;
+
+            descriptions.push(quote! {
+                ::vulkan::prelude::vk::VertexInputAttributeDescription {
+                    binding,
+                    location: #location,
+                    format: ::vulkan::prelude::vk::Format::#format,
+                    offset: ::vulkan::memoffset::offset_of!(#name, #field_name) as u32 + #row_offset,
+                }
+            });
+        }
+
+        next_location += attribute.locations;
+    }
+
+    Ok(quote! {
+        impl ::vulkan::VertexInput for #name {
+            const INPUT_RATE: ::vulkan::prelude::vk::VertexInputRate = #input_rate;
+
+            fn attribute_descriptions(
+                binding: u32,
+            ) -> Vec<::vulkan::prelude::vk::VertexInputAttributeDescription> {
+                vec![#(#descriptions),*]
+            }
+        }
+    })
+}
+
+fn string_attribute(attrs: &[Attribute], name: &str) -> Result<Option<(String, Span)>> {
+    let attr = match attrs.iter().find(|attr| attr.path.is_ident(name)) {
+        Some(attr) => attr,
+        None => return Ok(None),
+    };
+
+    if let Meta::NameValue(meta) = attr.parse_meta()? {
+        if let Lit::Str(value) = meta.lit {
+            return Ok(Some((value.value(), value.span())));
+        }
+    }
+    Err(Error::new_spanned(
+        attr,
+        format!("expected #[{} = \"...\"]", name),
+    ))
+}
+
+fn location_attribute(attrs: &[Attribute]) -> Result<Option<u32>> {
+    let attr = match attrs.iter().find(|attr| attr.path.is_ident("location")) {
+        Some(attr) => attr,
+        None => return Ok(None),
+    };
+
+    if let Meta::NameValue(meta) = attr.parse_meta()? {
+        if let Lit::Int(value) = meta.lit {
+            return Ok(Some(value.base10_parse()?));
+        }
+    }
+    Err(Error::new_spanned(attr, "expected #[location = N]"))
+}
+
+//Component type suffix of a scalar: f32 -> SFLOAT, u32 -> UINT, i32 -> SINT
+fn scalar_suffix(ty: &Type) -> Option<&'static str> {
+    if let Type::Path(path) = ty {
+        let segment = path.path.segments.last()?;
+        return match segment.ident.to_string().as_str() {
+            "f32" => Some("SFLOAT"),
+            "u32" => Some("UINT"),
+            "i32" => Some("SINT"),
+            _ => None,
+        };
+    }
+    None
+}
+
+fn array_length(len: &Expr) -> Option<u32> {
+    if let Expr::Lit(lit) = len {
+        if let Lit::Int(value) = &lit.lit {
+            return value.base10_parse().ok();
+        }
+    }
+    None
+}
+
+fn vector_format(components: u32, suffix: &str) -> Option<String> {
+    let channels = ["R32", "G32", "B32", "A32"];
+    if components == 0 || components > 4 {
+        return None;
+    }
+    Some(format!(
+        "{}_{}",
+        channels[..components as usize].concat(),
+        suffix
+    ))
+}
+
+fn infer_format(ty: &Type) -> Result<VertexFormat> {
+    let error = || {
+        Error::new_spanned(
+            ty,
+            "can not infer vertex format for this type, add #[format = \"...\"]",
+        )
+    };
+
+    let (format, locations) = match ty {
+        Type::Array(array) => {
+            let len = array_length(&array.len).ok_or_else(error)?;
+            match &*array.elem {
+                //[[f32; 4]; 4] matrix takes one location per row
+                Type::Array(row) => {
+                    let row_len = array_length(&row.len).ok_or_else(error)?;
+                    if row_len != 4 || scalar_suffix(&row.elem) != Some("SFLOAT") {
+                        return Err(error());
+                    }
+                    (vector_format(4, "SFLOAT"), len)
+                }
+                elem => (vector_format(len, scalar_suffix(elem).ok_or_else(error)?), 1),
+            }
+        }
+        Type::Path(path) => {
+            if let Some(suffix) = scalar_suffix(ty) {
+                (vector_format(1, suffix), 1)
+            } else {
+                let segment = path.path.segments.last().ok_or_else(error)?;
+
+                //cgmath types, component type defaults to f32
+                let suffix = match &segment.arguments {
+                    PathArguments::AngleBracketed(args) => match args.args.first() {
+                        Some(GenericArgument::Type(inner)) => {
+                            scalar_suffix(inner).ok_or_else(error)?
+                        }
+                        _ => return Err(error()),
+                    },
+                    PathArguments::None => "SFLOAT",
+                    _ => return Err(error()),
+                };
+
+                match segment.ident.to_string().as_str() {
+                    "Vector1" => (vector_format(1, suffix), 1),
+                    "Vector2" | "Point2" => (vector_format(2, suffix), 1),
+                    "Vector3" | "Point3" => (vector_format(3, suffix), 1),
+                    "Vector4" => (vector_format(4, suffix), 1),
+                    "Matrix4" if suffix == "SFLOAT" => (vector_format(4, suffix), 4),
+                    _ => return Err(error()),
+                }
+            }
+        }
+        _ => return Err(error()),
+    };
+
+    Ok(VertexFormat {
+        format: Ident::new(&format.ok_or_else(error)?, Span::call_site()),
+        locations,
+    })
+}
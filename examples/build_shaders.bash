@@ -4,6 +4,9 @@
 glslangValidator -V ./src/bin/load_gltf/shaders/model.vert -o ./src/bin/load_gltf/shaders/model.vert.spv
 glslangValidator -V ./src/bin/load_gltf/shaders/model.frag -o ./src/bin/load_gltf/shaders/model.frag.spv
 
+glslangValidator -V ./src/bin/load_gltf/shaders/model_indirect.vert -o ./src/bin/load_gltf/shaders/model_indirect.vert.spv
+glslangValidator -V ./src/bin/load_gltf/shaders/cull.comp -o ./src/bin/load_gltf/shaders/cull.comp.spv
+
 
 # Lights
 glslangValidator -V ./src/bin/lights/shaders/mesh.vert -o ./src/bin/lights/shaders/mesh.vert.spv
@@ -27,3 +30,7 @@ glslangValidator -V ./src/bin/forward_plus/shaders/light_culling.comp -o ./src/b
 
 glslangValidator -V ./src/bin/forward_plus/shaders/forward.frag -o ./src/bin/forward_plus/shaders/forward.frag.spv
 glslangValidator -V ./src/bin/forward_plus/shaders/forward.vert -o ./src/bin/forward_plus/shaders/forward.vert.spv
+
+
+# Tests
+glslangValidator -V ./tests/shaders/multiply.comp -o ./tests/shaders/multiply.comp.spv
@@ -1,17 +1,51 @@
 mod pipelines;
 use vulkan::{
-    prelude::*, utilities::as_byte_slice, utilities::FPSLimiter, Context, PipelineType, Queue,
-    Swapchain, VkThread,
+    prelude::*, utilities::as_byte_slice, Context, FrameTimer, GpuProfiler, PipelineType,
+    QueryPool, Queue, RenderTarget, Swapchain, VkThread,
 };
 
-use examples::utils::{events, gltf_importer};
+use examples::utils::{events, gltf_importer, gltf_importer::Scene, golden};
 
-use pipelines::PushTransform;
+use pipelines::{Deferred, Gbuffer, PushTransform};
 use std::{path::Path, sync::Arc};
 use winit::event::{Event, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
 
 fn main() {
+    if golden::requested() {
+        let (context, draw_instance, target) = golden::setup("deferred_rendering");
+        let profiler = GpuProfiler::new(2, context);
+        let scene = gltf_importer::Importer::load(Path::new("assets/multi_texture.gltf"))
+            .build(&draw_instance);
+        let g_buffer = Gbuffer::build(&scene, &target, &draw_instance);
+        let deferred_pipe = Deferred::build(
+            &g_buffer.get_buffer_images(),
+            &scene,
+            &target,
+            &draw_instance,
+        );
+
+        let pixels = draw_instance.render_offscreen(
+            &target,
+            target.final_layout(),
+            |command_buffer, device| {
+                draw(
+                    command_buffer,
+                    device,
+                    &g_buffer,
+                    &deferred_pipe,
+                    &scene,
+                    0,
+                    target.extent(),
+                    &profiler,
+                    None,
+                )
+            },
+        );
+        golden::finish("deferred_rendering", pixels);
+        return;
+    }
+
     let event_loop = EventLoop::new();
     let window = winit::window::WindowBuilder::new()
         .with_title("test")
@@ -31,8 +65,8 @@ fn main() {
     let mut scene =
         gltf_importer::Importer::load(Path::new("assets/multi_texture.gltf")).build(&draw_instance);
 
-    let mut g_buffer = pipelines::Gbuffer::build(&scene, &swapchain, &draw_instance);
-    let mut deferred_pipe = pipelines::Deferred::build(
+    let mut g_buffer = Gbuffer::build(&scene, &swapchain, &draw_instance);
+    let mut deferred_pipe = Deferred::build(
         &g_buffer.get_buffer_images(),
         &scene,
         &swapchain,
@@ -40,7 +74,25 @@ fn main() {
     );
 
     let command_buffers = draw_instance.create_command_buffers(swapchain.image_views.len());
-    let mut tick_counter = FPSLimiter::new();
+    let mut frame_timer = FrameTimer::new();
+    frame_timer.set_target_fps(examples::target_fps());
+    let log_memory = examples::memory_log_requested();
+    let profiler = GpuProfiler::new(2, vulkan.clone());
+    let log_profile = examples::profile_requested();
+    //Counts the work of both passes to compare against other renderers
+    let statistics = if log_profile && QueryPool::supports_pipeline_statistics(&vulkan) {
+        Some(QueryPool::pipeline_statistics(
+            1,
+            vk::QueryPipelineStatisticFlags::INPUT_ASSEMBLY_VERTICES
+                | vk::QueryPipelineStatisticFlags::INPUT_ASSEMBLY_PRIMITIVES
+                | vk::QueryPipelineStatisticFlags::VERTEX_SHADER_INVOCATIONS
+                | vk::QueryPipelineStatisticFlags::CLIPPING_PRIMITIVES
+                | vk::QueryPipelineStatisticFlags::FRAGMENT_SHADER_INVOCATIONS,
+            vulkan.clone(),
+        ))
+    } else {
+        None
+    };
     let mut events = events::Event::new();
 
     //Event loop
@@ -51,13 +103,15 @@ fn main() {
                 //Drop GLTF file on running window to load new file
                 println!("Loading model at {:?}", path);
                 scene = gltf_importer::Importer::load(&path).build(&draw_instance);
-                g_buffer = pipelines::Gbuffer::build(&scene, &swapchain, &draw_instance);
-                deferred_pipe = pipelines::Deferred::build(
+                g_buffer = Gbuffer::build(&scene, &swapchain, &draw_instance);
+                deferred_pipe = Deferred::build(
                     &g_buffer.get_buffer_images(),
                     &scene,
                     &swapchain,
                     &draw_instance,
                 );
+                //Usage should return to the same level when swapping between the same scenes
+                println!("{}", vulkan.memory_stats());
             }
             _ => {
                 events.handle_event(event);
@@ -74,159 +128,46 @@ fn main() {
         Event::MainEventsCleared => {
             window.request_redraw();
 
-            print!("FPS: {}\r", tick_counter.fps());
-            tick_counter.tick_frame();
+            if log_memory {
+                examples::print_status(&format!(
+                    "{} {}",
+                    frame_timer.stats(),
+                    vulkan.memory_stats()
+                ));
+            } else if log_profile {
+                let work = statistics
+                    .as_ref()
+                    .and_then(|statistics| statistics.statistics())
+                    .map(|statistics| statistics[0].to_string())
+                    .unwrap_or_default();
+                examples::print_status(&format!(
+                    "{} {} {}",
+                    frame_timer.stats(),
+                    profiler.summary(),
+                    work
+                ));
+            } else {
+                examples::print_status(&frame_timer.stats().to_string());
+            }
+            frame_timer.limit();
+            frame_timer.tick();
         }
         Event::RedrawRequested(_window_id) => {
-            let extent = vk::Rect2D {
-                offset: vk::Offset2D { x: 0, y: 0 },
-                extent: swapchain.extent,
-            };
-
-            let viewports = [vk::Viewport {
-                x: 0.0,
-                y: 0.0,
-                width: swapchain.extent.width as f32,
-                height: swapchain.extent.height as f32,
-                min_depth: 0.0,
-                max_depth: 1.0,
-            }];
-
             if let Ok((image_index, _s)) = queue.load_next_frame(&mut swapchain) {
-                let g_pass = vk::RenderPassBeginInfo::builder()
-                    .framebuffer(g_buffer.framebuffers[image_index as usize].buffer())
-                    .render_pass(g_buffer.renderpass.pass())
-                    .render_area(extent)
-                    .clear_values(&[
-                        vk::ClearValue {
-                            color: vk::ClearColorValue {
-                                float32: [0.0, 0.0, 0.0, 0.0],
-                            },
-                        },
-                        vk::ClearValue {
-                            color: vk::ClearColorValue {
-                                float32: [0.0, 0.0, 0.0, 0.0],
-                            },
-                        },
-                        vk::ClearValue {
-                            color: vk::ClearColorValue {
-                                float32: [0.0, 0.0, 0.0, 0.0],
-                            },
-                        },
-                        vk::ClearValue {
-                            // clear value for depth buffer
-                            depth_stencil: vk::ClearDepthStencilValue {
-                                depth: 1.0,
-                                stencil: 0,
-                            },
-                        },
-                    ])
-                    .build();
-
-                let deferred_pass = vk::RenderPassBeginInfo::builder()
-                    .framebuffer(deferred_pipe.framebuffers[image_index as usize].buffer())
-                    .render_pass(deferred_pipe.renderpass.pass())
-                    .render_area(extent)
-                    .clear_values(&[vk::ClearValue {
-                        color: vk::ClearColorValue {
-                            float32: [0.0, 0.0, 0.0, 0.0],
-                        },
-                    }])
-                    .build();
-
                 draw_instance.build_command(
                     command_buffers[image_index as usize],
-                    |command_buffer, device| unsafe {
-                        device.cmd_set_viewport(command_buffer, 0, &viewports);
-
-                        device.cmd_set_scissor(command_buffer, 0, &[extent]);
-
-                        //Build gbuffer data
-                        device.cmd_begin_render_pass(
-                            command_buffer,
-                            &g_pass,
-                            vk::SubpassContents::INLINE,
-                        );
-
-                        device.cmd_bind_pipeline(
+                    |command_buffer, device| {
+                        draw(
                             command_buffer,
-                            vk::PipelineBindPoint::GRAPHICS,
-                            g_buffer.pipeline.default(),
-                        );
-
-                        device.cmd_bind_descriptor_sets(
-                            command_buffer,
-                            vk::PipelineBindPoint::GRAPHICS,
-                            g_buffer.pipeline.layout(0),
-                            0,
-                            &[g_buffer.pipeline_descriptor.set],
-                            &[],
-                        );
-
-                        for node in &scene.nodes {
-                            if let Some(mesh_index) = node.mesh_index {
-                                let mesh = scene.get_mesh(mesh_index);
-
-                                mesh.primitives.iter().for_each(|primitive| {
-                                    device.cmd_bind_vertex_buffers(
-                                        command_buffer,
-                                        0,
-                                        &[scene.vertices.clone().buffer],
-                                        &[primitive.vertex_offset as u64],
-                                    );
-                                    device.cmd_bind_index_buffer(
-                                        command_buffer,
-                                        scene.indices.clone().buffer,
-                                        primitive.indice_offset as u64,
-                                        vk::IndexType::UINT32,
-                                    );
-
-                                    device.cmd_push_constants(
-                                        command_buffer,
-                                        g_buffer.pipeline.layout(0),
-                                        vk::ShaderStageFlags::VERTEX,
-                                        0,
-                                        as_byte_slice(&PushTransform {
-                                            transform: node.transform_matrix,
-                                        }),
-                                    );
-                                    device.cmd_draw_indexed(
-                                        command_buffer,
-                                        primitive.indices_len as u32,
-                                        1,
-                                        0,
-                                        0,
-                                        0,
-                                    );
-                                });
-                            }
-                        }
-
-                        device.cmd_end_render_pass(command_buffer);
-
-                        //Draw final render on a quad
-                        device.cmd_begin_render_pass(
-                            command_buffer,
-                            &deferred_pass,
-                            vk::SubpassContents::INLINE,
-                        );
-
-                        device.cmd_bind_pipeline(
-                            command_buffer,
-                            vk::PipelineBindPoint::GRAPHICS,
-                            deferred_pipe.pipeline.default(),
-                        );
-
-                        device.cmd_bind_descriptor_sets(
-                            command_buffer,
-                            vk::PipelineBindPoint::GRAPHICS,
-                            deferred_pipe.pipeline.layout(0),
-                            0,
-                            &[deferred_pipe.pipeline_descriptor.set],
-                            &[],
-                        );
-                        device.cmd_draw_indexed(command_buffer, 6, 1, 0, 0, 0);
-                        device.cmd_end_render_pass(command_buffer);
+                            device,
+                            &g_buffer,
+                            &deferred_pipe,
+                            &scene,
+                            image_index as usize,
+                            swapchain.extent,
+                            &profiler,
+                            statistics.as_ref(),
+                        )
                     },
                 );
 
@@ -240,7 +181,184 @@ fn main() {
                 vulkan.wait_idle();
             }
         }
-        Event::LoopDestroyed => {}
+        Event::LoopDestroyed => {
+            if log_profile {
+                for (name, stats) in profiler.report() {
+                    println!("{}: {:?}", name, stats);
+                }
+                profiler
+                    .save_chrome_trace("gpu_trace.json")
+                    .expect("Failed to save GPU trace!");
+            }
+        }
         _ => {}
     });
 }
+
+//Records the g-buffer pass and the lighting pass on a quad, shared by the window and --golden
+#[allow(clippy::too_many_arguments)]
+fn draw(
+    command_buffer: vk::CommandBuffer,
+    device: &Device,
+    g_buffer: &Gbuffer,
+    deferred_pipe: &Deferred,
+    scene: &Scene,
+    image_index: usize,
+    extent: vk::Extent2D,
+    profiler: &GpuProfiler,
+    statistics: Option<&QueryPool>,
+) {
+    let render_area = vk::Rect2D {
+        offset: vk::Offset2D { x: 0, y: 0 },
+        extent,
+    };
+
+    let viewports = [vk::Viewport {
+        x: 0.0,
+        y: 0.0,
+        width: extent.width as f32,
+        height: extent.height as f32,
+        min_depth: 0.0,
+        max_depth: 1.0,
+    }];
+
+    let g_pass = vk::RenderPassBeginInfo::builder()
+        .framebuffer(g_buffer.framebuffers.buffer(image_index))
+        .render_pass(g_buffer.renderpass.pass())
+        .render_area(render_area)
+        .clear_values(&[
+            vk::ClearValue {
+                color: vk::ClearColorValue {
+                    float32: [0.0, 0.0, 0.0, 0.0],
+                },
+            },
+            vk::ClearValue {
+                color: vk::ClearColorValue {
+                    float32: [0.0, 0.0, 0.0, 0.0],
+                },
+            },
+            vk::ClearValue {
+                color: vk::ClearColorValue {
+                    float32: [0.0, 0.0, 0.0, 0.0],
+                },
+            },
+            vk::ClearValue {
+                // clear value for depth buffer
+                depth_stencil: vk::ClearDepthStencilValue {
+                    depth: 1.0,
+                    stencil: 0,
+                },
+            },
+        ])
+        .build();
+
+    let deferred_pass = vk::RenderPassBeginInfo::builder()
+        .framebuffer(deferred_pipe.framebuffers.buffer(image_index))
+        .render_pass(deferred_pipe.renderpass.pass())
+        .render_area(render_area)
+        .clear_values(&[vk::ClearValue {
+            color: vk::ClearColorValue {
+                float32: [0.0, 0.0, 0.0, 0.0],
+            },
+        }])
+        .build();
+
+    profiler.begin_frame(command_buffer);
+    if let Some(statistics) = statistics {
+        statistics.begin_frame(command_buffer);
+        statistics.begin(command_buffer, 0);
+    }
+
+    unsafe {
+        device.cmd_set_viewport(command_buffer, 0, &viewports);
+
+        device.cmd_set_scissor(command_buffer, 0, &[render_area]);
+
+        //Build gbuffer data
+        let g_buffer_scope = profiler.begin_scope(command_buffer, "gbuffer");
+        device.cmd_begin_render_pass(command_buffer, &g_pass, vk::SubpassContents::INLINE);
+
+        device.cmd_bind_pipeline(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            g_buffer.pipeline.default(),
+        );
+
+        device.cmd_bind_descriptor_sets(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            g_buffer.pipeline.layout(0),
+            0,
+            &[g_buffer.pipeline_descriptor.set],
+            &[],
+        );
+
+        for node in &scene.nodes {
+            if let Some(mesh_index) = node.mesh_index {
+                let mesh = scene.get_mesh(mesh_index);
+
+                mesh.primitives.iter().for_each(|primitive| {
+                    device.cmd_bind_vertex_buffers(
+                        command_buffer,
+                        0,
+                        &[scene.vertices.buffer],
+                        &[primitive.vertex_offset as u64],
+                    );
+                    device.cmd_bind_index_buffer(
+                        command_buffer,
+                        scene.indices.buffer,
+                        primitive.indice_offset as u64,
+                        vk::IndexType::UINT32,
+                    );
+
+                    device.cmd_push_constants(
+                        command_buffer,
+                        g_buffer.pipeline.layout(0),
+                        vk::ShaderStageFlags::VERTEX,
+                        0,
+                        as_byte_slice(&PushTransform {
+                            transform: node.transform_matrix,
+                        }),
+                    );
+                    device.cmd_draw_indexed(
+                        command_buffer,
+                        primitive.indices_len as u32,
+                        1,
+                        0,
+                        0,
+                        0,
+                    );
+                });
+            }
+        }
+
+        device.cmd_end_render_pass(command_buffer);
+        profiler.end_scope(command_buffer, g_buffer_scope);
+
+        //Draw final render on a quad
+        let lighting_scope = profiler.begin_scope(command_buffer, "lighting");
+        device.cmd_begin_render_pass(command_buffer, &deferred_pass, vk::SubpassContents::INLINE);
+
+        device.cmd_bind_pipeline(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            deferred_pipe.pipeline.default(),
+        );
+
+        device.cmd_bind_descriptor_sets(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            deferred_pipe.pipeline.layout(0),
+            0,
+            &[deferred_pipe.pipeline_descriptor.set],
+            &[],
+        );
+        device.cmd_draw_indexed(command_buffer, 6, 1, 0, 0, 0);
+        device.cmd_end_render_pass(command_buffer);
+        profiler.end_scope(command_buffer, lighting_scope);
+    }
+
+    if let Some(statistics) = statistics {
+        statistics.end(command_buffer, 0);
+    }
+}
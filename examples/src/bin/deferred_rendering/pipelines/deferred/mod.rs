@@ -1,6 +1,6 @@
 use vulkan::{
-    prelude::*, utilities::as_byte_slice, Buffer, Descriptor, DescriptorSet, Framebuffer, Image,
-    Pipeline, Renderpass, Shader, Swapchain, VkThread,
+    prelude::*, utilities::as_byte_slice, Buffer, Descriptor, DescriptorSet, FramebufferAttachment,
+    FramebufferSet, Image, Pipeline, RenderTarget, Renderpass, Shader, VkThread,
 };
 
 use std::{default::Default, ffi::CString, path::Path};
@@ -11,17 +11,17 @@ use std::mem;
 
 pub struct Deferred {
     pub pipeline_descriptor: Descriptor,
-    pub framebuffers: Vec<Framebuffer>,
+    pub framebuffers: FramebufferSet,
     pub pipeline: Pipeline,
     pub renderpass: Renderpass,
-    pub light_buffer: Buffer,
+    pub light_buffer: Buffer<Light>,
 }
 
 impl Deferred {
-    pub fn build(
+    pub fn build<T: RenderTarget>(
         images: &Vec<&Image>,
         scene: &Scene,
-        swapchain: &Swapchain,
+        target: &T,
         vulkan: &VkThread,
     ) -> Self {
         //Light buffer stuff
@@ -90,66 +90,32 @@ impl Deferred {
         let viewports = [vk::Viewport {
             x: 0.0,
             y: 0.0,
-            width: swapchain.width() as f32,
-            height: swapchain.height() as f32,
+            width: target.width() as f32,
+            height: target.height() as f32,
             min_depth: 0.0,
             max_depth: 1.0,
         }];
 
         let scissors = [vk::Rect2D {
             offset: vk::Offset2D { x: 0, y: 0 },
-            extent: swapchain.extent,
+            extent: target.extent(),
         }];
-        let renderpass = Renderpass::new(
-            vk::RenderPassCreateInfo::builder()
-                .subpasses(&[vk::SubpassDescription::builder()
-                    .color_attachments(&[vk::AttachmentReference {
-                        attachment: 0,
-                        layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-                    }])
-                    .build()])
-                .dependencies(&[vk::SubpassDependency {
-                    src_subpass: vk::SUBPASS_EXTERNAL,
-                    dst_subpass: 0,
-                    src_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
-                    dst_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
-                    src_access_mask: vk::AccessFlags::empty(),
-                    dst_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_READ
-                        | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
-                    dependency_flags: vk::DependencyFlags::empty(),
-                }])
-                .attachments(&[vk::AttachmentDescription {
-                    flags: vk::AttachmentDescriptionFlags::empty(),
-                    format: swapchain.format,
-                    samples: vk::SampleCountFlags::TYPE_1,
-                    load_op: vk::AttachmentLoadOp::CLEAR,
-                    store_op: vk::AttachmentStoreOp::STORE,
-                    stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
-                    stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
-                    initial_layout: vk::ImageLayout::UNDEFINED,
-                    final_layout: vk::ImageLayout::PRESENT_SRC_KHR,
-                }])
-                .build(),
-            vulkan.context(),
+        let renderpass = Renderpass::builder()
+            .color_attachment(
+                target.format(),
+                vk::SampleCountFlags::TYPE_1,
+                vk::AttachmentLoadOp::CLEAR,
+                vk::AttachmentStoreOp::STORE,
+                target.final_layout(),
+            )
+            .build(vulkan.context());
+
+        let framebuffers = FramebufferSet::new(
+            target,
+            renderpass.pass(),
+            vec![FramebufferAttachment::Swapchain],
         );
 
-        let framebuffers: Vec<Framebuffer> = swapchain
-            .image_views
-            .iter()
-            .map(|image| {
-                Framebuffer::new(
-                    vk::FramebufferCreateInfo::builder()
-                        .layers(1)
-                        .render_pass(renderpass.pass())
-                        .attachments(&[*image])
-                        .width(swapchain.width())
-                        .height(swapchain.height())
-                        .build(),
-                    vulkan.context(),
-                )
-            })
-            .collect();
-
         //Shader specialization constants
         let specialization_data = SpecializationData {
             materials_amount: scene.materials.len() as u32,
@@ -1,48 +1,15 @@
 use vulkan::{prelude::*, Image, VkThread};
 
 pub fn create_image(format: vk::Format, width: u32, height: u32, vulkan: &VkThread) -> Image {
-    let mut image = Image::create_image(
-        vk::ImageCreateInfo {
-            s_type: vk::StructureType::IMAGE_CREATE_INFO,
-            image_type: vk::ImageType::TYPE_2D,
-            format,
-            extent: vk::Extent3D {
-                width,
-                height,
-                depth: 1,
-            },
-            mip_levels: 1,
-            array_layers: 1,
-            samples: vk::SampleCountFlags::TYPE_1,
-            tiling: vk::ImageTiling::OPTIMAL,
-            usage: vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::SAMPLED,
-            sharing_mode: vk::SharingMode::EXCLUSIVE,
-            ..Default::default()
-        },
-        vk_mem::MemoryUsage::GpuOnly,
+    let mut image = Image::attachment(
+        width,
+        height,
+        format,
+        vk::SampleCountFlags::TYPE_1,
+        vk::ImageUsageFlags::SAMPLED,
         vulkan.context(),
     );
 
-    image.attach_view(vk::ImageViewCreateInfo {
-        view_type: vk::ImageViewType::TYPE_2D,
-        format,
-        components: vk::ComponentMapping {
-            r: vk::ComponentSwizzle::IDENTITY,
-            g: vk::ComponentSwizzle::IDENTITY,
-            b: vk::ComponentSwizzle::IDENTITY,
-            a: vk::ComponentSwizzle::IDENTITY,
-        },
-        subresource_range: vk::ImageSubresourceRange {
-            aspect_mask: vk::ImageAspectFlags::COLOR,
-            base_mip_level: 0,
-            level_count: 1,
-            base_array_layer: 0,
-            layer_count: 1,
-        },
-        image: image.image(),
-        ..Default::default()
-    });
-
     image.attach_sampler(vk::SamplerCreateInfo {
         s_type: vk::StructureType::SAMPLER_CREATE_INFO,
         mag_filter: vk::Filter::LINEAR,
@@ -58,27 +25,10 @@ pub fn create_image(format: vk::Format, width: u32, height: u32, vulkan: &VkThre
         ..Default::default()
     });
 
-    vulkan.apply_pipeline_barrier(
-        vk::PipelineStageFlags::BOTTOM_OF_PIPE,
-        vk::PipelineStageFlags::FRAGMENT_SHADER,
-        vk::ImageMemoryBarrier {
-            s_type: vk::StructureType::IMAGE_MEMORY_BARRIER,
-            src_access_mask: vk::AccessFlags::empty(),
-            dst_access_mask: vk::AccessFlags::SHADER_READ,
-            old_layout: vk::ImageLayout::UNDEFINED,
-            new_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
-            src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
-            dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
-            image: image.image(),
-            subresource_range: vk::ImageSubresourceRange {
-                aspect_mask: vk::ImageAspectFlags::COLOR,
-                base_mip_level: 0,
-                level_count: 1,
-                base_array_layer: 0,
-                layer_count: 1,
-            },
-            ..Default::default()
-        },
+    image.transition(
+        vk::ImageLayout::UNDEFINED,
+        vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        vulkan,
     );
 
     image
@@ -2,11 +2,10 @@ mod helpers;
 
 use std::{default::Default, ffi::CString, mem, path::Path};
 use vulkan::{
-    offset_of,
     prelude::*,
     utilities::{as_byte_slice, Shader},
-    Buffer, Descriptor, DescriptorSet, Framebuffer, Image, Pipeline, Renderpass, Swapchain,
-    VkThread,
+    Buffer, Descriptor, DescriptorSet, FramebufferAttachment, FramebufferSet, Image, Pipeline,
+    RenderTarget, Renderpass, VertexInput, VkThread,
 };
 
 use super::definitions::{PushTransform, SpecializationData};
@@ -26,17 +25,17 @@ pub struct Gbuffer {
     pub normal: Image,
 
     pub uniform_buffer: Buffer,
-    pub material_buffer: Buffer,
+    pub material_buffer: Buffer<MaterialRaw>,
 
     pub camera: Camera,
     pub renderpass: Renderpass,
-    pub framebuffers: Vec<Framebuffer>,
+    pub framebuffers: FramebufferSet,
     pub pipeline: Pipeline,
 }
 
 impl Gbuffer {
     //Creates a new g_buffer
-    pub fn build(scene: &Scene, swapchain: &Swapchain, vulkan: &VkThread) -> Gbuffer {
+    pub fn build<T: RenderTarget>(scene: &Scene, target: &T, vulkan: &VkThread) -> Gbuffer {
         let context = vulkan.context();
         //Create buffer data
         let camera = Camera::new(cgmath::Point3::new(0.0, 0.0, 0.0), 15.0, 1.3);
@@ -80,14 +79,15 @@ impl Gbuffer {
         //Create empty placeholder texture for shader
         let context = vulkan.context();
 
-        let (width, height) = (swapchain.extent.width, swapchain.extent.height);
+        let (width, height) = (target.width(), target.height());
 
         //Create the textures that G_Buffer saves to
         let color = helpers::create_image(vk::Format::R8G8B8A8_UNORM, width, height, &vulkan);
         let normal = helpers::create_image(vk::Format::R16G16B16A16_SFLOAT, width, height, &vulkan);
         let position =
             helpers::create_image(vk::Format::R16G16B16A16_SFLOAT, width, height, &vulkan);
-        let depth = examples::create_depth_resources(&swapchain, context.clone());
+        let depth =
+            examples::create_depth_resources(target, vk::SampleCountFlags::TYPE_1, context.clone());
 
         //Empty texture, so shader wont crash when there is 0 materials
         let empty_image = examples::create_empty_image(&vulkan);
@@ -258,61 +258,18 @@ impl Gbuffer {
         );
 
         //FRAMEBUFFERS
-        let framebuffers: Vec<Framebuffer> = swapchain
-            .image_views
-            .iter()
-            .map(|_image| {
-                Framebuffer::new(
-                    vk::FramebufferCreateInfo::builder()
-                        .layers(1)
-                        .render_pass(renderpass.pass())
-                        .attachments(&[color.view(), normal.view(), position.view(), depth.view()])
-                        .width(swapchain.width())
-                        .height(swapchain.height())
-                        .build(),
-                    vulkan.context(),
-                )
-            })
-            .collect();
-
-        let attributes = [
-            vk::VertexInputAttributeDescription {
-                binding: 0,
-                location: 0,
-                format: vk::Format::R32G32B32_SFLOAT,
-                offset: offset_of!(Vertex, position) as u32,
-            },
-            vk::VertexInputAttributeDescription {
-                binding: 0,
-                location: 1,
-                format: vk::Format::R32G32B32A32_SFLOAT,
-                offset: offset_of!(Vertex, color) as u32,
-            },
-            vk::VertexInputAttributeDescription {
-                binding: 0,
-                location: 2,
-                format: vk::Format::R32G32B32A32_SFLOAT,
-                offset: offset_of!(Vertex, tangents) as u32,
-            },
-            vk::VertexInputAttributeDescription {
-                binding: 0,
-                location: 3,
-                format: vk::Format::R32G32B32_SFLOAT,
-                offset: offset_of!(Vertex, normal) as u32,
-            },
-            vk::VertexInputAttributeDescription {
-                binding: 0,
-                location: 4,
-                format: vk::Format::R32G32_SFLOAT,
-                offset: offset_of!(Vertex, uv) as u32,
-            },
-            vk::VertexInputAttributeDescription {
-                binding: 0,
-                location: 5,
-                format: vk::Format::R32_SINT,
-                offset: offset_of!(Vertex, material_id) as u32,
-            },
-        ];
+        let framebuffers = FramebufferSet::new(
+            target,
+            renderpass.pass(),
+            vec![
+                FramebufferAttachment::View(color.view()),
+                FramebufferAttachment::View(normal.view()),
+                FramebufferAttachment::View(position.view()),
+                FramebufferAttachment::View(depth.view()),
+            ],
+        );
+
+        let attributes = Vertex::attribute_descriptions(0);
 
         let viewports = [vk::Viewport {
             x: 0.0,
@@ -325,7 +282,7 @@ impl Gbuffer {
 
         let scissors = [vk::Rect2D {
             offset: vk::Offset2D { x: 0, y: 0 },
-            extent: swapchain.extent,
+            extent: target.extent(),
         }];
 
         let shader_name = CString::new("main").unwrap();
@@ -362,11 +319,7 @@ impl Gbuffer {
                 ])
                 .vertex_input_state(
                     &vk::PipelineVertexInputStateCreateInfo::builder()
-                        .vertex_binding_descriptions(&[vk::VertexInputBindingDescription {
-                            binding: 0,
-                            stride: mem::size_of::<Vertex>() as u32,
-                            input_rate: vk::VertexInputRate::VERTEX,
-                        }])
+                        .vertex_binding_descriptions(&[Vertex::binding_description(0)])
                         .vertex_attribute_descriptions(&attributes)
                         .build(),
                 )
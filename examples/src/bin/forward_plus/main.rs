@@ -1,7 +1,7 @@
 mod pipelines;
 use vulkan::{
-    prelude::*, utilities::as_byte_slice, utilities::FPSLimiter, Context, PipelineType, Queue,
-    Swapchain, VkThread,
+    prelude::*, utilities::as_byte_slice, Context, FrameTimer, PipelineType, Queue, Swapchain,
+    VkThread,
 };
 
 use examples::utils::{events, gltf_importer};
@@ -30,7 +30,9 @@ fn main() {
         gltf_importer::Importer::load(Path::new("assets/multi_texture.gltf")).build(&draw_instance);
 
     let command_buffers = draw_instance.create_command_buffers(swapchain.image_views.len());
-    let mut tick_counter = FPSLimiter::new();
+    let mut frame_timer = FrameTimer::new();
+    frame_timer.set_target_fps(examples::target_fps());
+    let log_memory = examples::memory_log_requested();
     let mut events = events::Event::new();
 
     //Event loop
@@ -41,6 +43,8 @@ fn main() {
                 //Drop GLTF file on running window to load new file
                 println!("Loading model at {:?}", path);
                 scene = gltf_importer::Importer::load(&path).build(&draw_instance);
+                //Usage should return to the same level when swapping between the same scenes
+                println!("{}", vulkan.memory_stats());
             }
             _ => {
                 events.handle_event(event);
@@ -54,8 +58,17 @@ fn main() {
         Event::MainEventsCleared => {
             window.request_redraw();
 
-            print!("FPS: {}\r", tick_counter.fps());
-            tick_counter.tick_frame();
+            if log_memory {
+                examples::print_status(&format!(
+                    "{} {}",
+                    frame_timer.stats(),
+                    vulkan.memory_stats()
+                ));
+            } else {
+                examples::print_status(&frame_timer.stats().to_string());
+            }
+            frame_timer.limit();
+            frame_timer.tick();
         }
         Event::RedrawRequested(_window_id) => {
             let extent = vk::Rect2D {
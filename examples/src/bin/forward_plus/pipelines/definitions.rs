@@ -1,5 +1,5 @@
 use std::mem;
-use vulkan::{offset_of, prelude::*, utilities::as_byte_slice};
+use vulkan::{offset_of, prelude::*, utilities::as_byte_slice, ShaderLayout};
 
 #[allow(dead_code)]
 pub struct ForwardConstants {
@@ -103,7 +103,8 @@ pub struct ComputePushConstant {
 pub const MAX_POINT_LIGHT_PER_TILE: u32 = 1023;
 
 #[repr(C)]
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, ShaderLayout)]
+#[layout = "std430"]
 pub struct LightVisibility {
     pub count: u32,
     pub indicies: [u32; MAX_POINT_LIGHT_PER_TILE as usize],
@@ -1,6 +1,6 @@
 use vulkan::{
     offset_of, prelude::*, utilities::as_byte_slice, Buffer, Descriptor, DescriptorSet,
-    Framebuffer, Image, Pipeline, Renderpass, Shader, Swapchain, VkThread,
+    Framebuffer, Image, Pipeline, Renderpass, Shader, Swapchain, VertexInput, VkThread,
 };
 
 use std::{default::Default, ffi::CString, path::Path};
@@ -17,8 +17,8 @@ use std::mem;
 
 pub struct Pipes {
     pub camera: Camera,
-    pub camera_buffer: Buffer,
-    pub materials_buffer: Buffer,
+    pub camera_buffer: Buffer<CameraRaw>,
+    pub materials_buffer: Buffer<MaterialRaw>,
 
     //Depth texture
     pub depth_image: Image,
@@ -29,8 +29,8 @@ pub struct Pipes {
     pub forward_descriptor: Descriptor,
     pub forward_pass: Renderpass,
 
-    pub light_buffer: Buffer,
-    pub material_buffer: Buffer,
+    pub light_buffer: Buffer<Light>,
+    pub material_buffer: Buffer<MaterialRaw>,
 
     pub pipelines: Pipeline,
 
@@ -38,7 +38,7 @@ pub struct Pipes {
 }
 
 pub fn new(scene: &Scene, swapchain: &Swapchain, vulkan: &VkThread) {
-    let depth_image = examples::create_depth_resources(&swapchain, vulkan.context());
+    let depth_image = examples::create_depth_resources(&swapchain, vk::SampleCountFlags::TYPE_1, vulkan.context());
     let empty_image = examples::create_empty_image(&vulkan);
 
     //Pipeline stuff
@@ -69,8 +69,8 @@ pub fn new(scene: &Scene, swapchain: &Swapchain, vulkan: &VkThread) {
     let row_count: u32 = (swapchain.width() - 1) / TILE_SIZE + 1;
     let column_count: u32 = (swapchain.height() - 1) / TILE_SIZE + 1;
 
-    let culling_data = Buffer::new_mapped_basic(
-        (row_count * column_count * mem::size_of::<LightVisibility>() as u32 + 1u32) as u64,
+    let culling_data = Buffer::<LightVisibility>::with_len(
+        (row_count * column_count) as usize,
         vk::BufferUsageFlags::STORAGE_BUFFER,
         vk_mem::MemoryUsage::CpuOnly,
         vulkan.context(),
@@ -79,15 +79,13 @@ pub fn new(scene: &Scene, swapchain: &Swapchain, vulkan: &VkThread) {
     //Create camera buffer
     let camera = Camera::new(cgmath::Point3::new(0.0, 0.0, 0.0), 15.0, 1.3);
 
-    let camera_buffer = Buffer::new_mapped_basic(
-        mem::size_of::<CameraRaw>() as u64,
+    let camera_buffer = Buffer::from_data(
+        &[camera.raw()],
         vk::BufferUsageFlags::UNIFORM_BUFFER,
         vk_mem::MemoryUsage::CpuOnly,
         vulkan.context(),
     );
 
-    camera_buffer.upload_to_buffer(&[camera.raw()], 0);
-
     //Create light buffers
     let light_buffer = vulkan.create_gpu_buffer(
         vk::BufferUsageFlags::UNIFORM_BUFFER,
@@ -174,44 +172,7 @@ pub fn new(scene: &Scene, swapchain: &Swapchain, vulkan: &VkThread) {
     //Attributes
 
     //Forward renderer
-    let forward_attributes = [
-        vk::VertexInputAttributeDescription {
-            binding: 0,
-            location: 0,
-            format: vk::Format::R32G32B32_SFLOAT,
-            offset: offset_of!(Vertex, position) as u32,
-        },
-        vk::VertexInputAttributeDescription {
-            binding: 0,
-            location: 1,
-            format: vk::Format::R32G32B32A32_SFLOAT,
-            offset: offset_of!(Vertex, color) as u32,
-        },
-        vk::VertexInputAttributeDescription {
-            binding: 0,
-            location: 2,
-            format: vk::Format::R32G32B32A32_SFLOAT,
-            offset: offset_of!(Vertex, tangents) as u32,
-        },
-        vk::VertexInputAttributeDescription {
-            binding: 0,
-            location: 3,
-            format: vk::Format::R32G32B32_SFLOAT,
-            offset: offset_of!(Vertex, normal) as u32,
-        },
-        vk::VertexInputAttributeDescription {
-            binding: 0,
-            location: 4,
-            format: vk::Format::R32G32_SFLOAT,
-            offset: offset_of!(Vertex, uv) as u32,
-        },
-        vk::VertexInputAttributeDescription {
-            binding: 0,
-            location: 5,
-            format: vk::Format::R32_SINT,
-            offset: offset_of!(Vertex, material_id) as u32,
-        },
-    ];
+    let forward_attributes = Vertex::attribute_descriptions(0);
 
     //Depth attributes
     let depth_attributes = [vk::VertexInputAttributeDescription {
@@ -568,11 +529,7 @@ pub fn new(scene: &Scene, swapchain: &Swapchain, vulkan: &VkThread) {
     pipeline_description.p_stages = forward_shader.as_ptr();
     pipeline_description.stage_count = forward_shader.len() as u32;
     pipeline_description.p_vertex_input_state = &vk::PipelineVertexInputStateCreateInfo::builder()
-        .vertex_binding_descriptions(&[vk::VertexInputBindingDescription {
-            binding: 0,
-            stride: mem::size_of::<Vertex>() as u32,
-            input_rate: vk::VertexInputRate::VERTEX,
-        }])
+        .vertex_binding_descriptions(&[Vertex::binding_description(0)])
         .vertex_attribute_descriptions(&forward_attributes)
         .build();
     pipeline_description.layout = pipelines.layout(1);
@@ -1,26 +1,26 @@
 mod pipelines;
 
 use vulkan::{
-    prelude::*,
-    utilities::{as_byte_slice, FPSLimiter},
-    Context, Framebuffer, PipelineType, Queue, Swapchain, VkThread,
+    prelude::*, utilities::as_byte_slice, Buffer, Context, FrameTimer, Framebuffer, FramebufferSet,
+    GpuProfiler, PipelineType, Queue, RenderTarget, Swapchain, VkThread,
 };
 
-use examples::utils::{events, Batch, Camera, Mesh};
+use examples::utils::{events, golden, Batch, Camera, Mesh};
 use pipelines::{mesh_pipeline, Light, PushConstantModel, Vertex};
 use std::path::Path;
 use std::sync::Arc;
-use winit::event::{Event, WindowEvent};
+use winit::event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
 
-fn main() {
-    let event_loop = EventLoop::new();
-    let window = winit::window::WindowBuilder::new()
-        .with_title("test")
-        .with_inner_size(winit::dpi::LogicalSize::new(800.0, 600.0))
-        .build(&event_loop)
-        .expect("Failed to create window.");
+//Vertex and index buffers of the scene and the ball marking the light
+struct Models {
+    scene_vertex_buffer: Buffer<Vertex>,
+    scene_index_buffer: Buffer<u32>,
+    ball_vertex_buffer: Buffer<Vertex>,
+    ball_index_buffer: Buffer<u32>,
+}
 
+fn main() {
     let mut camera = Camera::new(cgmath::Point3::new(0.0, 0.0, 0.0), 15.0, 1.3);
 
     let light = Light::new(
@@ -29,62 +29,6 @@ fn main() {
         [0.5, 0.5, 0.5, 1.0],
     );
 
-    let vulkan = Arc::new(Context::new(&window, "lights", true));
-    let instance = VkThread::new(PipelineType::Draw, vulkan.clone());
-
-    let mut queue = Queue::new(vulkan.clone());
-
-    let mut swapchain = Swapchain::new(vulkan.clone());
-    let command_buffers = instance.create_command_buffers(swapchain.image_views.len());
-
-    let mut pipeline = mesh_pipeline::Pipeline::new(&swapchain, &instance, camera, light);
-
-    let mut framebuffers: Vec<Framebuffer> = swapchain
-        .image_views
-        .iter()
-        .map(|image| {
-            Framebuffer::new(
-                vk::FramebufferCreateInfo::builder()
-                    .layers(1)
-                    .render_pass(pipeline.renderpass)
-                    .attachments(&[*image, pipeline.depth_image.view()])
-                    .width(swapchain.width())
-                    .height(swapchain.height())
-                    .build(),
-                vulkan.clone(),
-            )
-        })
-        .collect();
-
-    let mut shadow_framebuffer: Framebuffer = Framebuffer::new(
-        vk::FramebufferCreateInfo::builder()
-            .layers(1)
-            .render_pass(pipeline.shadow_pipeline.renderpass)
-            .attachments(&[pipeline.shadow_pipeline.image.view()])
-            .width(swapchain.width())
-            .height(swapchain.height())
-            .build(),
-        vulkan.clone(),
-    );
-
-    let scene_batch = load_model(Path::new("assets/lights.obj"));
-
-    let scene_index_buffer =
-        instance.create_gpu_buffer(vk::BufferUsageFlags::INDEX_BUFFER, &scene_batch.indices);
-    let scene_vertex_buffer =
-        instance.create_gpu_buffer(vk::BufferUsageFlags::VERTEX_BUFFER, &scene_batch.vertices);
-
-    let ball_batch = load_model(Path::new("assets/ball.obj"));
-
-    let ball_index_buffer =
-        instance.create_gpu_buffer(vk::BufferUsageFlags::INDEX_BUFFER, &ball_batch.indices);
-
-    let ball_vertex_buffer =
-        instance.create_gpu_buffer(vk::BufferUsageFlags::VERTEX_BUFFER, &ball_batch.vertices);
-
-    let mut tick_counter = FPSLimiter::new();
-    let mut events = events::Event::new();
-
     let mut scene_data = PushConstantModel::new(
         cgmath::Decomposed {
             scale: 1.0,
@@ -103,265 +47,445 @@ fn main() {
         [1.0, 1.0, 1.0],
     );
 
+    if golden::requested() {
+        let (context, instance, target) = golden::setup("lights");
+        let profiler = GpuProfiler::new(2, context);
+        let pipeline = mesh_pipeline::Pipeline::new(
+            &target,
+            &instance,
+            camera,
+            light,
+            vk::SampleCountFlags::TYPE_1,
+        );
+        let framebuffers = FramebufferSet::new(
+            &target,
+            pipeline.renderpass.pass(),
+            pipeline.framebuffer_attachments(),
+        );
+        let shadow_framebuffer = create_shadow_framebuffer(&target, &pipeline);
+        let models = load_models(&instance);
+
+        rotate_scene(&mut scene_data, golden::TIME);
+
+        let pixels =
+            instance.render_offscreen(&target, target.final_layout(), |command_buffer, device| {
+                draw(
+                    command_buffer,
+                    device,
+                    &pipeline,
+                    framebuffers.buffer(0),
+                    &shadow_framebuffer,
+                    target.extent(),
+                    &models,
+                    &scene_data,
+                    &ball_data,
+                    &profiler,
+                )
+            });
+        golden::finish("lights", pixels);
+        return;
+    }
+
+    let event_loop = EventLoop::new();
+    let window = winit::window::WindowBuilder::new()
+        .with_title("test")
+        .with_inner_size(winit::dpi::LogicalSize::new(800.0, 600.0))
+        .build(&event_loop)
+        .expect("Failed to create window.");
+
+    let vulkan = Arc::new(Context::new(&window, "lights", true));
+    let instance = VkThread::new(PipelineType::Draw, vulkan.clone());
+
+    let mut queue = Queue::new(vulkan.clone());
+
+    let mut swapchain = Swapchain::new(vulkan.clone());
+    let command_buffers = instance.create_command_buffers(swapchain.image_views.len());
+
+    //Press F12 to save the next frame as screenshot.png
+    let mut take_screenshot = false;
+
+    //Press M to cycle between MSAA sample counts
+    let mut samples = vk::SampleCountFlags::TYPE_1;
+    let max_samples = vulkan.get_max_usable_sample_count();
+
+    let mut pipeline = mesh_pipeline::Pipeline::new(&swapchain, &instance, camera, light, samples);
+
+    let mut framebuffers = FramebufferSet::new(
+        &swapchain,
+        pipeline.renderpass.pass(),
+        pipeline.framebuffer_attachments(),
+    );
+
+    let mut shadow_framebuffer = create_shadow_framebuffer(&swapchain, &pipeline);
+
+    let models = load_models(&instance);
+
+    let mut frame_timer = FrameTimer::new();
+    frame_timer.set_target_fps(examples::target_fps());
+    let mut events = events::Event::new();
+
+    let profiler = GpuProfiler::new(2, vulkan.clone());
+    let log_profile = examples::profile_requested();
+
     event_loop.run(move |event, _, control_flow| match event {
         Event::WindowEvent { event, .. } => match event {
             WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
             WindowEvent::Resized(_) => {}
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        virtual_keycode: Some(VirtualKeyCode::F12),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => take_screenshot = true,
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        virtual_keycode: Some(VirtualKeyCode::M),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                samples = examples::next_sample_count(samples, max_samples);
+                println!("MSAA samples: {:?}", samples);
+
+                vulkan.wait_idle();
+                pipeline =
+                    mesh_pipeline::Pipeline::new(&swapchain, &instance, camera, light, samples);
+                framebuffers.rebuild(
+                    &swapchain,
+                    pipeline.renderpass.pass(),
+                    pipeline.framebuffer_attachments(),
+                );
+                shadow_framebuffer = create_shadow_framebuffer(&swapchain, &pipeline);
+            }
             _ => {
                 events.handle_event(event);
                 if events.event_happened {
                     //Camera updates
                     camera.handle_events(&events);
-                    pipeline.uniform_buffer.upload_to_buffer(&[camera.raw()], 0);
+                    pipeline.uniform_buffer.update(&[camera.raw()]);
                     events.clear();
                 }
             }
         },
         Event::MainEventsCleared => {
             window.request_redraw();
-            print!("FPS: {}\r", tick_counter.fps());
-            tick_counter.tick_frame();
+            if log_profile {
+                examples::print_status(&format!("{} {}", frame_timer.stats(), profiler.summary()));
+            } else {
+                examples::print_status(&frame_timer.stats().to_string());
+            }
+            frame_timer.limit();
+            frame_timer.tick();
         }
         Event::RedrawRequested(_window_id) => {
-            let delta_time = tick_counter.delta_time();
-            // rotate scene
-
-            let extent = vk::Rect2D {
-                offset: vk::Offset2D { x: 0, y: 0 },
-                extent: swapchain.extent,
-            };
-
-            let viewports = [vk::Viewport {
-                x: 0.0,
-                y: 0.0,
-                width: swapchain.extent.width as f32,
-                height: swapchain.extent.height as f32,
-                min_depth: 0.0,
-                max_depth: 1.0,
-            }];
-
-            use cgmath::Zero;
-            let transform: cgmath::Decomposed<cgmath::Vector3<f32>, cgmath::Basis3<f32>> =
-                cgmath::Decomposed {
-                    scale: 1.0,
-                    rot: cgmath::Rotation3::from_angle_y(cgmath::Deg(90.0) * delta_time),
-                    disp: cgmath::Vector3::zero(),
-                };
-
-            scene_data.update_transform(transform);
+            //Rotates at the same speed at any frame rate
+            for _ in 0..frame_timer.fixed_steps() {
+                rotate_scene(&mut scene_data, frame_timer.fixed_step().as_secs_f32());
+            }
 
             if let Ok((image_index, _s)) = queue.load_next_frame(&mut swapchain) {
-                let scene_pass = vk::RenderPassBeginInfo::builder()
-                    .framebuffer(framebuffers[image_index as usize].buffer())
-                    .render_pass(pipeline.renderpass)
-                    .render_area(extent)
-                    .clear_values(&[
-                        vk::ClearValue {
-                            // clear value for color buffer
-                            color: vk::ClearColorValue {
-                                float32: [0.0, 0.0, 0.0, 1.0],
-                            },
-                        },
-                        vk::ClearValue {
-                            // clear value for depth buffer
-                            depth_stencil: vk::ClearDepthStencilValue {
-                                depth: 1.0,
-                                stencil: 0,
-                            },
-                        },
-                    ])
-                    .build();
-
-                let shadow_pass_info = vk::RenderPassBeginInfo::builder()
-                    .framebuffer(shadow_framebuffer.buffer())
-                    .render_pass(pipeline.shadow_pipeline.renderpass)
-                    .render_area(extent)
-                    .clear_values(&[vk::ClearValue {
-                        // clear value for depth buffer
-                        depth_stencil: vk::ClearDepthStencilValue {
-                            depth: 1.0,
-                            stencil: 0,
-                        },
-                    }])
-                    .build();
-
                 instance.build_command(
                     command_buffers[image_index as usize],
-                    |command_buffer, device| unsafe {
-                        device.cmd_set_viewport(command_buffer, 0, &viewports);
-                        device.cmd_set_scissor(command_buffer, 0, &[extent]);
-                        //Depth buffer
-                        device.cmd_push_constants(
-                            command_buffer,
-                            pipeline.layout,
-                            vk::ShaderStageFlags::VERTEX,
-                            0,
-                            as_byte_slice(&scene_data),
-                        );
-
-                        device.cmd_begin_render_pass(
-                            command_buffer,
-                            &shadow_pass_info,
-                            vk::SubpassContents::INLINE,
-                        );
-
-                        device.cmd_bind_pipeline(
-                            command_buffer,
-                            vk::PipelineBindPoint::GRAPHICS,
-                            pipeline.shadow_pipeline.pipeline,
-                        );
-                        device.cmd_bind_descriptor_sets(
-                            command_buffer,
-                            vk::PipelineBindPoint::GRAPHICS,
-                            pipeline.shadow_pipeline.layout,
-                            0,
-                            &[pipeline.shadow_pipeline.descriptor.set],
-                            &[],
-                        );
-
-                        device.cmd_bind_vertex_buffers(
-                            command_buffer,
-                            0,
-                            &[scene_vertex_buffer.buffer],
-                            &[0],
-                        );
-                        device.cmd_bind_index_buffer(
-                            command_buffer,
-                            scene_index_buffer.buffer,
-                            0,
-                            vk::IndexType::UINT32,
-                        );
-                        device.cmd_draw_indexed(
-                            command_buffer,
-                            scene_batch.indices.len() as u32,
-                            1,
-                            0,
-                            0,
-                            1,
-                        );
-
-                        device.cmd_end_render_pass(command_buffer);
-
-                        //Scene
-                        device.cmd_begin_render_pass(
-                            command_buffer,
-                            &scene_pass,
-                            vk::SubpassContents::INLINE,
-                        );
-                        device.cmd_bind_pipeline(
-                            command_buffer,
-                            vk::PipelineBindPoint::GRAPHICS,
-                            pipeline.pipeline,
-                        );
-                        device.cmd_bind_descriptor_sets(
-                            command_buffer,
-                            vk::PipelineBindPoint::GRAPHICS,
-                            pipeline.layout,
-                            0,
-                            &[pipeline.pipeline_descriptor.set],
-                            &[],
-                        );
-
-                        device.cmd_bind_vertex_buffers(
-                            command_buffer,
-                            0,
-                            &[scene_vertex_buffer.buffer],
-                            &[0],
-                        );
-                        device.cmd_bind_index_buffer(
-                            command_buffer,
-                            scene_index_buffer.buffer,
-                            0,
-                            vk::IndexType::UINT32,
-                        );
-                        device.cmd_draw_indexed(
-                            command_buffer,
-                            scene_batch.indices.len() as u32,
-                            1,
-                            0,
-                            0,
-                            1,
-                        );
-
-                        //Ball
-                        device.cmd_push_constants(
-                            command_buffer,
-                            pipeline.layout,
-                            vk::ShaderStageFlags::VERTEX,
-                            0,
-                            as_byte_slice(&ball_data),
-                        );
-                        device.cmd_bind_vertex_buffers(
-                            command_buffer,
-                            0,
-                            &[ball_vertex_buffer.buffer],
-                            &[0],
-                        );
-                        device.cmd_bind_index_buffer(
-                            command_buffer,
-                            ball_index_buffer.buffer,
-                            0,
-                            vk::IndexType::UINT32,
-                        );
-                        device.cmd_draw_indexed(
+                    |command_buffer, device| {
+                        draw(
                             command_buffer,
-                            ball_batch.indices.len() as u32,
-                            1,
-                            0,
-                            0,
-                            1,
-                        );
-
-                        device.cmd_end_render_pass(command_buffer);
+                            device,
+                            &pipeline,
+                            framebuffers.buffer(image_index as usize),
+                            &shadow_framebuffer,
+                            swapchain.extent,
+                            &models,
+                            &scene_data,
+                            &ball_data,
+                            &profiler,
+                        )
                     },
                 );
 
-                queue.render_frame(
-                    &mut swapchain,
-                    command_buffers[image_index as usize],
-                    image_index,
-                );
+                queue.submit_frame(command_buffers[image_index as usize]);
+                if take_screenshot {
+                    queue.wait_queue_idle();
+                    swapchain
+                        .capture(image_index as usize, &instance, "screenshot.png")
+                        .expect("Failed to save screenshot!");
+                    println!("Saved screenshot.png");
+                    take_screenshot = false;
+                }
+                queue.present_frame(&swapchain, image_index);
             } else {
                 println!("Failed to draw frame");
                 //Resize
                 vulkan.wait_idle();
                 swapchain = Swapchain::new(vulkan.clone());
 
-                pipeline = mesh_pipeline::Pipeline::new(&swapchain, &instance, camera, light);
-                framebuffers = swapchain
-                    .image_views
-                    .iter()
-                    .map(|image| {
-                        Framebuffer::new(
-                            vk::FramebufferCreateInfo::builder()
-                                .layers(1)
-                                .render_pass(pipeline.renderpass)
-                                .attachments(&[*image, pipeline.depth_image.view()])
-                                .width(swapchain.width())
-                                .height(swapchain.height())
-                                .build(),
-                            vulkan.clone(),
-                        )
-                    })
-                    .collect();
-
-                shadow_framebuffer = Framebuffer::new(
-                    vk::FramebufferCreateInfo::builder()
-                        .layers(1)
-                        .render_pass(pipeline.shadow_pipeline.renderpass)
-                        .attachments(&[pipeline.shadow_pipeline.image.view()])
-                        .width(swapchain.width())
-                        .height(swapchain.height())
-                        .build(),
-                    vulkan.clone(),
+                pipeline =
+                    mesh_pipeline::Pipeline::new(&swapchain, &instance, camera, light, samples);
+                framebuffers.rebuild(
+                    &swapchain,
+                    pipeline.renderpass.pass(),
+                    pipeline.framebuffer_attachments(),
                 );
+
+                shadow_framebuffer = create_shadow_framebuffer(&swapchain, &pipeline);
+            }
+        }
+        Event::LoopDestroyed => {
+            if log_profile {
+                for (name, stats) in profiler.report() {
+                    println!("{}: {:?}", name, stats);
+                }
+                profiler
+                    .save_chrome_trace("gpu_trace.json")
+                    .expect("Failed to save GPU trace!");
             }
         }
-        Event::LoopDestroyed => {}
         _ => {}
     });
 }
 
+//Rotates the scene around the y axis, 90 degrees per second
+fn rotate_scene(scene_data: &mut PushConstantModel, delta_time: f32) {
+    use cgmath::Zero;
+    let transform: cgmath::Decomposed<cgmath::Vector3<f32>, cgmath::Basis3<f32>> =
+        cgmath::Decomposed {
+            scale: 1.0,
+            rot: cgmath::Rotation3::from_angle_y(cgmath::Deg(90.0) * delta_time),
+            disp: cgmath::Vector3::zero(),
+        };
+
+    scene_data.update_transform(transform);
+}
+
+fn create_shadow_framebuffer<T: RenderTarget>(
+    target: &T,
+    pipeline: &mesh_pipeline::Pipeline,
+) -> Framebuffer {
+    Framebuffer::new(
+        vk::FramebufferCreateInfo::builder()
+            .layers(1)
+            .render_pass(pipeline.shadow_pipeline.renderpass)
+            .attachments(&[pipeline.shadow_pipeline.image.view()])
+            .width(target.width())
+            .height(target.height())
+            .build(),
+        target.context(),
+    )
+}
+
+//Records the shadow pass and the scene pass, shared by the window and --golden
+#[allow(clippy::too_many_arguments)]
+fn draw(
+    command_buffer: vk::CommandBuffer,
+    device: &Device,
+    pipeline: &mesh_pipeline::Pipeline,
+    framebuffer: vk::Framebuffer,
+    shadow_framebuffer: &Framebuffer,
+    extent: vk::Extent2D,
+    models: &Models,
+    scene_data: &PushConstantModel,
+    ball_data: &PushConstantModel,
+    profiler: &GpuProfiler,
+) {
+    let render_area = vk::Rect2D {
+        offset: vk::Offset2D { x: 0, y: 0 },
+        extent,
+    };
+
+    let viewports = [vk::Viewport {
+        x: 0.0,
+        y: 0.0,
+        width: extent.width as f32,
+        height: extent.height as f32,
+        min_depth: 0.0,
+        max_depth: 1.0,
+    }];
+
+    let scene_pass = vk::RenderPassBeginInfo::builder()
+        .framebuffer(framebuffer)
+        .render_pass(pipeline.renderpass.pass())
+        .render_area(render_area)
+        .clear_values(&[
+            vk::ClearValue {
+                // clear value for color buffer
+                color: vk::ClearColorValue {
+                    float32: [0.0, 0.0, 0.0, 1.0],
+                },
+            },
+            vk::ClearValue {
+                // clear value for depth buffer
+                depth_stencil: vk::ClearDepthStencilValue {
+                    depth: 1.0,
+                    stencil: 0,
+                },
+            },
+        ])
+        .build();
+
+    let shadow_pass_info = vk::RenderPassBeginInfo::builder()
+        .framebuffer(shadow_framebuffer.buffer())
+        .render_pass(pipeline.shadow_pipeline.renderpass)
+        .render_area(render_area)
+        .clear_values(&[vk::ClearValue {
+            // clear value for depth buffer
+            depth_stencil: vk::ClearDepthStencilValue {
+                depth: 1.0,
+                stencil: 0,
+            },
+        }])
+        .build();
+
+    profiler.begin_frame(command_buffer);
+
+    unsafe {
+        device.cmd_set_viewport(command_buffer, 0, &viewports);
+        device.cmd_set_scissor(command_buffer, 0, &[render_area]);
+        //Depth buffer
+        device.cmd_push_constants(
+            command_buffer,
+            pipeline.layout,
+            vk::ShaderStageFlags::VERTEX,
+            0,
+            as_byte_slice(scene_data),
+        );
+
+        let shadow_scope = profiler.begin_scope(command_buffer, "shadow");
+        device.cmd_begin_render_pass(
+            command_buffer,
+            &shadow_pass_info,
+            vk::SubpassContents::INLINE,
+        );
+
+        device.cmd_bind_pipeline(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            pipeline.shadow_pipeline.pipeline,
+        );
+        device.cmd_bind_descriptor_sets(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            pipeline.shadow_pipeline.layout,
+            0,
+            &[pipeline.shadow_pipeline.descriptor.set],
+            &[],
+        );
+
+        device.cmd_bind_vertex_buffers(
+            command_buffer,
+            0,
+            &[models.scene_vertex_buffer.buffer],
+            &[0],
+        );
+        device.cmd_bind_index_buffer(
+            command_buffer,
+            models.scene_index_buffer.buffer,
+            0,
+            vk::IndexType::UINT32,
+        );
+        device.cmd_draw_indexed(
+            command_buffer,
+            models.scene_index_buffer.len() as u32,
+            1,
+            0,
+            0,
+            1,
+        );
+
+        device.cmd_end_render_pass(command_buffer);
+        profiler.end_scope(command_buffer, shadow_scope);
+
+        //Scene
+        let scene_scope = profiler.begin_scope(command_buffer, "scene");
+        device.cmd_begin_render_pass(command_buffer, &scene_pass, vk::SubpassContents::INLINE);
+        device.cmd_bind_pipeline(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            pipeline.pipeline,
+        );
+        device.cmd_bind_descriptor_sets(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            pipeline.layout,
+            0,
+            &[pipeline.pipeline_descriptor.set],
+            &[],
+        );
+
+        device.cmd_bind_vertex_buffers(
+            command_buffer,
+            0,
+            &[models.scene_vertex_buffer.buffer],
+            &[0],
+        );
+        device.cmd_bind_index_buffer(
+            command_buffer,
+            models.scene_index_buffer.buffer,
+            0,
+            vk::IndexType::UINT32,
+        );
+        device.cmd_draw_indexed(
+            command_buffer,
+            models.scene_index_buffer.len() as u32,
+            1,
+            0,
+            0,
+            1,
+        );
+
+        //Ball
+        device.cmd_push_constants(
+            command_buffer,
+            pipeline.layout,
+            vk::ShaderStageFlags::VERTEX,
+            0,
+            as_byte_slice(ball_data),
+        );
+        device.cmd_bind_vertex_buffers(
+            command_buffer,
+            0,
+            &[models.ball_vertex_buffer.buffer],
+            &[0],
+        );
+        device.cmd_bind_index_buffer(
+            command_buffer,
+            models.ball_index_buffer.buffer,
+            0,
+            vk::IndexType::UINT32,
+        );
+        device.cmd_draw_indexed(
+            command_buffer,
+            models.ball_index_buffer.len() as u32,
+            1,
+            0,
+            0,
+            1,
+        );
+
+        device.cmd_end_render_pass(command_buffer);
+        profiler.end_scope(command_buffer, scene_scope);
+    }
+}
+
+fn load_models(vulkan: &VkThread) -> Models {
+    let scene_batch = load_model(Path::new("assets/lights.obj"));
+    let ball_batch = load_model(Path::new("assets/ball.obj"));
+
+    Models {
+        scene_vertex_buffer: vulkan
+            .create_gpu_buffer(vk::BufferUsageFlags::VERTEX_BUFFER, &scene_batch.vertices),
+        scene_index_buffer: vulkan
+            .create_gpu_buffer(vk::BufferUsageFlags::INDEX_BUFFER, &scene_batch.indices),
+        ball_vertex_buffer: vulkan
+            .create_gpu_buffer(vk::BufferUsageFlags::VERTEX_BUFFER, &ball_batch.vertices),
+        ball_index_buffer: vulkan
+            .create_gpu_buffer(vk::BufferUsageFlags::INDEX_BUFFER, &ball_batch.indices),
+    }
+}
+
 fn load_model(model_path: &Path) -> Batch<Vertex> {
     let model_obj = tobj::load_obj(model_path).expect("Failed to load model object!");
 
@@ -1,6 +1,6 @@
 use vulkan::{
-    Swapchain, offset_of, prelude::*, Buffer, Context, Descriptor,
-    DescriptorSet, Image, Shader, VkThread,
+    prelude::*, Context, Descriptor, DescriptorSet, FramebufferAttachment, Image, RenderTarget,
+    Renderpass, Shader, UniformBuffer, VertexInput, VkThread,
 };
 
 use super::{Light, PushConstantModel, Vertex, shadowmap_pipeline};
@@ -14,12 +14,14 @@ pub struct Pipeline {
     pub layout: vk::PipelineLayout,
 
     pub depth_image: Image,
-    pub uniform_buffer: Buffer,
+    pub color_image: Option<Image>,
+    pub samples: vk::SampleCountFlags,
+    pub uniform_buffer: UniformBuffer<CameraRaw>,
     pub uniform_transform: Camera,
 
-    pub light_buffer: Buffer,
+    pub light_buffer: UniformBuffer<Light>,
     pub light: Light,
-    pub renderpass: vk::RenderPass,
+    pub renderpass: Renderpass,
 
     pub pipeline_descriptor: Descriptor,
 
@@ -28,31 +30,29 @@ pub struct Pipeline {
 
 impl Pipeline {
     //Creates a new pipeline
-    pub fn new(
-        swapchain: &Swapchain,
+    pub fn new<T: RenderTarget>(
+        target: &T,
         vulkan: &VkThread,
         camera: Camera,
         light_data: Light,
+        samples: vk::SampleCountFlags,
     ) -> Pipeline {
         //Create buffer data
-        let depth_image = examples::create_depth_resources(&swapchain, vulkan.context());
+        let depth_image = examples::create_depth_resources(target, samples, vulkan.context());
 
-        let uniform_buffer = Buffer::new_mapped_basic(
-            mem::size_of::<CameraRaw>() as u64,
-            vk::BufferUsageFlags::UNIFORM_BUFFER,
-            vk_mem::MemoryUsage::CpuOnly,
-            vulkan.context(),
-        );
-
-        let light_buffer = Buffer::new_mapped_basic(
-            mem::size_of::<Light>() as u64,
-            vk::BufferUsageFlags::UNIFORM_BUFFER,
-            vk_mem::MemoryUsage::CpuOnly,
-            vulkan.context(),
-        );
+        //Multisampled color target, resolved into the render target image
+        let color_image = if samples != vk::SampleCountFlags::TYPE_1 {
+            Some(examples::create_color_resources(
+                target,
+                samples,
+                vulkan.context(),
+            ))
+        } else {
+            None
+        };
 
-        uniform_buffer.upload_to_buffer(&[camera.raw()], 0);
-        light_buffer.upload_to_buffer(&[light_data], 0);
+        let uniform_buffer = UniformBuffer::new(&[camera.raw()], vulkan.context());
+        let light_buffer = UniformBuffer::new(&[light_data], vulkan.context());
 
         let push_constant_range = vk::PushConstantRange::builder()
             .stage_flags(vk::ShaderStageFlags::VERTEX)
@@ -60,13 +60,9 @@ impl Pipeline {
             .build();
 
         let shadow_pipeline = shadowmap_pipeline::Pipeline::new(
-            &swapchain,
+            target,
             &vulkan,
-            vk::DescriptorBufferInfo {
-                buffer: light_buffer.buffer,
-                offset: 0,
-                range: light_buffer.size,
-            },
+            light_buffer.descriptor_info(),
             push_constant_range,
         );
 
@@ -76,22 +72,14 @@ impl Pipeline {
                     bind_index: 0,
                     flag: vk::ShaderStageFlags::VERTEX,
                     bind_type: vk::DescriptorType::UNIFORM_BUFFER,
-                    buffer_info: Some(vec![vk::DescriptorBufferInfo {
-                        buffer: uniform_buffer.buffer,
-                        offset: 0,
-                        range: uniform_buffer.size,
-                    }]),
+                    buffer_info: Some(vec![uniform_buffer.descriptor_info()]),
                     ..Default::default()
                 },
                 DescriptorSet {
                     bind_index: 1,
                     flag: vk::ShaderStageFlags::ALL_GRAPHICS,
                     bind_type: vk::DescriptorType::UNIFORM_BUFFER,
-                    buffer_info: Some(vec![vk::DescriptorBufferInfo {
-                        buffer: light_buffer.buffer,
-                        offset: 0,
-                        range: light_buffer.size,
-                    }]),
+                    buffer_info: Some(vec![light_buffer.descriptor_info()]),
                     ..Default::default()
                 },
                 DescriptorSet {
@@ -132,7 +120,7 @@ impl Pipeline {
         };
 
         let shader_name = CString::new("main").unwrap();
-        let renderpass = create_render_pass(&swapchain, &vulkan);
+        let renderpass = create_render_pass(target, &vulkan, samples);
         let pipeline = unsafe {
             vulkan
                 .device()
@@ -157,25 +145,8 @@ impl Pipeline {
                         ])
                         .vertex_input_state(
                             &vk::PipelineVertexInputStateCreateInfo::builder()
-                                .vertex_binding_descriptions(&[vk::VertexInputBindingDescription {
-                                    binding: 0,
-                                    stride: mem::size_of::<Vertex>() as u32,
-                                    input_rate: vk::VertexInputRate::VERTEX,
-                                }])
-                                .vertex_attribute_descriptions(&[
-                                    vk::VertexInputAttributeDescription {
-                                        binding: 0,
-                                        location: 0,
-                                        format: vk::Format::R32G32B32_SFLOAT,
-                                        offset: offset_of!(Vertex, pos) as u32,
-                                    },
-                                    vk::VertexInputAttributeDescription {
-                                        binding: 0,
-                                        location: 1,
-                                        format: vk::Format::R32G32_SFLOAT,
-                                        offset: offset_of!(Vertex, normal) as u32,
-                                    },
-                                ])
+                                .vertex_binding_descriptions(&[Vertex::binding_description(0)])
+                                .vertex_attribute_descriptions(&Vertex::attribute_descriptions(0))
                                 .build(),
                         )
                         .input_assembly_state(&vk::PipelineInputAssemblyStateCreateInfo {
@@ -186,13 +157,13 @@ impl Pipeline {
                             &vk::PipelineViewportStateCreateInfo::builder()
                                 .scissors(&[vk::Rect2D {
                                     offset: vk::Offset2D { x: 0, y: 0 },
-                                    extent: swapchain.extent,
+                                    extent: target.extent(),
                                 }])
                                 .viewports(&[vk::Viewport {
                                     x: 0.0,
                                     y: 0.0,
-                                    width: swapchain.extent.width as f32,
-                                    height: swapchain.extent.height as f32,
+                                    width: target.width() as f32,
+                                    height: target.height() as f32,
                                     min_depth: 0.0,
                                     max_depth: 1.0,
                                 }]),
@@ -204,7 +175,7 @@ impl Pipeline {
                             ..Default::default()
                         })
                         .multisample_state(&vk::PipelineMultisampleStateCreateInfo {
-                            rasterization_samples: vk::SampleCountFlags::TYPE_1,
+                            rasterization_samples: samples,
                             ..Default::default()
                         })
                         .depth_stencil_state(&vk::PipelineDepthStencilStateCreateInfo {
@@ -237,7 +208,7 @@ impl Pipeline {
                             ]),
                         )
                         .layout(pipeline_layout)
-                        .render_pass(renderpass)
+                        .render_pass(renderpass.pass())
                         .build()],
                     None,
                 )
@@ -250,6 +221,8 @@ impl Pipeline {
 
             layout: pipeline_layout,
             depth_image,
+            color_image,
+            samples,
 
             uniform_buffer,
             light_buffer,
@@ -264,6 +237,23 @@ impl Pipeline {
     }
 }
 
+impl Pipeline {
+    //Framebuffer attachments matching the renderpass attachment order
+    pub fn framebuffer_attachments(&self) -> Vec<FramebufferAttachment> {
+        match &self.color_image {
+            Some(color_image) => vec![
+                FramebufferAttachment::View(color_image.view()),
+                FramebufferAttachment::View(self.depth_image.view()),
+                FramebufferAttachment::Swapchain,
+            ],
+            None => vec![
+                FramebufferAttachment::Swapchain,
+                FramebufferAttachment::View(self.depth_image.view()),
+            ],
+        }
+    }
+}
+
 impl Drop for Pipeline {
     fn drop(&mut self) {
         unsafe {
@@ -272,15 +262,15 @@ impl Drop for Pipeline {
             self.context
                 .device
                 .destroy_pipeline_layout(self.layout, None);
-
-            self.context
-                .device
-                .destroy_render_pass(self.renderpass, None);
         }
     }
 }
 
-pub fn create_render_pass(swapchain: &Swapchain, vulkan: &VkThread) -> vk::RenderPass {
+pub fn create_render_pass<T: RenderTarget>(
+    target: &T,
+    vulkan: &VkThread,
+    samples: vk::SampleCountFlags,
+) -> Renderpass {
     let depth_format = vulkan.context.find_depth_format(
         &[
             vk::Format::D32_SFLOAT,
@@ -291,59 +281,40 @@ pub fn create_render_pass(swapchain: &Swapchain, vulkan: &VkThread) -> vk::Rende
         vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT,
     );
 
-    let subpasses = vk::SubpassDescription::builder()
-        .color_attachments(&[vk::AttachmentReference {
-            attachment: 0,
-            layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-        }])
-        .depth_stencil_attachment(&vk::AttachmentReference {
-            attachment: 1,
-            layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
-        })
-        .build();
-
-    let renderpass_create_info = vk::RenderPassCreateInfo::builder()
-        .subpasses(&[subpasses])
-        .dependencies(&[vk::SubpassDependency {
-            src_subpass: vk::SUBPASS_EXTERNAL,
-            dst_subpass: 0,
-            src_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
-            dst_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
-            src_access_mask: vk::AccessFlags::empty(),
-            dst_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_READ
-                | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
-            dependency_flags: vk::DependencyFlags::empty(),
-        }])
-        .attachments(&[
-            vk::AttachmentDescription {
-                flags: vk::AttachmentDescriptionFlags::empty(),
-                format: swapchain.format,
-                samples: vk::SampleCountFlags::TYPE_1,
-                load_op: vk::AttachmentLoadOp::CLEAR,
-                store_op: vk::AttachmentStoreOp::STORE,
-                stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
-                stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
-                initial_layout: vk::ImageLayout::UNDEFINED,
-                final_layout: vk::ImageLayout::PRESENT_SRC_KHR,
-            },
-            vk::AttachmentDescription {
-                flags: vk::AttachmentDescriptionFlags::empty(),
-                format: depth_format,
-                samples: vk::SampleCountFlags::TYPE_1,
-                load_op: vk::AttachmentLoadOp::CLEAR,
-                store_op: vk::AttachmentStoreOp::DONT_CARE,
-                stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
-                stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
-                initial_layout: vk::ImageLayout::UNDEFINED,
-                final_layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
-            },
-        ])
-        .build();
-
-    unsafe {
-        vulkan
-            .device()
-            .create_render_pass(&renderpass_create_info, None)
-            .expect("Failed to create render pass!")
+    if samples == vk::SampleCountFlags::TYPE_1 {
+        return Renderpass::builder()
+            .color_attachment(
+                target.format(),
+                samples,
+                vk::AttachmentLoadOp::CLEAR,
+                vk::AttachmentStoreOp::STORE,
+                target.final_layout(),
+            )
+            .depth_attachment(
+                depth_format,
+                samples,
+                vk::AttachmentLoadOp::CLEAR,
+                vk::AttachmentStoreOp::DONT_CARE,
+                vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+            )
+            .build(vulkan.context());
     }
+
+    Renderpass::builder()
+        .color_attachment(
+            target.format(),
+            samples,
+            vk::AttachmentLoadOp::CLEAR,
+            vk::AttachmentStoreOp::DONT_CARE,
+            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+        )
+        .depth_attachment(
+            depth_format,
+            samples,
+            vk::AttachmentLoadOp::CLEAR,
+            vk::AttachmentStoreOp::DONT_CARE,
+            vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+        )
+        .resolve_attachment(target.format(), target.final_layout())
+        .build(vulkan.context())
 }
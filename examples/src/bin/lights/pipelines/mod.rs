@@ -2,15 +2,18 @@ pub mod mesh_pipeline;
 pub mod shadowmap_pipeline;
 
 use cgmath::{Deg, Matrix4, Point3, Vector3};
+use vulkan::{ShaderLayout, VertexInput};
 
-#[derive(Clone, Debug, Copy)]
+#[repr(C)]
+#[derive(Clone, Debug, Copy, VertexInput)]
 pub struct Vertex {
     pub pos: [f32; 3],
     pub normal: [f32; 3],
 }
 
 #[repr(C)]
-#[derive(Clone, Debug, Copy)]
+#[derive(Clone, Debug, Copy, ShaderLayout)]
+#[layout = "std140"]
 pub struct Light {
     pub position: cgmath::Vector4<f32>,
     pub projection: cgmath::Matrix4<f32>,
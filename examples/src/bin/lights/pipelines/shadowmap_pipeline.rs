@@ -1,8 +1,8 @@
 use super::Vertex;
 use std::{default::Default, ffi::CString, mem, path::Path, sync::Arc};
 use vulkan::{
-    offset_of, prelude::*, utilities::Shader, Context, Descriptor, DescriptorSet, Image, Swapchain,
-    VkThread,
+    offset_of, prelude::*, utilities::Shader, Context, Descriptor, DescriptorSet, Image,
+    RenderTarget, VkThread,
 };
 
 pub struct Pipeline {
@@ -15,8 +15,8 @@ pub struct Pipeline {
 }
 
 impl Pipeline {
-    pub fn new(
-        swapchain: &Swapchain,
+    pub fn new<T: RenderTarget>(
+        target: &T,
         vulkan: &VkThread,
         lights: vk::DescriptorBufferInfo,
         pushconstant: vk::PushConstantRange,
@@ -34,15 +34,15 @@ impl Pipeline {
         let viewports = vk::Viewport {
             x: 0.0,
             y: 0.0,
-            width: swapchain.extent.width as f32,
-            height: swapchain.extent.height as f32,
+            width: target.width() as f32,
+            height: target.height() as f32,
             min_depth: 0.0,
             max_depth: 1.0,
         };
 
         let scissors = vk::Rect2D {
             offset: vk::Offset2D { x: 0, y: 0 },
-            extent: swapchain.extent,
+            extent: target.extent(),
         };
         let depth_format = context.find_depth_format(
             &[
@@ -167,48 +167,15 @@ impl Pipeline {
                 .expect("Unable to create graphics pipeline")
         }[0];
 
-        let mut shadow_map_image = Image::create_image(
-            vk::ImageCreateInfo {
-                s_type: vk::StructureType::IMAGE_CREATE_INFO,
-                image_type: vk::ImageType::TYPE_2D,
-                format: depth_format,
-                extent: vk::Extent3D {
-                    width: swapchain.extent.width,
-                    height: swapchain.extent.height,
-                    depth: 1,
-                },
-                mip_levels: 1,
-                array_layers: 1,
-                samples: vk::SampleCountFlags::TYPE_1,
-                tiling: vk::ImageTiling::OPTIMAL,
-                usage: vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT | vk::ImageUsageFlags::SAMPLED,
-                sharing_mode: vk::SharingMode::EXCLUSIVE,
-                ..Default::default()
-            },
-            vk_mem::MemoryUsage::GpuOnly,
+        let mut shadow_map_image = Image::attachment(
+            target.width(),
+            target.height(),
+            depth_format,
+            vk::SampleCountFlags::TYPE_1,
+            vk::ImageUsageFlags::SAMPLED,
             context.clone(),
         );
 
-        shadow_map_image.attach_view(vk::ImageViewCreateInfo {
-            view_type: vk::ImageViewType::TYPE_2D,
-            format: depth_format,
-            components: vk::ComponentMapping {
-                r: vk::ComponentSwizzle::IDENTITY,
-                g: vk::ComponentSwizzle::IDENTITY,
-                b: vk::ComponentSwizzle::IDENTITY,
-                a: vk::ComponentSwizzle::IDENTITY,
-            },
-            subresource_range: vk::ImageSubresourceRange {
-                aspect_mask: vk::ImageAspectFlags::DEPTH,
-                base_mip_level: 0,
-                level_count: 1,
-                base_array_layer: 0,
-                layer_count: 1,
-            },
-            image: shadow_map_image.image(),
-            ..Default::default()
-        });
-
         shadow_map_image.attach_sampler(vk::SamplerCreateInfo {
             s_type: vk::StructureType::SAMPLER_CREATE_INFO,
             mag_filter: vk::Filter::LINEAR,
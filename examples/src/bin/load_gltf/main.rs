@@ -1,16 +1,49 @@
 mod pipelines;
 use vulkan::{
-    prelude::*, utilities::as_byte_slice, utilities::FPSLimiter, Context, Queue, Swapchain, PipelineType, 
-    VkThread,
+    prelude::*, utilities::as_byte_slice, Context, FrameCommands, FrameTimer, IndirectCommands,
+    ParallelRecorder, PipelineType, Queue, RecordMode, RenderPassInheritance, RenderTarget,
+    Swapchain, VkThread,
 };
 
-use examples::utils::{events, gltf_importer};
+use examples::utils::{
+    events, gltf_importer,
+    gltf_importer::{Node, Primitive, Scene},
+    golden,
+};
 use pipelines::{definitions::PushTransform, mesh_pipeline};
 use std::{path::Path, sync::Arc};
-use winit::event::{Event, WindowEvent};
+use winit::event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
 
 fn main() {
+    if golden::requested() {
+        let (_context, instance, target) = golden::setup("load_gltf");
+        let scene =
+            gltf_importer::Importer::load(Path::new("assets/multi_texture.gltf")).build(&instance);
+        let mesh_pipeline = mesh_pipeline::Pipeline::build_for(
+            &scene,
+            &target,
+            &instance,
+            vk::SampleCountFlags::TYPE_1,
+            false,
+        );
+
+        let pixels =
+            instance.render_offscreen(&target, target.final_layout(), |command_buffer, device| {
+                draw(
+                    command_buffer,
+                    device,
+                    &mesh_pipeline,
+                    &scene,
+                    0,
+                    target.extent(),
+                    None,
+                )
+            });
+        golden::finish("load_gltf", pixels);
+        return;
+    }
+
     let event_loop = EventLoop::new();
     let window = winit::window::WindowBuilder::new()
         .with_title("test")
@@ -26,11 +59,45 @@ fn main() {
     let mut scene = gltf_importer::Importer::load(Path::new("../../GLTF_tests/multi_texture.gltf"))
         .build(&instance);
 
-    let mut mesh_pipeline = mesh_pipeline::Pipeline::build_for(&scene, &swapchain, &instance);
+    //Press F12 to save the next frame as screenshot.png
+    let mut take_screenshot = false;
+
+    //Press M to cycle between MSAA sample counts
+    let mut samples = vk::SampleCountFlags::TYPE_1;
+    let max_samples = vulkan.get_max_usable_sample_count();
+
+    //Start with --gpu-culling to frustum cull in a compute shader and draw indirectly
+    let gpu_culling = examples::gpu_culling_requested() && IndirectCommands::is_supported(&vulkan);
+    if examples::gpu_culling_requested() && !gpu_culling {
+        println!("drawIndirectFirstInstance is not supported, culling on the CPU");
+    }
+
+    let mut mesh_pipeline =
+        mesh_pipeline::Pipeline::build_for(&scene, &swapchain, &instance, samples, gpu_culling);
+
+    //The culling push constants change with the camera, everything else reads
+    //the camera from the uniform buffer and is only recorded again when dirty
+    let record_mode = if gpu_culling {
+        RecordMode::EveryFrame
+    } else {
+        RecordMode::WhenDirty
+    };
+    let mut commands = FrameCommands::new(&instance, swapchain.image_views.len(), record_mode);
 
-    let command_buffers = instance.create_command_buffers(swapchain.image_views.len());
+    //Start with --parallel to record the primitives on every core with secondary command buffers
+    let mut recorder = if examples::parallel_recording_requested() && !gpu_culling {
+        Some(ParallelRecorder::new(
+            ParallelRecorder::available_threads(),
+            swapchain.image_views.len(),
+            vulkan.clone(),
+        ))
+    } else {
+        None
+    };
 
-    let mut tick_counter = FPSLimiter::new();
+    let mut frame_timer = FrameTimer::new();
+    frame_timer.set_target_fps(examples::target_fps());
+    let log_memory = examples::memory_log_requested();
     let mut events = events::Event::new();
     event_loop.run(move |event, _, control_flow| match event {
         Event::WindowEvent { event, .. } => match event {
@@ -39,7 +106,47 @@ fn main() {
                 //Drop GLTF file on running window to load new file
                 println!("Loading model at {:?}", path);
                 scene = gltf_importer::Importer::load(&path).build(&instance);
-                mesh_pipeline = mesh_pipeline::Pipeline::build_for(&scene, &swapchain, &instance);
+                mesh_pipeline = mesh_pipeline::Pipeline::build_for(
+                    &scene,
+                    &swapchain,
+                    &instance,
+                    samples,
+                    gpu_culling,
+                );
+                commands.mark_dirty();
+                //Usage should return to the same level when swapping between the same scenes
+                println!("{}", vulkan.memory_stats());
+            }
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        virtual_keycode: Some(VirtualKeyCode::F12),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => take_screenshot = true,
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        virtual_keycode: Some(VirtualKeyCode::M),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                samples = examples::next_sample_count(samples, max_samples);
+                println!("MSAA samples: {:?}", samples);
+
+                vulkan.wait_idle();
+                mesh_pipeline = mesh_pipeline::Pipeline::build_for(
+                    &scene,
+                    &swapchain,
+                    &instance,
+                    samples,
+                    gpu_culling,
+                );
+                commands.mark_dirty();
             }
             _ => {
                 events.handle_event(event);
@@ -48,7 +155,7 @@ fn main() {
                     mesh_pipeline.camera.handle_events(&events);
                     mesh_pipeline
                         .uniform_buffer
-                        .upload_to_buffer(&[mesh_pipeline.camera.raw()], 0);
+                        .update(&[mesh_pipeline.camera.raw()]);
                     events.clear();
                 }
             }
@@ -56,127 +163,250 @@ fn main() {
         Event::MainEventsCleared => {
             window.request_redraw();
 
-            print!("FPS: {}\r", tick_counter.fps());
-            tick_counter.tick_frame();
+            if log_memory {
+                examples::print_status(&format!(
+                    "{} {}",
+                    frame_timer.stats(),
+                    vulkan.memory_stats()
+                ));
+            } else {
+                examples::print_status(&frame_timer.stats().to_string());
+            }
+            frame_timer.limit();
+            frame_timer.tick();
         }
         Event::RedrawRequested(_window_id) => {
-            let extent = vk::Rect2D {
-                offset: vk::Offset2D { x: 0, y: 0 },
-                extent: swapchain.extent,
-            };
-
-            let viewports = [vk::Viewport {
-                x: 0.0,
-                y: 0.0,
-                width: swapchain.extent.width as f32,
-                height: swapchain.extent.height as f32,
-                min_depth: 0.0,
-                max_depth: 1.0,
-            }];
-
             if let Ok((image_index, _s)) = queue.load_next_frame(&mut swapchain) {
-                let scene_pass = vk::RenderPassBeginInfo::builder()
-                    .framebuffer(mesh_pipeline.framebuffers[image_index as usize].buffer())
-                    .render_pass(mesh_pipeline.renderpass)
-                    .render_area(extent)
-                    .clear_values(&[
-                        vk::ClearValue {
-                            // clear value for color buffer
-                            color: vk::ClearColorValue {
-                                float32: [0.0, 0.0, 0.0, 1.0],
-                            },
-                        },
-                        vk::ClearValue {
-                            // clear value for depth buffer
-                            depth_stencil: vk::ClearDepthStencilValue {
-                                depth: 1.0,
-                                stencil: 0,
-                            },
-                        },
-                    ])
-                    .build();
-
-                instance.build_command(
-                    command_buffers[image_index as usize],
-                    |command_buffer, device| unsafe {
-                        device.cmd_set_viewport(command_buffer, 0, &viewports);
-                        device.cmd_set_scissor(command_buffer, 0, &[extent]);
-                        device.cmd_begin_render_pass(
-                            command_buffer,
-                            &scene_pass,
-                            vk::SubpassContents::INLINE,
-                        );
-                        device.cmd_bind_pipeline(
-                            command_buffer,
-                            vk::PipelineBindPoint::GRAPHICS,
-                            mesh_pipeline.pipeline,
-                        );
-
-                        device.cmd_bind_descriptor_sets(
+                let command_buffer = commands.command_buffer(
+                    &instance,
+                    image_index as usize,
+                    |command_buffer, device| {
+                        draw(
                             command_buffer,
-                            vk::PipelineBindPoint::GRAPHICS,
-                            mesh_pipeline.layout,
-                            0,
-                            &[mesh_pipeline.pipeline_descriptor.set],
-                            &[],
-                        );
-
-                        for node in &scene.nodes {
-                            if let Some(mesh_index) = node.mesh_index {
-                                let mesh = scene.get_mesh(mesh_index);
-
-                                mesh.primitives.iter().for_each(|primitive| {
-                                    device.cmd_bind_vertex_buffers(
-                                        command_buffer,
-                                        0,
-                                        &[scene.vertices.clone().buffer],
-                                        &[primitive.vertex_offset as u64],
-                                    );
-                                    device.cmd_bind_index_buffer(
-                                        command_buffer,
-                                        scene.indices.clone().buffer,
-                                        primitive.indice_offset as u64,
-                                        vk::IndexType::UINT32,
-                                    );
-
-                                    device.cmd_push_constants(
-                                        command_buffer,
-                                        mesh_pipeline.layout,
-                                        vk::ShaderStageFlags::VERTEX,
-                                        0,
-                                        as_byte_slice(&PushTransform {
-                                            transform: node.transform_matrix,
-                                        }),
-                                    );
-                                    device.cmd_draw_indexed(
-                                        command_buffer,
-                                        primitive.indices_len as u32,
-                                        1,
-                                        0,
-                                        0,
-                                        0,
-                                    );
-                                });
-                            }
-                        }
-
-                        device.cmd_end_render_pass(command_buffer);
+                            device,
+                            &mesh_pipeline,
+                            &scene,
+                            image_index as usize,
+                            swapchain.extent,
+                            recorder.as_ref(),
+                        )
                     },
                 );
 
-                queue.render_frame(
-                    &mut swapchain,
-                    command_buffers[image_index as usize],
-                    image_index,
-                );
+                queue.submit_frame(command_buffer);
+                if take_screenshot {
+                    queue.wait_queue_idle();
+                    swapchain
+                        .capture(image_index as usize, &instance, "screenshot.png")
+                        .expect("Failed to save screenshot!");
+                    println!("Saved screenshot.png");
+                    take_screenshot = false;
+                }
+                queue.present_frame(&swapchain, image_index);
             } else {
                 //Resize window
                 vulkan.wait_idle();
                 swapchain = Swapchain::new(vulkan.clone());
-                mesh_pipeline = mesh_pipeline::Pipeline::build_for(&scene, &swapchain, &instance);
+                mesh_pipeline = mesh_pipeline::Pipeline::build_for(
+                    &scene,
+                    &swapchain,
+                    &instance,
+                    samples,
+                    gpu_culling,
+                );
+                if let Some(recorder) = &mut recorder {
+                    recorder.resize(swapchain.image_views.len());
+                }
+                commands.mark_dirty();
             }
         }
         Event::LoopDestroyed => {}
         _ => {}
     });
 }
+
+//Records every mesh node of the scene, shared by the window and --golden.
+//With GPU culling the visible primitives are drawn with one indirect draw,
+//with a recorder the primitives are split across its threads.
+fn draw(
+    command_buffer: vk::CommandBuffer,
+    device: &Device,
+    mesh_pipeline: &mesh_pipeline::Pipeline,
+    scene: &Scene,
+    image_index: usize,
+    extent: vk::Extent2D,
+    recorder: Option<&ParallelRecorder>,
+) {
+    let render_area = vk::Rect2D {
+        offset: vk::Offset2D { x: 0, y: 0 },
+        extent,
+    };
+
+    let state = DrawState {
+        pipeline: mesh_pipeline.pipeline,
+        layout: mesh_pipeline.layout,
+        descriptor_set: mesh_pipeline.pipeline_descriptor.set,
+        vertices: scene.vertices.buffer,
+        indices: scene.indices.buffer,
+        render_area,
+    };
+
+    //GPU culling draws everything with one command, nothing to split
+    let recorder = recorder.filter(|_| mesh_pipeline.culling.is_none());
+    let contents = match recorder {
+        Some(_) => vk::SubpassContents::SECONDARY_COMMAND_BUFFERS,
+        None => vk::SubpassContents::INLINE,
+    };
+
+    let scene_pass = vk::RenderPassBeginInfo::builder()
+        .framebuffer(mesh_pipeline.framebuffers.buffer(image_index))
+        .render_pass(mesh_pipeline.renderpass.pass())
+        .render_area(render_area)
+        .clear_values(&[
+            vk::ClearValue {
+                // clear value for color buffer
+                color: vk::ClearColorValue {
+                    float32: [0.0, 0.0, 0.0, 1.0],
+                },
+            },
+            vk::ClearValue {
+                // clear value for depth buffer
+                depth_stencil: vk::ClearDepthStencilValue {
+                    depth: 1.0,
+                    stencil: 0,
+                },
+            },
+        ])
+        .build();
+
+    if let Some(culling) = &mesh_pipeline.culling {
+        let camera = mesh_pipeline.camera.raw();
+        culling.record(command_buffer, camera.proj * camera.view);
+    }
+
+    //Every primitive of every mesh node, with the node it is drawn for
+    let draws: Vec<(&Node, &Primitive)> = scene
+        .nodes
+        .iter()
+        .filter_map(|node| node.mesh_index.map(|mesh_index| (node, mesh_index)))
+        .flat_map(|(node, mesh_index)| {
+            scene
+                .get_mesh(mesh_index)
+                .primitives
+                .iter()
+                .map(move |primitive| (node, primitive))
+        })
+        .collect();
+
+    unsafe {
+        device.cmd_begin_render_pass(command_buffer, &scene_pass, contents);
+
+        match (&mesh_pipeline.culling, recorder) {
+            (Some(culling), _) => {
+                state.bind(command_buffer, device);
+                device.cmd_bind_vertex_buffers(command_buffer, 0, &[state.vertices], &[0]);
+                device.cmd_bind_index_buffer(
+                    command_buffer,
+                    state.indices,
+                    0,
+                    vk::IndexType::UINT32,
+                );
+                culling.commands.draw(command_buffer);
+            }
+            (None, Some(recorder)) => {
+                let inheritance = RenderPassInheritance {
+                    render_pass: mesh_pipeline.renderpass.pass(),
+                    subpass: 0,
+                    framebuffer: mesh_pipeline.framebuffers.buffer(image_index),
+                };
+                let secondaries = recorder.record(
+                    image_index,
+                    inheritance,
+                    &draws,
+                    |secondary, device, draws| record_primitives(secondary, device, state, draws),
+                );
+                recorder.execute(command_buffer, &secondaries);
+            }
+            (None, None) => record_primitives(command_buffer, device, state, &draws),
+        }
+
+        device.cmd_end_render_pass(command_buffer);
+    }
+}
+
+//Handles every command buffer of a frame binds, secondary command buffers inherit no state
+#[derive(Clone, Copy)]
+struct DrawState {
+    pipeline: vk::Pipeline,
+    layout: vk::PipelineLayout,
+    descriptor_set: vk::DescriptorSet,
+    vertices: vk::Buffer,
+    indices: vk::Buffer,
+    render_area: vk::Rect2D,
+}
+
+impl DrawState {
+    unsafe fn bind(&self, command_buffer: vk::CommandBuffer, device: &Device) {
+        let viewports = [vk::Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: self.render_area.extent.width as f32,
+            height: self.render_area.extent.height as f32,
+            min_depth: 0.0,
+            max_depth: 1.0,
+        }];
+
+        device.cmd_set_viewport(command_buffer, 0, &viewports);
+        device.cmd_set_scissor(command_buffer, 0, &[self.render_area]);
+        device.cmd_bind_pipeline(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            self.pipeline,
+        );
+        device.cmd_bind_descriptor_sets(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            self.layout,
+            0,
+            &[self.descriptor_set],
+            &[],
+        );
+    }
+}
+
+fn record_primitives(
+    command_buffer: vk::CommandBuffer,
+    device: &Device,
+    state: DrawState,
+    draws: &[(&Node, &Primitive)],
+) {
+    unsafe {
+        state.bind(command_buffer, device);
+
+        for (node, primitive) in draws {
+            device.cmd_bind_vertex_buffers(
+                command_buffer,
+                0,
+                &[state.vertices],
+                &[primitive.vertex_offset as u64],
+            );
+            device.cmd_bind_index_buffer(
+                command_buffer,
+                state.indices,
+                primitive.indice_offset as u64,
+                vk::IndexType::UINT32,
+            );
+
+            device.cmd_push_constants(
+                command_buffer,
+                state.layout,
+                vk::ShaderStageFlags::VERTEX,
+                0,
+                as_byte_slice(&PushTransform {
+                    transform: node.transform_matrix,
+                }),
+            );
+            device.cmd_draw_indexed(command_buffer, primitive.indices_len as u32, 1, 0, 0, 0);
+        }
+    }
+}
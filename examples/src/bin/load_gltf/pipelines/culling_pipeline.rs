@@ -0,0 +1,102 @@
+use vulkan::{
+    prelude::*, ComputePipeline, Context, Descriptor, DescriptorSet, IndirectCommands,
+    StorageBuffer,
+};
+
+use examples::utils::{
+    culling::{self, CullPushConstant, DrawRecord, CULL_GROUP_SIZE},
+    gltf_importer::Scene,
+};
+use std::{mem, path::Path, sync::Arc};
+
+//Frustum culls the draw records of a scene with cull.comp, the visible ones
+//are drawn with a single indirect draw
+pub struct Culling {
+    pub records: StorageBuffer<DrawRecord>,
+    pub commands: IndirectCommands,
+    pub pipeline: ComputePipeline,
+}
+
+impl Culling {
+    pub fn new(scene: &Scene, context: Arc<Context>) -> Culling {
+        let mut draw_records = culling::draw_records(scene);
+        let record_count = draw_records.len();
+        //Storage buffers can not be empty
+        if draw_records.is_empty() {
+            draw_records.push(DrawRecord {
+                transform: [[0.0; 4]; 4],
+                aabb_min: [0.0; 4],
+                aabb_max: [0.0; 4],
+                first_index: 0,
+                index_count: 0,
+                vertex_offset: 0,
+                material: -1,
+            });
+        }
+
+        let records = StorageBuffer::new(&draw_records, context.clone());
+        let commands = IndirectCommands::new(record_count as u32, context.clone());
+
+        let descriptor = Descriptor::new(
+            vec![
+                DescriptorSet {
+                    bind_index: 0,
+                    flag: vk::ShaderStageFlags::COMPUTE,
+                    bind_type: vk::DescriptorType::STORAGE_BUFFER,
+                    buffer_info: Some(vec![records.descriptor_info()]),
+                    ..Default::default()
+                },
+                DescriptorSet {
+                    bind_index: 1,
+                    flag: vk::ShaderStageFlags::COMPUTE,
+                    bind_type: vk::DescriptorType::STORAGE_BUFFER,
+                    buffer_info: Some(vec![commands.commands_info()]),
+                    ..Default::default()
+                },
+                DescriptorSet {
+                    bind_index: 2,
+                    flag: vk::ShaderStageFlags::COMPUTE,
+                    bind_type: vk::DescriptorType::STORAGE_BUFFER,
+                    buffer_info: Some(vec![commands.count_info()]),
+                    ..Default::default()
+                },
+            ],
+            context.clone(),
+        );
+
+        let pipeline = ComputePipeline::new(
+            &Path::new("src/bin/load_gltf/shaders/cull.comp.spv"),
+            vec![descriptor],
+            mem::size_of::<CullPushConstant>() as u32,
+            context,
+        );
+
+        Culling {
+            records,
+            commands,
+            pipeline,
+        }
+    }
+
+    //Writes the indirect commands of the records inside the frustum of `view_proj`,
+    //record outside of a render pass
+    pub fn record(&self, command_buffer: vk::CommandBuffer, view_proj: cgmath::Matrix4<f32>) {
+        let record_count = self.commands.max_draws();
+        self.commands.reset(command_buffer);
+
+        self.pipeline.push_constants(
+            command_buffer,
+            &CullPushConstant {
+                planes: culling::frustum_planes(view_proj),
+                record_count,
+                padding_0: 0,
+                padding_1: 0,
+                padding_2: 0,
+            },
+        );
+        self.pipeline
+            .dispatch_for(command_buffer, record_count, CULL_GROUP_SIZE);
+
+        self.commands.finish_writes(command_buffer);
+    }
+}
@@ -1,10 +1,11 @@
 use vulkan::{
-    offset_of,
     prelude::*,
     utilities::{as_byte_slice, Shader},
-    Buffer, Context, Descriptor, DescriptorSet, Image, Swapchain, VkThread, Framebuffer
+    Buffer, Context, Descriptor, DescriptorSet, FramebufferAttachment, FramebufferSet, Image,
+    RenderTarget, Renderpass, UniformBuffer, VertexInput, VkThread,
 };
 
+use super::culling_pipeline::Culling;
 use super::definitions::{PushTransform, SpecializationData};
 use examples::utils::{
     gltf_importer::{MaterialRaw, Scene, Vertex},
@@ -16,33 +17,48 @@ pub struct Pipeline {
     pub pipeline: vk::Pipeline,
     pub layout: vk::PipelineLayout,
     pub pipeline_descriptor: Descriptor,
-    pub framebuffers: Vec<Framebuffer>,
+    pub framebuffers: FramebufferSet,
 
     _empty_image: Image,
     pub depth_image: Image,
-    pub uniform_buffer: Buffer,
-    pub material_buffer: Buffer,
+    pub color_image: Option<Image>,
+    pub samples: vk::SampleCountFlags,
+    pub uniform_buffer: UniformBuffer<CameraRaw>,
+    pub material_buffer: Buffer<MaterialRaw>,
     pub camera: Camera,
-    pub renderpass: vk::RenderPass,
+    pub renderpass: Renderpass,
+    //Set when the scene is drawn with GPU frustum culling and indirect draws
+    pub culling: Option<Culling>,
 
     context: Arc<Context>,
 }
 
 impl Pipeline {
-    //Creates a new pipeline
-    pub fn build_for(scene: &Scene, swapchain: &Swapchain, vulkan: &VkThread) -> Pipeline {
+    //Creates a new pipeline, with `gpu_culling` the vertex shader reads the node
+    //transforms from the culled draw records instead of push constants
+    pub fn build_for<T: RenderTarget>(
+        scene: &Scene,
+        target: &T,
+        vulkan: &VkThread,
+        samples: vk::SampleCountFlags,
+        gpu_culling: bool,
+    ) -> Pipeline {
         let context = vulkan.context();
         //Create buffer data
         let camera = Camera::new(cgmath::Point3::new(0.0, 0.0, 0.0), 15.0, 1.3);
-        let depth_image = examples::create_depth_resources(&swapchain, context.clone());
-        let uniform_buffer = Buffer::new_mapped_basic(
-            mem::size_of::<CameraRaw>() as u64,
-            vk::BufferUsageFlags::UNIFORM_BUFFER,
-            vk_mem::MemoryUsage::CpuOnly,
-            context.clone(),
-        );
+        let depth_image = examples::create_depth_resources(target, samples, context.clone());
 
-        uniform_buffer.upload_to_buffer(&[camera.raw()], 0);
+        //Multisampled color target, resolved into the render target image
+        let color_image = if samples != vk::SampleCountFlags::TYPE_1 {
+            Some(examples::create_color_resources(
+                target,
+                samples,
+                context.clone(),
+            ))
+        } else {
+            None
+        };
+        let uniform_buffer = UniformBuffer::new(&[camera.raw()], context.clone());
 
         //Create material buffers
         let material_buffer = vulkan.create_gpu_buffer(
@@ -99,37 +115,46 @@ impl Pipeline {
             _ => scene.textures.len() as u32,
         };
 
-        let pipeline_descriptor = Descriptor::new(
-            vec![
-                DescriptorSet {
-                    bind_index: 0,
-                    flag: vk::ShaderStageFlags::VERTEX,
-                    bind_type: vk::DescriptorType::UNIFORM_BUFFER,
-                    buffer_info: Some(vec![vk::DescriptorBufferInfo {
-                        buffer: uniform_buffer.buffer,
-                        offset: 0,
-                        range: uniform_buffer.size,
-                    }]),
-                    ..Default::default()
-                },
-                DescriptorSet {
-                    bind_index: 1,
-                    flag: vk::ShaderStageFlags::FRAGMENT,
-                    bind_type: vk::DescriptorType::UNIFORM_BUFFER,
-                    buffer_info: Some(material_bindings),
-                    ..Default::default()
-                },
-                DescriptorSet {
-                    bind_index: 2,
-                    flag: vk::ShaderStageFlags::FRAGMENT,
-                    bind_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
-                    image_info: Some(texture_data),
-                    count: texture_count,
-                    ..Default::default()
-                },
-            ],
-            context.clone(),
-        );
+        let culling = if gpu_culling {
+            Some(Culling::new(scene, context.clone()))
+        } else {
+            None
+        };
+
+        let mut descriptor_sets = vec![
+            DescriptorSet {
+                bind_index: 0,
+                flag: vk::ShaderStageFlags::VERTEX,
+                bind_type: vk::DescriptorType::UNIFORM_BUFFER,
+                buffer_info: Some(vec![uniform_buffer.descriptor_info()]),
+                ..Default::default()
+            },
+            DescriptorSet {
+                bind_index: 1,
+                flag: vk::ShaderStageFlags::FRAGMENT,
+                bind_type: vk::DescriptorType::UNIFORM_BUFFER,
+                buffer_info: Some(material_bindings),
+                ..Default::default()
+            },
+            DescriptorSet {
+                bind_index: 2,
+                flag: vk::ShaderStageFlags::FRAGMENT,
+                bind_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                image_info: Some(texture_data),
+                count: texture_count,
+                ..Default::default()
+            },
+        ];
+        if let Some(culling) = &culling {
+            descriptor_sets.push(DescriptorSet {
+                bind_index: 3,
+                flag: vk::ShaderStageFlags::VERTEX,
+                bind_type: vk::DescriptorType::STORAGE_BUFFER,
+                buffer_info: Some(vec![culling.records.descriptor_info()]),
+                ..Default::default()
+            });
+        }
+        let pipeline_descriptor = Descriptor::new(descriptor_sets, context.clone());
 
         //Create pipeline stuff
         let pipeline_layout = unsafe {
@@ -170,7 +195,12 @@ impl Pipeline {
         };
 
         let shader_name = CString::new("main").unwrap();
-        let renderpass = create_render_pass(&swapchain, context.clone());
+        let vertex_shader = if culling.is_some() {
+            "src/bin/load_gltf/shaders/model_indirect.vert.spv"
+        } else {
+            "src/bin/load_gltf/shaders/model.vert.spv"
+        };
+        let renderpass = create_render_pass(target, context.clone(), samples);
         let pipeline = unsafe {
             context
                 .device
@@ -179,7 +209,7 @@ impl Pipeline {
                     &[vk::GraphicsPipelineCreateInfo::builder()
                         .stages(&[
                             Shader::new(
-                                &Path::new("src/bin/load_gltf/shaders/model.vert.spv"),
+                                &Path::new(vertex_shader),
                                 vk::ShaderStageFlags::VERTEX,
                                 &shader_name,
                                 context.clone(),
@@ -196,49 +226,8 @@ impl Pipeline {
                         ])
                         .vertex_input_state(
                             &vk::PipelineVertexInputStateCreateInfo::builder()
-                                .vertex_binding_descriptions(&[vk::VertexInputBindingDescription {
-                                    binding: 0,
-                                    stride: mem::size_of::<Vertex>() as u32,
-                                    input_rate: vk::VertexInputRate::VERTEX,
-                                }])
-                                .vertex_attribute_descriptions(&[
-                                    vk::VertexInputAttributeDescription {
-                                        binding: 0,
-                                        location: 0,
-                                        format: vk::Format::R32G32B32_SFLOAT,
-                                        offset: offset_of!(Vertex, position) as u32,
-                                    },
-                                    vk::VertexInputAttributeDescription {
-                                        binding: 0,
-                                        location: 1,
-                                        format: vk::Format::R32G32B32A32_SFLOAT,
-                                        offset: offset_of!(Vertex, color) as u32,
-                                    },
-                                    vk::VertexInputAttributeDescription {
-                                        binding: 0,
-                                        location: 2,
-                                        format: vk::Format::R32G32B32A32_SFLOAT,
-                                        offset: offset_of!(Vertex, tangents) as u32,
-                                    },
-                                    vk::VertexInputAttributeDescription {
-                                        binding: 0,
-                                        location: 3,
-                                        format: vk::Format::R32G32B32_SFLOAT,
-                                        offset: offset_of!(Vertex, normal) as u32,
-                                    },
-                                    vk::VertexInputAttributeDescription {
-                                        binding: 0,
-                                        location: 4,
-                                        format: vk::Format::R32G32_SFLOAT,
-                                        offset: offset_of!(Vertex, uv) as u32,
-                                    },
-                                    vk::VertexInputAttributeDescription {
-                                        binding: 0,
-                                        location: 5,
-                                        format: vk::Format::R32_SINT,
-                                        offset: offset_of!(Vertex, material_id) as u32,
-                                    },
-                                ])
+                                .vertex_binding_descriptions(&[Vertex::binding_description(0)])
+                                .vertex_attribute_descriptions(&Vertex::attribute_descriptions(0))
                                 .build(),
                         )
                         .input_assembly_state(&vk::PipelineInputAssemblyStateCreateInfo {
@@ -249,13 +238,13 @@ impl Pipeline {
                             &vk::PipelineViewportStateCreateInfo::builder()
                                 .scissors(&[vk::Rect2D {
                                     offset: vk::Offset2D { x: 0, y: 0 },
-                                    extent: swapchain.extent,
+                                    extent: target.extent(),
                                 }])
                                 .viewports(&[vk::Viewport {
                                     x: 0.0,
                                     y: 0.0,
-                                    width: swapchain.extent.width as f32,
-                                    height: swapchain.extent.height as f32,
+                                    width: target.width() as f32,
+                                    height: target.height() as f32,
                                     min_depth: 0.0,
                                     max_depth: 1.0,
                                 }]),
@@ -267,7 +256,7 @@ impl Pipeline {
                             ..Default::default()
                         })
                         .multisample_state(&vk::PipelineMultisampleStateCreateInfo {
-                            rasterization_samples: vk::SampleCountFlags::TYPE_1,
+                            rasterization_samples: samples,
                             ..Default::default()
                         })
                         .depth_stencil_state(&vk::PipelineDepthStencilStateCreateInfo {
@@ -300,29 +289,25 @@ impl Pipeline {
                             ]),
                         )
                         .layout(pipeline_layout)
-                        .render_pass(renderpass)
+                        .render_pass(renderpass.pass())
                         .build()],
                     None,
                 )
                 .expect("Unable to create graphics pipeline")
         }[0];
 
-        let framebuffers = swapchain
-        .image_views
-        .iter()
-        .map(|image| {
-            Framebuffer::new(
-                vk::FramebufferCreateInfo::builder()
-                    .layers(1)
-                    .render_pass(renderpass)
-                    .attachments(&[*image, depth_image.view()])
-                    .width(swapchain.width())
-                    .height(swapchain.height())
-                    .build(),
-                vulkan.context(),
-            )
-        })
-        .collect();
+        let attachments = match &color_image {
+            Some(color_image) => vec![
+                FramebufferAttachment::View(color_image.view()),
+                FramebufferAttachment::View(depth_image.view()),
+                FramebufferAttachment::Swapchain,
+            ],
+            None => vec![
+                FramebufferAttachment::Swapchain,
+                FramebufferAttachment::View(depth_image.view()),
+            ],
+        };
+        let framebuffers = FramebufferSet::new(target, renderpass.pass(), attachments);
 
         Pipeline {
             pipeline: pipeline,
@@ -330,11 +315,14 @@ impl Pipeline {
             pipeline_descriptor,
             layout: pipeline_layout,
             depth_image,
+            color_image,
+            samples,
             _empty_image: empty_image,
             uniform_buffer,
             material_buffer,
             camera,
             renderpass,
+            culling,
             context,
         }
     }
@@ -348,15 +336,15 @@ impl Drop for Pipeline {
             self.context
                 .device
                 .destroy_pipeline_layout(self.layout, None);
-
-            self.context
-                .device
-                .destroy_render_pass(self.renderpass, None);
         }
     }
 }
 
-pub fn create_render_pass(swapchain: &Swapchain, context: Arc<Context>) -> vk::RenderPass {
+pub fn create_render_pass<T: RenderTarget>(
+    target: &T,
+    context: Arc<Context>,
+    samples: vk::SampleCountFlags,
+) -> Renderpass {
     let depth_format = context.find_depth_format(
         &[
             vk::Format::D32_SFLOAT,
@@ -367,59 +355,40 @@ pub fn create_render_pass(swapchain: &Swapchain, context: Arc<Context>) -> vk::R
         vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT,
     );
 
-    let subpasses = vk::SubpassDescription::builder()
-        .color_attachments(&[vk::AttachmentReference {
-            attachment: 0,
-            layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-        }])
-        .depth_stencil_attachment(&vk::AttachmentReference {
-            attachment: 1,
-            layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
-        })
-        .build();
-
-    let renderpass_create_info = vk::RenderPassCreateInfo::builder()
-        .subpasses(&[subpasses])
-        .dependencies(&[vk::SubpassDependency {
-            src_subpass: vk::SUBPASS_EXTERNAL,
-            dst_subpass: 0,
-            src_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
-            dst_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
-            src_access_mask: vk::AccessFlags::empty(),
-            dst_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_READ
-                | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
-            dependency_flags: vk::DependencyFlags::empty(),
-        }])
-        .attachments(&[
-            vk::AttachmentDescription {
-                flags: vk::AttachmentDescriptionFlags::empty(),
-                format: swapchain.format,
-                samples: vk::SampleCountFlags::TYPE_1,
-                load_op: vk::AttachmentLoadOp::CLEAR,
-                store_op: vk::AttachmentStoreOp::STORE,
-                stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
-                stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
-                initial_layout: vk::ImageLayout::UNDEFINED,
-                final_layout: vk::ImageLayout::PRESENT_SRC_KHR,
-            },
-            vk::AttachmentDescription {
-                flags: vk::AttachmentDescriptionFlags::empty(),
-                format: depth_format,
-                samples: vk::SampleCountFlags::TYPE_1,
-                load_op: vk::AttachmentLoadOp::CLEAR,
-                store_op: vk::AttachmentStoreOp::DONT_CARE,
-                stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
-                stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
-                initial_layout: vk::ImageLayout::UNDEFINED,
-                final_layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
-            },
-        ])
-        .build();
-
-    unsafe {
-        context
-            .device
-            .create_render_pass(&renderpass_create_info, None)
-            .expect("Failed to create render pass!")
+    if samples == vk::SampleCountFlags::TYPE_1 {
+        return Renderpass::builder()
+            .color_attachment(
+                target.format(),
+                samples,
+                vk::AttachmentLoadOp::CLEAR,
+                vk::AttachmentStoreOp::STORE,
+                target.final_layout(),
+            )
+            .depth_attachment(
+                depth_format,
+                samples,
+                vk::AttachmentLoadOp::CLEAR,
+                vk::AttachmentStoreOp::DONT_CARE,
+                vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+            )
+            .build(context);
     }
+
+    Renderpass::builder()
+        .color_attachment(
+            target.format(),
+            samples,
+            vk::AttachmentLoadOp::CLEAR,
+            vk::AttachmentStoreOp::DONT_CARE,
+            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+        )
+        .depth_attachment(
+            depth_format,
+            samples,
+            vk::AttachmentLoadOp::CLEAR,
+            vk::AttachmentStoreOp::DONT_CARE,
+            vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+        )
+        .resolve_attachment(target.format(), target.final_layout())
+        .build(context)
 }
@@ -1,4 +1,3 @@
+pub mod culling_pipeline;
 pub mod definitions;
 pub mod mesh_pipeline;
-
-
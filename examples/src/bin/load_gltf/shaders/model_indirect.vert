@@ -0,0 +1,51 @@
+#version 450
+
+#extension GL_ARB_separate_shader_objects : enable
+
+layout (binding = 0) uniform Camera {
+    vec4 position;
+    mat4 view;
+    mat4 proj;
+} camera;
+
+struct DrawRecord {
+    mat4 transform;
+    vec4 aabb_min;
+    vec4 aabb_max;
+    uint first_index;
+    uint index_count;
+    int vertex_offset;
+    int material;
+};
+
+//Written once per scene, indexed by the first instance cull.comp sets
+layout (std430, binding = 3) readonly buffer Records {
+    DrawRecord records[];
+};
+
+layout (location = 0) in vec3 inPosition;
+layout (location = 1) in vec4 inColor;
+layout (location = 2) in vec4 tangents;
+layout (location = 3) in vec3 normal;
+layout (location = 4) in vec2 uv;
+layout (location = 5) in int material_index;
+
+layout (location = 0) out vec4 fragColor;
+layout (location = 1) out vec4 out_tangents;
+layout (location = 2) out vec3 out_normal;
+layout (location = 3) out vec2 out_uv;
+layout (location = 4) out int out_material_index;
+
+out gl_PerVertex {
+    vec4 gl_Position;
+};
+
+void main() {
+    mat4 model_transform = records[gl_InstanceIndex].transform;
+    gl_Position = camera.proj * camera.view * model_transform * vec4(inPosition, 1.0);
+    fragColor = inColor;
+    out_normal = normal;
+    out_uv = uv;
+    out_material_index = material_index;
+    out_tangents = tangents;
+}
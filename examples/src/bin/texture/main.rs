@@ -1,6 +1,10 @@
 mod pipeline;
 
-use vulkan::{prelude::*, utilities::FPSLimiter, Context, Framebuffer, Queue, Swapchain, VkThread, PipelineType};
+use examples::utils::golden;
+use vulkan::{
+    prelude::*, Buffer, Context, FrameCommands, FrameTimer, FramebufferAttachment, FramebufferSet,
+    PipelineType, Queue, RecordMode, RenderTarget, Swapchain, VkThread,
+};
 use winit::event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
 
@@ -33,6 +37,34 @@ fn main() {
 
     let indices = vec![0, 1, 2, 2, 3, 0];
 
+    if golden::requested() {
+        let (_context, instance, target) = golden::setup("texture");
+        let pipeline = Pipeline::new(&target, &instance);
+        let framebuffers = FramebufferSet::new(
+            &target,
+            pipeline.renderpass,
+            vec![FramebufferAttachment::Swapchain],
+        );
+        let index_buffer = instance.create_gpu_buffer(vk::BufferUsageFlags::INDEX_BUFFER, &indices);
+        let vertex_buffer =
+            instance.create_gpu_buffer(vk::BufferUsageFlags::VERTEX_BUFFER, &vertices);
+
+        let pixels =
+            instance.render_offscreen(&target, target.final_layout(), |command_buffer, device| {
+                draw(
+                    command_buffer,
+                    device,
+                    &pipeline,
+                    framebuffers.buffer(0),
+                    target.extent(),
+                    &vertex_buffer,
+                    &index_buffer,
+                )
+            });
+        golden::finish("texture", pixels);
+        return;
+    }
+
     let event_loop = EventLoop::new();
     let window = winit::window::WindowBuilder::new()
         .with_title("test")
@@ -58,25 +90,20 @@ fn main() {
         &vertices,
     );
 
-    let command_buffers = instance.create_command_buffers(swapchain.image_views.len());
-    let mut framebuffers: Vec<Framebuffer> = swapchain
-        .image_views
-        .iter()
-        .map(|image| {
-            Framebuffer::new(
-                vk::FramebufferCreateInfo::builder()
-                    .layers(1)
-                    .render_pass(pipeline.renderpass)
-                    .attachments(&[*image])
-                    .width(swapchain.width())
-                    .height(swapchain.height())
-                    .build(),
-                vulkan.clone(),
-            )
-        })
-        .collect();
-
-    let mut tick_counter = FPSLimiter::new();
+    //Nothing changes between frames, the command buffers are recorded once per image
+    let mut commands = FrameCommands::new(
+        &instance,
+        swapchain.image_views.len(),
+        RecordMode::WhenDirty,
+    );
+    let mut framebuffers = FramebufferSet::new(
+        &swapchain,
+        pipeline.renderpass,
+        vec![FramebufferAttachment::Swapchain],
+    );
+
+    let mut frame_timer = FrameTimer::new();
+    frame_timer.set_target_fps(examples::target_fps());
 
     event_loop.run(move |event, _, control_flow| match event {
         Event::WindowEvent { event, .. } => match event {
@@ -97,83 +124,32 @@ fn main() {
         },
         Event::MainEventsCleared => {
             window.request_redraw();
-            print!("FPS: {}\r", tick_counter.fps());
+            examples::print_status(&frame_timer.stats().to_string());
 
-            tick_counter.tick_frame();
+            frame_timer.limit();
+            frame_timer.tick();
         }
         Event::RedrawRequested(_window_id) => {
-            let extent = vk::Rect2D {
-                offset: vk::Offset2D { x: 0, y: 0 },
-                extent: swapchain.extent,
-            };
-
-            let viewports = [vk::Viewport {
-                x: 0.0,
-                y: 0.0,
-                width: swapchain.extent.width as f32,
-                height: swapchain.extent.height as f32,
-                min_depth: 0.0,
-                max_depth: 1.0,
-            }];
             let frame = queue.load_next_frame(&swapchain);
 
             if let Ok((image_index, _is_suboptimal)) = frame {
-                let render_pass_info = vk::RenderPassBeginInfo::builder()
-                    .framebuffer(framebuffers[image_index as usize].buffer())
-                    .render_pass(pipeline.renderpass)
-                    .clear_values(&[vk::ClearValue {
-                        color: vk::ClearColorValue {
-                            float32: [0.0, 0.0, 0.0, 1.0],
-                        },
-                    }])
-                    .render_area(extent)
-                    .build();
-
-                instance.build_command(
-                    command_buffers[image_index as usize],
-                    |command_buffer, device| unsafe {
-                        device.cmd_begin_render_pass(
-                            command_buffer,
-                            &render_pass_info,
-                            vk::SubpassContents::INLINE,
-                        );
-                        device.cmd_bind_pipeline(
+                let command_buffer = commands.command_buffer(
+                    &instance,
+                    image_index as usize,
+                    |command_buffer, device| {
+                        draw(
                             command_buffer,
-                            vk::PipelineBindPoint::GRAPHICS,
-                            pipeline.pipeline,
-                        );
-                        device.cmd_bind_descriptor_sets(
-                            command_buffer,
-                            vk::PipelineBindPoint::GRAPHICS,
-                            pipeline.layout,
-                            0,
-                            &[pipeline.pipeline_descriptor.set],
-                            &[],
-                        );
-                        device.cmd_set_viewport(command_buffer, 0, &viewports);
-                        device.cmd_set_scissor(command_buffer, 0, &[extent]);
-                        device.cmd_bind_vertex_buffers(
-                            command_buffer,
-                            0,
-                            &[vertex_buffer.buffer],
-                            &[0],
-                        );
-                        device.cmd_bind_index_buffer(
-                            command_buffer,
-                            index_buffer.buffer,
-                            0,
-                            vk::IndexType::UINT32,
-                        );
-                        device.cmd_draw_indexed(command_buffer, indices.len() as u32, 1, 0, 0, 1);
-                        device.cmd_end_render_pass(command_buffer);
+                            device,
+                            &pipeline,
+                            framebuffers.buffer(image_index as usize),
+                            swapchain.extent,
+                            &vertex_buffer,
+                            &index_buffer,
+                        )
                     },
                 );
 
-                queue.render_frame(
-                    &swapchain,
-                    command_buffers[image_index as usize],
-                    image_index,
-                );
+                queue.render_frame(&swapchain, command_buffer, image_index);
             } else {
                 println!("Failed to draw frame {:?}", frame.err());
 
@@ -181,25 +157,83 @@ fn main() {
                 swapchain = Swapchain::new(vulkan.clone());
 
                 pipeline = pipeline::Pipeline::new(&swapchain, &instance);
-                framebuffers = swapchain
-                    .image_views
-                    .iter()
-                    .map(|image| {
-                        Framebuffer::new(
-                            vk::FramebufferCreateInfo::builder()
-                                .layers(1)
-                                .render_pass(pipeline.renderpass)
-                                .attachments(&[*image])
-                                .width(swapchain.width())
-                                .height(swapchain.height())
-                                .build(),
-                            vulkan.clone(),
-                        )
-                    })
-                    .collect();
+                framebuffers = FramebufferSet::new(
+                    &swapchain,
+                    pipeline.renderpass,
+                    vec![FramebufferAttachment::Swapchain],
+                );
+                commands.mark_dirty();
             }
         }
         Event::LoopDestroyed => {}
         _ => {}
     });
 }
+
+//Records the textured quad into `framebuffer`, shared by the window and --golden
+fn draw(
+    command_buffer: vk::CommandBuffer,
+    device: &Device,
+    pipeline: &Pipeline,
+    framebuffer: vk::Framebuffer,
+    extent: vk::Extent2D,
+    vertex_buffer: &Buffer<Vertex>,
+    index_buffer: &Buffer<u32>,
+) {
+    let render_area = vk::Rect2D {
+        offset: vk::Offset2D { x: 0, y: 0 },
+        extent,
+    };
+
+    let viewports = [vk::Viewport {
+        x: 0.0,
+        y: 0.0,
+        width: extent.width as f32,
+        height: extent.height as f32,
+        min_depth: 0.0,
+        max_depth: 1.0,
+    }];
+
+    let render_pass_info = vk::RenderPassBeginInfo::builder()
+        .framebuffer(framebuffer)
+        .render_pass(pipeline.renderpass)
+        .clear_values(&[vk::ClearValue {
+            color: vk::ClearColorValue {
+                float32: [0.0, 0.0, 0.0, 1.0],
+            },
+        }])
+        .render_area(render_area)
+        .build();
+
+    unsafe {
+        device.cmd_begin_render_pass(
+            command_buffer,
+            &render_pass_info,
+            vk::SubpassContents::INLINE,
+        );
+        device.cmd_bind_pipeline(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            pipeline.pipeline,
+        );
+        device.cmd_bind_descriptor_sets(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            pipeline.layout,
+            0,
+            &[pipeline.pipeline_descriptor.set],
+            &[],
+        );
+        device.cmd_set_viewport(command_buffer, 0, &viewports);
+        device.cmd_set_scissor(command_buffer, 0, &[render_area]);
+        device.cmd_bind_vertex_buffers(command_buffer, 0, &[vertex_buffer.buffer], &[0]);
+        device.cmd_bind_index_buffer(
+            command_buffer,
+            index_buffer.buffer,
+            0,
+            vk::IndexType::UINT32,
+        );
+        device.cmd_draw_indexed(command_buffer, index_buffer.len() as u32, 1, 0, 0, 1);
+        device.cmd_end_render_pass(command_buffer);
+    }
+}
@@ -1,7 +1,7 @@
 use cgmath::{Deg, Matrix4, Point3, Vector3};
 use vulkan::{
-    Swapchain, offset_of, prelude::*, Buffer, Context, Descriptor,
-    DescriptorSet, Image, Shader, VkThread,
+    offset_of, prelude::*, Buffer, Context, Descriptor, DescriptorSet, Image, RenderTarget, Shader,
+    VkThread,
 };
 
 use std::default::Default;
@@ -30,7 +30,7 @@ pub struct Pipeline {
     pub pipeline: vk::Pipeline,
     pub layout: vk::PipelineLayout,
     pub texture: Image,
-    pub uniform_buffer: Buffer,
+    pub uniform_buffer: Buffer<UniformBufferObject>,
     pub uniform_transform: UniformBufferObject,
     pub renderpass: vk::RenderPass,
     context: Arc<Context>,
@@ -39,8 +39,8 @@ pub struct Pipeline {
 }
 
 impl Pipeline {
-    //Creates a new pipeline
-    pub fn new(swapchain: &Swapchain, vulkan: &VkThread) -> Pipeline {
+    //Creates a new pipeline rendering into `target`, a Swapchain or an OffscreenTarget
+    pub fn new<T: RenderTarget>(target: &T, vulkan: &VkThread) -> Pipeline {
         let vertex_input_state_info = vk::PipelineVertexInputStateCreateInfo::builder()
             .vertex_binding_descriptions(&[vk::VertexInputBindingDescription {
                 binding: 0,
@@ -75,14 +75,14 @@ impl Pipeline {
         let viewports = [vk::Viewport {
             x: 0.0,
             y: 0.0,
-            width: swapchain.extent.width as f32,
-            height: swapchain.extent.height as f32,
+            width: target.width() as f32,
+            height: target.height() as f32,
             min_depth: 0.0,
             max_depth: 1.0,
         }];
         let scissors = [vk::Rect2D {
             offset: vk::Offset2D { x: 0, y: 0 },
-            extent: swapchain.extent,
+            extent: target.extent(),
         }];
         let viewport_state_info = vk::PipelineViewportStateCreateInfo::builder()
             .scissors(&scissors)
@@ -134,11 +134,11 @@ impl Pipeline {
 
         //Create texture image
 
-        let texture = examples::create_texture(&Path::new("assets/texture.jpg"), &vulkan);
+        let texture = examples::create_texture(&Path::new("assets/wood.jpg"), &vulkan);
 
         //Create uniform buffer
 
-        let uniform_data = create_uniform_data(&swapchain);
+        let uniform_data = create_uniform_data(target.extent());
  
 
         let uniform_buffer = vulkan.create_gpu_buffer(
@@ -186,7 +186,7 @@ impl Pipeline {
                 .create_pipeline_layout(&layout_create_info, None)
                 .unwrap()
         };
-        let renderpass = create_render_pass(&swapchain, &vulkan);
+        let renderpass = create_render_pass(target, &vulkan);
         let shader_name = CString::new("main").unwrap();
      
 
@@ -255,7 +255,7 @@ impl Drop for Pipeline {
         }
     }
 }
-pub fn create_uniform_data(swapchain: &Swapchain) -> UniformBufferObject {
+pub fn create_uniform_data(extent: vk::Extent2D) -> UniformBufferObject {
     UniformBufferObject {
         model: Matrix4::from_angle_z(Deg(90.0)),
         view: Matrix4::look_at(
@@ -266,7 +266,7 @@ pub fn create_uniform_data(swapchain: &Swapchain) -> UniformBufferObject {
         proj: {
             let proj = cgmath::perspective(
                 Deg(45.0),
-                swapchain.extent.width as f32 / swapchain.extent.height as f32,
+                extent.width as f32 / extent.height as f32,
                 0.1,
                 10.0,
             );
@@ -275,17 +275,17 @@ pub fn create_uniform_data(swapchain: &Swapchain) -> UniformBufferObject {
     }
 }
 
-pub fn create_render_pass(swapchain: &Swapchain, vulkan: &VkThread) -> vk::RenderPass {
+pub fn create_render_pass<T: RenderTarget>(target: &T, vulkan: &VkThread) -> vk::RenderPass {
     let color_attachment = vk::AttachmentDescription {
         flags: vk::AttachmentDescriptionFlags::empty(),
-        format: swapchain.format,
+        format: target.format(),
         samples: vk::SampleCountFlags::TYPE_1,
         load_op: vk::AttachmentLoadOp::CLEAR,
         store_op: vk::AttachmentStoreOp::STORE,
         stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
         stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
         initial_layout: vk::ImageLayout::UNDEFINED,
-        final_layout: vk::ImageLayout::PRESENT_SRC_KHR,
+        final_layout: target.final_layout(),
     };
 
     let subpasses = [vk::SubpassDescription {
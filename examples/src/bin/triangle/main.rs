@@ -1,6 +1,10 @@
 mod pipeline;
 
-use vulkan::{prelude::*, utilities::FPSLimiter, Context, Queue, Swapchain, VkThread, PipelineType};
+use examples::utils::golden;
+use vulkan::{
+    prelude::*, Buffer, CommandEncoder, Context, FrameCommands, FrameTimer, Framebuffer,
+    PipelineType, Queue, RecordMode, RenderTarget, Swapchain, VkThread,
+};
 use winit::event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
 
@@ -24,6 +28,29 @@ fn main() {
 
     let indices = vec![0, 1, 2];
 
+    if golden::requested() {
+        let (_context, instance, target) = golden::setup("triangle");
+        let pipeline = pipeline::Pipe::new(&target, &instance);
+        let index_buffer = instance.create_gpu_buffer(vk::BufferUsageFlags::INDEX_BUFFER, &indices);
+        let vertex_buffer =
+            instance.create_gpu_buffer(vk::BufferUsageFlags::VERTEX_BUFFER, &vertices);
+
+        let pixels =
+            instance.render_offscreen(&target, target.final_layout(), |command_buffer, device| {
+                draw(
+                    command_buffer,
+                    device,
+                    &pipeline,
+                    &pipeline.framebuffers[0],
+                    target.extent(),
+                    &vertex_buffer,
+                    &index_buffer,
+                )
+            });
+        golden::finish("triangle", pixels);
+        return;
+    }
+
     let event_loop = EventLoop::new();
     let window = winit::window::WindowBuilder::new()
         .with_title("test")
@@ -43,8 +70,14 @@ fn main() {
     let index_buffer = instance.create_gpu_buffer(vk::BufferUsageFlags::INDEX_BUFFER, &indices);
     let vertex_buffer = instance.create_gpu_buffer(vk::BufferUsageFlags::VERTEX_BUFFER, &vertices);
 
-    let command_buffers = instance.create_command_buffers(swapchain.image_views.len());
-    let mut tick_counter = FPSLimiter::new();
+    //Nothing changes between frames, the command buffers are recorded once per image
+    let mut commands = FrameCommands::new(
+        &instance,
+        swapchain.image_views.len(),
+        RecordMode::WhenDirty,
+    );
+    let mut frame_timer = FrameTimer::new();
+    frame_timer.set_target_fps(examples::target_fps());
 
 
 
@@ -67,85 +100,77 @@ fn main() {
         },
         Event::MainEventsCleared => {
             window.request_redraw();
-            print!("FPS: {}\r", tick_counter.fps());
+            examples::print_status(&frame_timer.stats().to_string());
 
-            tick_counter.tick_frame();
+            frame_timer.limit();
+            frame_timer.tick();
         }
         Event::RedrawRequested(_window_id) => {
-            let extent = vk::Rect2D {
-                offset: vk::Offset2D { x: 0, y: 0 },
-                extent: swapchain.extent,
-            };
-
-            let viewports = [vk::Viewport {
-                x: 0.0,
-                y: 0.0,
-                width: swapchain.extent.width as f32,
-                height: swapchain.extent.height as f32,
-                min_depth: 0.0,
-                max_depth: 1.0,
-            }];
-
             let frame = queue.load_next_frame(&swapchain);
 
             if let Ok((image_index, _is_suboptimal)) = frame {
-                let render_pass_info = vk::RenderPassBeginInfo::builder()
-                    .framebuffer(pipeline.framebuffers[image_index as usize].buffer())
-                    .render_pass(pipeline.renderpass.pass())
-                    .clear_values(&[vk::ClearValue {
-                        color: vk::ClearColorValue {
-                            float32: [0.0, 0.0, 0.0, 1.0],
-                        },
-                    }])
-                    .render_area(extent)
-                    .build();
-
-                instance.build_command(
-                    command_buffers[image_index as usize],
-                    |command_buffer, device| unsafe {
-                        device.cmd_begin_render_pass(
-                            command_buffer,
-                            &render_pass_info,
-                            vk::SubpassContents::INLINE,
-                        );
-                        device.cmd_bind_pipeline(
-                            command_buffer,
-                            vk::PipelineBindPoint::GRAPHICS,
-                            pipeline.pipeline.default(),
-                        );
-                        device.cmd_set_viewport(command_buffer, 0, &viewports);
-                        device.cmd_set_scissor(command_buffer, 0, &[extent]);
-                        device.cmd_bind_vertex_buffers(
-                            command_buffer,
-                            0,
-                            &[vertex_buffer.buffer],
-                            &[0],
-                        );
-                        device.cmd_bind_index_buffer(
+                let command_buffer = commands.command_buffer(
+                    &instance,
+                    image_index as usize,
+                    |command_buffer, device| {
+                        draw(
                             command_buffer,
-                            index_buffer.buffer,
-                            0,
-                            vk::IndexType::UINT32,
-                        );
-                        device.cmd_draw_indexed(command_buffer, indices.len() as u32, 1, 0, 0, 1);
-                        device.cmd_end_render_pass(command_buffer);
+                            device,
+                            &pipeline,
+                            &pipeline.framebuffers[image_index as usize],
+                            swapchain.extent,
+                            &vertex_buffer,
+                            &index_buffer,
+                        )
                     },
                 );
 
-                queue.render_frame(
-                    &swapchain,
-                    command_buffers[image_index as usize],
-                    image_index,
-                );
+                queue.render_frame(&swapchain, command_buffer, image_index);
             } else {
                 println!("Failed to draw frame {:?}", frame.err());
 
                 vulkan.wait_idle();
                 swapchain = Swapchain::new(vulkan.clone());
                 pipeline = pipeline::Pipe::new(&swapchain, &instance);
+                commands.mark_dirty();
             }
         }
         Event::LoopDestroyed => {}
         _ => {}
     });
 }
+
+//Records the triangle into `framebuffer`, shared by the window and --golden
+fn draw(
+    command_buffer: vk::CommandBuffer,
+    device: &Device,
+    pipeline: &pipeline::Pipe,
+    framebuffer: &Framebuffer,
+    extent: vk::Extent2D,
+    vertex_buffer: &Buffer<Vertex>,
+    index_buffer: &Buffer<u32>,
+) {
+    let render_area = vk::Rect2D {
+        offset: vk::Offset2D { x: 0, y: 0 },
+        extent,
+    };
+
+    let mut encoder = CommandEncoder::new(command_buffer, device);
+    encoder.begin_render_pass(
+        &pipeline.renderpass,
+        framebuffer,
+        render_area,
+        &[vk::ClearValue {
+            color: vk::ClearColorValue {
+                float32: [0.0, 0.0, 0.0, 1.0],
+            },
+        }],
+        vk::SubpassContents::INLINE,
+    );
+    encoder.bind_pipeline(&pipeline.pipeline, 0);
+    encoder.set_viewport(render_area);
+    encoder.bind_vertex_buffers(0, &[(vertex_buffer, 0)]);
+    encoder.bind_index_buffer(index_buffer, 0);
+    encoder.draw_indexed(index_buffer.len() as u32, 1, 0, 0, 1);
+    encoder.end_render_pass();
+}
@@ -5,8 +5,8 @@ use std::mem;
 use std::path::Path;
 
 use vulkan::{
-    offset_of, prelude::*, Buffer, Descriptor, DescriptorSet, Framebuffer, Pipeline, Renderpass,
-    Shader, Swapchain, VkThread,
+    offset_of, prelude::*, Buffer, Descriptor, DescriptorSet, FramebufferAttachment,
+    FramebufferSet, Pipeline, RenderTarget, Renderpass, Shader, VkThread,
 };
 
 #[repr(C)]
@@ -26,25 +26,25 @@ pub struct Pipe {
     pub pipeline: Pipeline,
     pub renderpass: Renderpass,
     pub pipeline_descriptor: Descriptor,
-    pub uniform_buffer: Buffer,
+    pub uniform_buffer: Buffer<UniformBufferObject>,
     pub uniform_transform: UniformBufferObject,
-    pub framebuffers: Vec<Framebuffer>,
+    pub framebuffers: FramebufferSet,
 }
 
 impl Pipe {
-    //Creates a new pipeline
-    pub fn new(swapchain: &Swapchain, vulkan: &VkThread) -> Self {
+    //Creates a new pipeline rendering into `target`, a Swapchain or an OffscreenTarget
+    pub fn new<T: RenderTarget>(target: &T, vulkan: &VkThread) -> Self {
         let viewports = [vk::Viewport {
             x: 0.0,
             y: 0.0,
-            width: swapchain.extent.width as f32,
-            height: swapchain.extent.height as f32,
+            width: target.width() as f32,
+            height: target.height() as f32,
             min_depth: 0.0,
             max_depth: 1.0,
         }];
         let scissors = [vk::Rect2D {
             offset: vk::Offset2D { x: 0, y: 0 },
-            extent: swapchain.extent,
+            extent: target.extent(),
         }];
 
         let noop_stencil_state = vk::StencilOpState {
@@ -57,7 +57,7 @@ impl Pipe {
 
         //Create uniform buffer
 
-        let uniform_data = create_uniform_data(&swapchain);
+        let uniform_data = create_uniform_data(target.extent());
 
         let uniform_buffer =
             vulkan.create_gpu_buffer(vk::BufferUsageFlags::UNIFORM_BUFFER, &[uniform_data]);
@@ -81,14 +81,14 @@ impl Pipe {
             vk::RenderPassCreateInfo::builder()
                 .attachments(&[vk::AttachmentDescription {
                     flags: vk::AttachmentDescriptionFlags::empty(),
-                    format: swapchain.format,
+                    format: target.format(),
                     samples: vk::SampleCountFlags::TYPE_1,
                     load_op: vk::AttachmentLoadOp::CLEAR,
                     store_op: vk::AttachmentStoreOp::STORE,
                     stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
                     stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
                     initial_layout: vk::ImageLayout::UNDEFINED,
-                    final_layout: vk::ImageLayout::PRESENT_SRC_KHR,
+                    final_layout: target.final_layout(),
                 }])
                 .subpasses(&[vk::SubpassDescription::builder()
                     .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
@@ -212,22 +212,11 @@ impl Pipe {
                 .build(),
         );
 
-        let framebuffers: Vec<Framebuffer> = swapchain
-            .image_views
-            .iter()
-            .map(|image| {
-                Framebuffer::new(
-                    vk::FramebufferCreateInfo::builder()
-                        .layers(1)
-                        .render_pass(renderpass.pass())
-                        .attachments(&[*image])
-                        .width(swapchain.width())
-                        .height(swapchain.height())
-                        .build(),
-                    vulkan.context(),
-                )
-            })
-            .collect();
+        let framebuffers = FramebufferSet::new(
+            target,
+            renderpass.pass(),
+            vec![FramebufferAttachment::Swapchain],
+        );
 
         Self {
             pipeline,
@@ -240,7 +229,7 @@ impl Pipe {
     }
 }
 
-pub fn create_uniform_data(swapchain: &Swapchain) -> UniformBufferObject {
+pub fn create_uniform_data(extent: vk::Extent2D) -> UniformBufferObject {
     UniformBufferObject {
         view: Matrix4::look_at(
             Point3::new(2.0, 2.0, 2.0),
@@ -250,7 +239,7 @@ pub fn create_uniform_data(swapchain: &Swapchain) -> UniformBufferObject {
         proj: {
             let proj = cgmath::perspective(
                 Deg(45.0),
-                swapchain.extent.width as f32 / swapchain.extent.height as f32,
+                extent.width as f32 / extent.height as f32,
                 0.1,
                 10.0,
             );
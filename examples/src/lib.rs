@@ -1,11 +1,23 @@
 pub mod utils;
 
 use image::GenericImageView;
-use vulkan::{prelude::*, Buffer, Context, Image, Swapchain, VkThread};
+use vulkan::{
+    prelude::*, Buffer, Context, Image, RenderTarget, TextureFile, VertexInput, VkThread,
+};
 
-use std::{default::Default, path::Path, sync::Arc};
+use std::{
+    io::{self, Write},
+    path::Path,
+    sync::Arc,
+};
 
+//Loads KTX2 and DDS files with their own mips, anything else through the image crate
 pub fn create_texture(image_path: &Path, vulkan: &VkThread) -> Image {
+    if let Some("ktx2") | Some("dds") = image_path.extension().and_then(|ext| ext.to_str()) {
+        let file = TextureFile::open(image_path).expect("Failed to load texture file!");
+        return Image::from_texture_file(&file, vulkan).expect("Failed to upload texture file!");
+    }
+
     let mut image_object = image::open(image_path).unwrap(); // this function is slow in debug mode.
     image_object = image_object.flipv();
     let (image_width, image_height) = (image_object.width(), image_object.height());
@@ -24,146 +36,15 @@ pub fn create_texture(image_path: &Path, vulkan: &VkThread) -> Image {
         panic!("Failed to load texture image!")
     }
 
-    let buffer = Buffer::new_mapped_basic(
-        image_size,
-        vk::BufferUsageFlags::TRANSFER_SRC,
-        vk_mem::MemoryUsage::CpuOnly,
-        vulkan.context(),
-    );
-
-    buffer.upload_to_buffer::<u8>(&image_data, 0);
-
-    let image_create_info = vk::ImageCreateInfo {
-        s_type: vk::StructureType::IMAGE_CREATE_INFO,
-        image_type: vk::ImageType::TYPE_2D,
-        format: vk::Format::R8G8B8A8_UNORM,
-        extent: vk::Extent3D {
-            width: image_width,
-            height: image_height,
-            depth: 1,
-        },
-        mip_levels: 1,
-        array_layers: 1,
-        samples: vk::SampleCountFlags::TYPE_1,
-        tiling: vk::ImageTiling::OPTIMAL,
-        usage: vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED,
-        sharing_mode: vk::SharingMode::EXCLUSIVE,
-        ..Default::default()
-    };
-
-    let mut image = Image::create_image(
-        image_create_info,
-        vk_mem::MemoryUsage::GpuOnly,
-        vulkan.context(),
-    );
-
-    vulkan.apply_pipeline_barrier(
-        vk::PipelineStageFlags::TOP_OF_PIPE,
-        vk::PipelineStageFlags::TRANSFER,
-        vk::ImageMemoryBarrier {
-            s_type: vk::StructureType::IMAGE_MEMORY_BARRIER,
-            src_access_mask: vk::AccessFlags::empty(),
-            dst_access_mask: vk::AccessFlags::TRANSFER_WRITE,
-            old_layout: vk::ImageLayout::UNDEFINED,
-            new_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-            src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
-            dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
-            image: image.image(),
-            subresource_range: vk::ImageSubresourceRange {
-                aspect_mask: vk::ImageAspectFlags::COLOR,
-                base_mip_level: 0,
-                level_count: 1,
-                base_array_layer: 0,
-                layer_count: 1,
-            },
-            ..Default::default()
-        },
-    );
-
-    let buffer_image_regions = vec![vk::BufferImageCopy {
-        image_subresource: vk::ImageSubresourceLayers {
-            aspect_mask: vk::ImageAspectFlags::COLOR,
-            mip_level: 0,
-            base_array_layer: 0,
-            layer_count: 1,
-        },
-        image_extent: vk::Extent3D {
-            width: image_width,
-            height: image_height,
-            depth: 1,
-        },
-        buffer_offset: 0,
-        buffer_image_height: 0,
-        buffer_row_length: 0,
-        image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
-    }];
-
-    vulkan.copy_buffer_to_image(buffer.buffer, image.image(), buffer_image_regions);
-
-    vulkan.apply_pipeline_barrier(
-        vk::PipelineStageFlags::TRANSFER,
-        vk::PipelineStageFlags::FRAGMENT_SHADER,
-        vk::ImageMemoryBarrier {
-            s_type: vk::StructureType::IMAGE_MEMORY_BARRIER,
-            src_access_mask: vk::AccessFlags::TRANSFER_WRITE,
-            dst_access_mask: vk::AccessFlags::SHADER_READ,
-            old_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-            new_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
-            src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
-            dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
-            image: image.image(),
-            subresource_range: vk::ImageSubresourceRange {
-                aspect_mask: vk::ImageAspectFlags::COLOR,
-                base_mip_level: 0,
-                level_count: 1,
-                base_array_layer: 0,
-                layer_count: 1,
-            },
-            ..Default::default()
-        },
-    );
-
-    image.attach_view(vk::ImageViewCreateInfo {
-        s_type: vk::StructureType::IMAGE_VIEW_CREATE_INFO,
-        view_type: vk::ImageViewType::TYPE_2D,
-        format: vk::Format::R8G8B8A8_UNORM,
-        image: image.image(),
-        components: vk::ComponentMapping {
-            r: vk::ComponentSwizzle::IDENTITY,
-            g: vk::ComponentSwizzle::IDENTITY,
-            b: vk::ComponentSwizzle::IDENTITY,
-            a: vk::ComponentSwizzle::IDENTITY,
-        },
-        subresource_range: vk::ImageSubresourceRange {
-            aspect_mask: vk::ImageAspectFlags::COLOR,
-            base_mip_level: 0,
-            level_count: 1,
-            base_array_layer: 0,
-            layer_count: 1,
-        },
-        ..Default::default()
-    });
-
-    image.attach_sampler(vk::SamplerCreateInfo {
-        s_type: vk::StructureType::SAMPLER_CREATE_INFO,
-        mag_filter: vk::Filter::LINEAR,
-        min_filter: vk::Filter::LINEAR,
-        mipmap_mode: vk::SamplerMipmapMode::LINEAR,
-        address_mode_u: vk::SamplerAddressMode::REPEAT,
-        address_mode_v: vk::SamplerAddressMode::REPEAT,
-        address_mode_w: vk::SamplerAddressMode::REPEAT,
-        max_lod: 1.0,
-        mip_lod_bias: 0.0,
-        anisotropy_enable: vk::TRUE,
-        max_anisotropy: 16.0,
-        ..Default::default()
-    });
-
-    image
+    Image::from_rgba8(image_width, image_height, &image_data, vulkan)
 }
 
 //Creates depth image
-pub fn create_depth_resources(swapchain: &Swapchain, context: Arc<Context>) -> Image {
+pub fn create_depth_resources<T: RenderTarget>(
+    target: &T,
+    samples: vk::SampleCountFlags,
+    context: Arc<Context>,
+) -> Image {
     let depth_format = context.find_depth_format(
         &[
             vk::Format::D32_SFLOAT,
@@ -173,112 +54,89 @@ pub fn create_depth_resources(swapchain: &Swapchain, context: Arc<Context>) -> I
         vk::ImageTiling::OPTIMAL,
         vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT,
     );
-    let depth_image_info = vk::ImageCreateInfo {
-        s_type: vk::StructureType::IMAGE_CREATE_INFO,
-        image_type: vk::ImageType::TYPE_2D,
-        format: depth_format,
-        mip_levels: 1,
-        array_layers: 1,
-        samples: vk::SampleCountFlags::TYPE_1,
-        tiling: vk::ImageTiling::OPTIMAL,
-        usage: vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
-        sharing_mode: vk::SharingMode::EXCLUSIVE,
-        extent: vk::Extent3D {
-            width: swapchain.extent.width,
-            height: swapchain.extent.height,
-            depth: 1,
-        },
-        ..Default::default()
+
+    Image::attachment(
+        target.width(),
+        target.height(),
+        depth_format,
+        samples,
+        vk::ImageUsageFlags::empty(),
+        context,
+    )
+}
+
+//Creates multisampled color image, that gets resolved into the render target image
+pub fn create_color_resources<T: RenderTarget>(
+    target: &T,
+    samples: vk::SampleCountFlags,
+    context: Arc<Context>,
+) -> Image {
+    Image::attachment(
+        target.width(),
+        target.height(),
+        target.format(),
+        samples,
+        vk::ImageUsageFlags::TRANSIENT_ATTACHMENT,
+        context,
+    )
+}
+
+//Cycles 1x -> 2x -> 4x -> 8x MSAA, wraps around when device max is reached
+pub fn next_sample_count(
+    current: vk::SampleCountFlags,
+    max: vk::SampleCountFlags,
+) -> vk::SampleCountFlags {
+    let next = match current {
+        vk::SampleCountFlags::TYPE_1 => vk::SampleCountFlags::TYPE_2,
+        vk::SampleCountFlags::TYPE_2 => vk::SampleCountFlags::TYPE_4,
+        vk::SampleCountFlags::TYPE_4 => vk::SampleCountFlags::TYPE_8,
+        _ => vk::SampleCountFlags::TYPE_1,
     };
 
-    let mut image = Image::create_image(depth_image_info, vk_mem::MemoryUsage::GpuOnly, context);
+    if next.as_raw() > max.as_raw() {
+        vk::SampleCountFlags::TYPE_1
+    } else {
+        next
+    }
+}
 
-    image.attach_view(vk::ImageViewCreateInfo {
-        s_type: vk::StructureType::IMAGE_VIEW_CREATE_INFO,
-        view_type: vk::ImageViewType::TYPE_2D,
-        format: depth_format,
-        image: image.image(),
-        subresource_range: vk::ImageSubresourceRange {
-            aspect_mask: vk::ImageAspectFlags::DEPTH,
-            base_mip_level: 0,
-            level_count: 1,
-            base_array_layer: 0,
-            layer_count: 1,
-        },
-        ..Default::default()
-    });
+//Examples started with --memory log GPU memory statistics next to the FPS counter
+pub fn memory_log_requested() -> bool {
+    std::env::args().any(|arg| arg == "--memory")
+}
 
-    image
+//Examples started with --profile log GPU pass timings and write gpu_trace.json on exit
+pub fn profile_requested() -> bool {
+    std::env::args().any(|arg| arg == "--profile")
 }
 
-pub fn create_empty_image(vulkan: &VkThread) -> Image {
-    let mut image = Image::create_image(
-        vk::ImageCreateInfo {
-            s_type: vk::StructureType::IMAGE_CREATE_INFO,
-            image_type: vk::ImageType::TYPE_2D,
-            format: vk::Format::R8G8B8A8_UNORM,
-            extent: vk::Extent3D {
-                width: 1,
-                height: 1,
-                depth: 1,
-            },
-            mip_levels: 1,
-            array_layers: 1,
-            samples: vk::SampleCountFlags::TYPE_1,
-            tiling: vk::ImageTiling::OPTIMAL,
-            usage: vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED,
-            sharing_mode: vk::SharingMode::EXCLUSIVE,
-            ..Default::default()
-        },
-        vk_mem::MemoryUsage::GpuOnly,
-        vulkan.context(),
-    );
+//load_gltf started with --gpu-culling frustum culls on the GPU and draws indirectly
+pub fn gpu_culling_requested() -> bool {
+    std::env::args().any(|arg| arg == "--gpu-culling")
+}
 
-    vulkan.apply_pipeline_barrier(
-        vk::PipelineStageFlags::ALL_GRAPHICS,
-        vk::PipelineStageFlags::FRAGMENT_SHADER,
-        vk::ImageMemoryBarrier {
-            s_type: vk::StructureType::IMAGE_MEMORY_BARRIER,
-            src_access_mask: vk::AccessFlags::empty(),
-            dst_access_mask: vk::AccessFlags::SHADER_READ,
-            old_layout: vk::ImageLayout::UNDEFINED,
-            new_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
-            src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
-            dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
-            image: image.image(),
-            subresource_range: vk::ImageSubresourceRange {
-                aspect_mask: vk::ImageAspectFlags::COLOR,
-                base_mip_level: 0,
-                level_count: 1,
-                base_array_layer: 0,
-                layer_count: 1,
-            },
-            ..Default::default()
-        },
-    );
+//load_gltf started with --parallel records its draws on several threads
+pub fn parallel_recording_requested() -> bool {
+    std::env::args().any(|arg| arg == "--parallel")
+}
 
-    image.attach_sampler(vk::SamplerCreateInfo::default());
-    image.attach_view(vk::ImageViewCreateInfo {
-        s_type: vk::StructureType::IMAGE_VIEW_CREATE_INFO,
-        view_type: vk::ImageViewType::TYPE_2D,
-        format: vk::Format::R8G8B8A8_UNORM,
-        image: image.image(),
-        components: vk::ComponentMapping {
-            r: vk::ComponentSwizzle::IDENTITY,
-            g: vk::ComponentSwizzle::IDENTITY,
-            b: vk::ComponentSwizzle::IDENTITY,
-            a: vk::ComponentSwizzle::IDENTITY,
-        },
-        subresource_range: vk::ImageSubresourceRange {
-            aspect_mask: vk::ImageAspectFlags::COLOR,
-            base_mip_level: 0,
-            level_count: 1,
-            base_array_layer: 0,
-            layer_count: 1,
-        },
-        ..Default::default()
-    });
-    image
+//Examples started with --max-fps=<fps> limit their frame rate with FrameTimer::limit
+pub fn target_fps() -> Option<f32> {
+    std::env::args()
+        .find_map(|arg| arg.strip_prefix("--max-fps=").map(str::to_owned))
+        .and_then(|fps| fps.parse().ok())
+}
+
+//Rewrites the status line in place, stdout is line buffered and has to be flushed
+pub fn print_status(status: &str) {
+    let mut stdout = io::stdout();
+    let _ = write!(stdout, "{}\r", status);
+    let _ = stdout.flush();
+}
+
+//1x1 black texture bound where a scene has no textures
+pub fn create_empty_image(vulkan: &VkThread) -> Image {
+    Image::from_rgba8(1, 1, &[0, 0, 0, 255], vulkan)
 }
 
 pub const OPENGL_TO_VULKAN_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
@@ -286,14 +144,14 @@ pub const OPENGL_TO_VULKAN_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
 );
 
 #[repr(C)]
-#[derive(Clone, Debug, Copy)]
+#[derive(Clone, Debug, Copy, VertexInput)]
 pub struct Quad {
     pub position: [f32; 3],
     pub uv: [f32; 2],
 }
 
 impl Quad {
-    pub fn vertex_buffer(vulkan: &VkThread) -> Buffer {
+    pub fn vertex_buffer(vulkan: &VkThread) -> Buffer<Quad> {
         let vertices = vec![
             Quad {
                 position: [-1.0, -1.0, 0.0],
@@ -316,7 +174,7 @@ impl Quad {
         vulkan.create_gpu_buffer(vk::BufferUsageFlags::VERTEX_BUFFER, &vertices)
     }
 
-    pub fn index_buffer(vulkan: &VkThread) -> Buffer {
+    pub fn index_buffer(vulkan: &VkThread) -> Buffer<u32> {
         vulkan.create_gpu_buffer(vk::BufferUsageFlags::INDEX_BUFFER, &vec![0, 1, 2, 2, 3, 0])
     }
 }
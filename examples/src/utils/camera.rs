@@ -1,5 +1,6 @@
 use super::Event;
 use cgmath::{Deg, Matrix4, Vector3, Vector4};
+use vulkan::ShaderLayout;
 //From this ThinMatrix tutorial https://www.youtube.com/watch?v=PoxDDZmctnU
 #[derive(Clone, Debug, Copy)]
 pub struct Camera {
@@ -115,7 +116,8 @@ impl Camera {
 }
 
 #[repr(C)]
-#[derive(Clone, Debug, Copy)]
+#[derive(Clone, Debug, Copy, ShaderLayout)]
+#[layout = "std140"]
 pub struct CameraRaw {
     pub position: Vector4<f32>,
     pub view: Matrix4<f32>,
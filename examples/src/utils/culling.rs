@@ -0,0 +1,179 @@
+use super::gltf_importer::{Scene, Vertex};
+use cgmath::Matrix4;
+use std::mem;
+use vulkan::ShaderLayout;
+
+//Threads per workgroup of cull.comp
+pub const CULL_GROUP_SIZE: u32 = 64;
+
+//One primitive of a scene node, culled by cull.comp and read by the vertex shader
+//through gl_InstanceIndex, which the culling shader sets to the record index
+#[repr(C)]
+#[derive(Clone, Copy, Debug, ShaderLayout)]
+#[layout = "std430"]
+pub struct DrawRecord {
+    pub transform: [[f32; 4]; 4],
+    pub aabb_min: [f32; 4],
+    pub aabb_max: [f32; 4],
+    pub first_index: u32,
+    pub index_count: u32,
+    pub vertex_offset: i32,
+    pub material: i32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, ShaderLayout)]
+#[layout = "std430"]
+pub struct CullPushConstant {
+    pub planes: [[f32; 4]; 6],
+    pub record_count: u32,
+    //std430 rounds the block up to the vec4 alignment of planes, [u32; 3] would be a uvec3
+    pub padding_0: u32,
+    pub padding_1: u32,
+    pub padding_2: u32,
+}
+
+//Draw records of every mesh node, offsets are in elements of the shared scene buffers
+pub fn draw_records(scene: &Scene) -> Vec<DrawRecord> {
+    scene
+        .nodes
+        .iter()
+        .filter_map(|node| node.mesh_index.map(|mesh| (node, scene.get_mesh(mesh))))
+        .flat_map(|(node, mesh)| {
+            mesh.primitives
+                .iter()
+                .filter(|primitive| primitive.indices_len > 0)
+                .map(move |primitive| {
+                    let [min, max] = world_bounds(&node.transform_matrix, primitive.bounds);
+                    DrawRecord {
+                        transform: node.transform_matrix.into(),
+                        aabb_min: [min[0], min[1], min[2], 1.0],
+                        aabb_max: [max[0], max[1], max[2], 1.0],
+                        first_index: primitive.indice_offset / mem::size_of::<u32>() as u32,
+                        index_count: primitive.indices_len as u32,
+                        vertex_offset: (primitive.vertex_offset / mem::size_of::<Vertex>()) as i32,
+                        material: primitive.material_id.unwrap_or(-1) as i32,
+                    }
+                })
+        })
+        .collect()
+}
+
+//World space box enclosing the transformed local box
+pub fn world_bounds(transform: &Matrix4<f32>, bounds: [[f32; 3]; 2]) -> [[f32; 3]; 2] {
+    let [min, max] = bounds;
+    let center = [0, 1, 2].map(|i| (min[i] + max[i]) * 0.5);
+    let extent = [0, 1, 2].map(|i| (max[i] - min[i]) * 0.5);
+
+    let mut world = [[0.0; 3]; 2];
+    for row in 0..3 {
+        let mut world_center = transform[3][row];
+        let mut world_extent = 0.0;
+        for column in 0..3 {
+            world_center += transform[column][row] * center[column];
+            world_extent += transform[column][row].abs() * extent[column];
+        }
+        world[0][row] = world_center - world_extent;
+        world[1][row] = world_center + world_extent;
+    }
+    world
+}
+
+//Normalized left, right, bottom, top, near and far planes of a Vulkan (0..1 depth)
+//projection times view matrix, points inside have a positive distance to all of them
+pub fn frustum_planes(view_proj: Matrix4<f32>) -> [[f32; 4]; 6] {
+    let row = |i: usize| {
+        [
+            view_proj[0][i],
+            view_proj[1][i],
+            view_proj[2][i],
+            view_proj[3][i],
+        ]
+    };
+    let add = |a: [f32; 4], b: [f32; 4]| [a[0] + b[0], a[1] + b[1], a[2] + b[2], a[3] + b[3]];
+    let sub = |a: [f32; 4], b: [f32; 4]| [a[0] - b[0], a[1] - b[1], a[2] - b[2], a[3] - b[3]];
+
+    let w = row(3);
+    [
+        add(w, row(0)),
+        sub(w, row(0)),
+        add(w, row(1)),
+        sub(w, row(1)),
+        row(2),
+        sub(w, row(2)),
+    ]
+    .map(|plane| {
+        let length = (plane[0] * plane[0] + plane[1] * plane[1] + plane[2] * plane[2]).sqrt();
+        plane.map(|value| value / length)
+    })
+}
+
+//CPU version of the test in cull.comp: the box is culled when its corner furthest
+//along a plane normal is still outside of that plane
+pub fn is_visible(planes: &[[f32; 4]; 6], bounds: [[f32; 3]; 2]) -> bool {
+    planes.iter().all(|plane| {
+        let corner = [0, 1, 2].map(|i| {
+            if plane[i] >= 0.0 {
+                bounds[1][i]
+            } else {
+                bounds[0][i]
+            }
+        });
+        plane[0] * corner[0] + plane[1] * corner[1] + plane[2] * corner[2] + plane[3] >= 0.0
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{Deg, Matrix4, Point3, Vector3};
+
+    fn planes() -> [[f32; 4]; 6] {
+        let view = Matrix4::look_at(
+            Point3::new(0.0, 0.0, 5.0),
+            Point3::new(0.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+        );
+        let proj = crate::OPENGL_TO_VULKAN_MATRIX * cgmath::perspective(Deg(45.0), 1.0, 0.1, 50.0);
+        frustum_planes(proj * view)
+    }
+
+    #[test]
+    fn box_in_front_of_camera_is_visible() {
+        assert!(is_visible(&planes(), [[-1.0; 3], [1.0; 3]]));
+    }
+
+    #[test]
+    fn boxes_outside_of_frustum_are_culled() {
+        //Behind the camera, past the far plane and far to the side
+        assert!(!is_visible(&planes(), [[-1.0, -1.0, 8.0], [1.0, 1.0, 9.0]]));
+        assert!(!is_visible(
+            &planes(),
+            [[-1.0, -1.0, -80.0], [1.0, 1.0, -60.0]]
+        ));
+        assert!(!is_visible(
+            &planes(),
+            [[40.0, -1.0, -1.0], [42.0, 1.0, 1.0]]
+        ));
+    }
+
+    #[test]
+    fn box_crossing_a_plane_is_visible() {
+        assert!(is_visible(
+            &planes(),
+            [[-100.0, -1.0, -1.0], [0.0, 1.0, 1.0]]
+        ));
+    }
+
+    #[test]
+    fn world_bounds_follow_translation_and_rotation() {
+        let transform = Matrix4::from_translation(Vector3::new(10.0, 0.0, 0.0))
+            * Matrix4::from_angle_z(Deg(90.0));
+        let [min, max] = world_bounds(&transform, [[0.0, 0.0, 0.0], [2.0, 1.0, 1.0]]);
+
+        let expected = [[9.0, 0.0, 0.0], [10.0, 2.0, 1.0]];
+        for (actual, expected) in min.iter().chain(&max).zip(expected.iter().flatten()) {
+            assert!((actual - expected).abs() < 1e-5);
+        }
+    }
+}
@@ -5,7 +5,7 @@ use gltf::{
 };
 
 use std::{path::Path, sync::Arc};
-use vulkan::{prelude::*, Buffer, Image, VkThread};
+use vulkan::{prelude::*, Buffer, Image, ShaderLayout, VertexInput, VkThread};
 
 pub struct Importer {
     doc: gltf::Document,
@@ -19,19 +19,19 @@ pub struct Scene {
     pub textures: Vec<Image>,
     pub materials: Vec<Material>,
     pub lights: Vec<Light>,
-    pub vertices: Arc<Buffer>,
-    pub indices: Arc<Buffer>,
+    pub vertices: Arc<Buffer<Vertex>>,
+    pub indices: Arc<Buffer<u32>>,
     pub indices_len: u32,
 }
 #[repr(C)]
-#[derive(Clone, Debug, Copy)]
+#[derive(Clone, Debug, Copy, VertexInput)]
 pub struct Vertex {
     pub position: [f32; 3],
     pub color: [f32; 4],
     pub tangents: [f32; 4],
     pub normal: [f32; 3],
     pub uv: [f32; 2],
-    pub material_id: isize,
+    pub material_id: i32,
 }
 
 #[derive(Debug, Clone)]
@@ -53,6 +53,8 @@ pub struct Primitive {
     pub vertice_len: usize,
     pub indices_len: usize,
     pub primitive_topology: vk::PrimitiveTopology,
+    //Local space minimum and maximum corner of the positions
+    pub bounds: [[f32; 3]; 2],
 }
 #[allow(dead_code)]
 pub struct Mesh {
@@ -62,7 +64,8 @@ pub struct Mesh {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, ShaderLayout)]
+#[layout = "std140"]
 pub struct Light {
     pub position: [f32; 4],
     pub color: [f32; 3],
@@ -73,9 +76,12 @@ pub struct Light {
     pub outer_cone_angle: f32,
 }
 
-#[derive(Clone, Copy, Debug)]
+//GLSL places every struct member of a std140 block on a 16 byte boundary
+#[repr(C, align(16))]
+#[derive(Clone, Copy, Debug, ShaderLayout)]
+#[layout = "std140"]
 pub struct TextureInfo {
-    index: isize,
+    index: i32,
     channel: u32,
 }
 
@@ -102,7 +108,8 @@ pub struct Material {
 }
 
 #[repr(C)]
-#[derive(Clone, Debug, Copy)]
+#[derive(Clone, Debug, Copy, ShaderLayout)]
+#[layout = "std140"]
 pub struct MaterialRaw {
     pub base_color: [f32; 4],
     pub color: [f32; 4],
@@ -241,7 +248,7 @@ impl<'a> From<GltfMaterial<'a>> for Material {
                     channel: 0,
                 },
                 |tex_info| TextureInfo {
-                    index: tex_info.texture().index() as isize,
+                    index: tex_info.texture().index() as i32,
                     channel: tex_info.tex_coord(),
                 },
             )
@@ -254,7 +261,7 @@ impl<'a> From<GltfMaterial<'a>> for Material {
                     channel: 0,
                 },
                 |tex_info| TextureInfo {
-                    index: tex_info.texture().index() as isize,
+                    index: tex_info.texture().index() as i32,
                     channel: tex_info.tex_coord(),
                 },
             )
@@ -271,7 +278,7 @@ impl<'a> From<GltfMaterial<'a>> for Material {
                     channel: 0,
                 },
                 |tex_info| TextureInfo {
-                    index: tex_info.texture().index() as isize,
+                    index: tex_info.texture().index() as i32,
                     channel: tex_info.tex_coord(),
                 },
             );
@@ -410,6 +417,7 @@ impl Importer {
             if sampler_index.is_some() {
                 textures[image_index].attach_sampler(samplers[sampler_index.unwrap()])
             } else {
+                let mip_levels = textures[image_index].mip_levels();
                 textures[image_index].attach_sampler(vk::SamplerCreateInfo {
                     s_type: vk::StructureType::SAMPLER_CREATE_INFO,
                     mag_filter: vk::Filter::LINEAR,
@@ -418,7 +426,8 @@ impl Importer {
                     address_mode_u: vk::SamplerAddressMode::REPEAT,
                     address_mode_v: vk::SamplerAddressMode::REPEAT,
                     address_mode_w: vk::SamplerAddressMode::REPEAT,
-                    max_lod: 1.0,
+                    min_lod: 0.0,
+                    max_lod: mip_levels as f32,
                     mip_lod_bias: 0.0,
                     anisotropy_enable: vk::TRUE,
                     max_anisotropy: 16.0,
@@ -512,13 +521,24 @@ impl Importer {
                             color: *colors.get(index).unwrap_or(&[1.0, 1.0, 1.0, 1.0]),
                             uv: *uvs.get(index).unwrap_or(&[0.0, 0.0]),
                             normal: *normals.get(index).unwrap_or(&[1.0, 1.0, 1.0]),
-                            material_id: material_id.unwrap_or(-1),
+                            material_id: material_id.map_or(-1, |id| id as i32),
                             tangents: *tangents.get(index).unwrap_or(&[0.0, 0.0, 0.0, 0.0]),
                         })
                         .collect();
 
                     vertices_data.extend_from_slice(&vertices);
 
+                    let bounds = vertices.iter().fold(
+                        [[f32::MAX; 3], [f32::MIN; 3]],
+                        |[min, max], vertex| {
+                            let p = vertex.position;
+                            [
+                                [min[0].min(p[0]), min[1].min(p[1]), min[2].min(p[2])],
+                                [max[0].max(p[0]), max[1].max(p[1]), max[2].max(p[2])],
+                            ]
+                        },
+                    );
+
                     let mut indice_offset: u32 = 0;
                     let mut indices_len: usize = 0;
                     if indices.is_some() {
@@ -536,6 +556,7 @@ impl Importer {
                         material_id,
                         vertice_len: vertices.len(),
                         primitive_topology,
+                        bounds,
                     }
                 })
                 .collect();
@@ -594,23 +615,40 @@ fn build_sampler(sampler: &gltf::texture::Sampler) -> vk::SamplerCreateInfo {
         }
     };
 
-    fn min_filter_mimap_filter(min_filter: MinFilter) -> (vk::Filter, vk::SamplerMipmapMode) {
+    //Filters without mipmaps sample level 0 only, clamping max_lod to 0.25 keeps
+    //the min filter working as described in the Vulkan spec
+    fn min_filter_mimap_filter(min_filter: MinFilter) -> (vk::Filter, vk::SamplerMipmapMode, f32) {
         match min_filter {
-            MinFilter::Linear => (vk::Filter::LINEAR, vk::SamplerMipmapMode::LINEAR),
-            MinFilter::Nearest => (vk::Filter::NEAREST, vk::SamplerMipmapMode::NEAREST),
-            MinFilter::LinearMipmapLinear => (vk::Filter::LINEAR, vk::SamplerMipmapMode::LINEAR),
-            MinFilter::LinearMipmapNearest => (vk::Filter::LINEAR, vk::SamplerMipmapMode::NEAREST),
-            MinFilter::NearestMipmapNearest => {
-                (vk::Filter::NEAREST, vk::SamplerMipmapMode::NEAREST)
-            }
-            MinFilter::NearestMipmapLinear => (vk::Filter::NEAREST, vk::SamplerMipmapMode::LINEAR),
+            MinFilter::Linear => (vk::Filter::LINEAR, vk::SamplerMipmapMode::NEAREST, 0.25),
+            MinFilter::Nearest => (vk::Filter::NEAREST, vk::SamplerMipmapMode::NEAREST, 0.25),
+            MinFilter::LinearMipmapLinear => (
+                vk::Filter::LINEAR,
+                vk::SamplerMipmapMode::LINEAR,
+                vk::LOD_CLAMP_NONE,
+            ),
+            MinFilter::LinearMipmapNearest => (
+                vk::Filter::LINEAR,
+                vk::SamplerMipmapMode::NEAREST,
+                vk::LOD_CLAMP_NONE,
+            ),
+            MinFilter::NearestMipmapNearest => (
+                vk::Filter::NEAREST,
+                vk::SamplerMipmapMode::NEAREST,
+                vk::LOD_CLAMP_NONE,
+            ),
+            MinFilter::NearestMipmapLinear => (
+                vk::Filter::NEAREST,
+                vk::SamplerMipmapMode::LINEAR,
+                vk::LOD_CLAMP_NONE,
+            ),
         }
     }
 
-    let (min_filter, mipmap_filter) = min_filter_mimap_filter(
+    //Samplers are shared between images, LOD_CLAMP_NONE leaves the clamp to the image's mip count
+    let (min_filter, mipmap_filter, max_lod) = min_filter_mimap_filter(
         sampler
             .min_filter()
-            .unwrap_or(gltf::texture::MinFilter::Nearest),
+            .unwrap_or(gltf::texture::MinFilter::LinearMipmapLinear),
     );
 
     let mag_filter = match sampler
@@ -628,7 +666,8 @@ fn build_sampler(sampler: &gltf::texture::Sampler) -> vk::SamplerCreateInfo {
         address_mode_u: address_mode(sampler.wrap_s()),
         address_mode_v: address_mode(sampler.wrap_t()),
         address_mode_w: vk::SamplerAddressMode::CLAMP_TO_EDGE,
-        max_lod: 1.0,
+        min_lod: 0.0,
+        max_lod,
         mip_lod_bias: 0.0,
         anisotropy_enable: vk::TRUE,
         max_anisotropy: 16.0,
@@ -638,178 +677,43 @@ fn build_sampler(sampler: &gltf::texture::Sampler) -> vk::SamplerCreateInfo {
 
 fn create_texture_image(properties: &gltf::image::Data, vulkan: &VkThread) -> Image {
     use gltf::image::Format;
-    use image::{Bgr, Bgra, ConvertBuffer, ImageBuffer, Rgb, Rgba};
-    let format = vk::Format::R8G8B8A8_UNORM;
-    type RgbaImage = ImageBuffer<Rgba<u8>, Vec<u8>>;
-    type BgraImage = ImageBuffer<Bgra<u8>, Vec<u8>>;
-    type RgbImage = ImageBuffer<Rgb<u8>, Vec<u8>>;
-    type BgrImage = ImageBuffer<Bgr<u8>, Vec<u8>>;
-
-    //Convert image format to R8G8B8A8_UNORM
-    let data = match properties.format {
-        Format::R8 | Format::R8G8 | Format::R8G8B8 => {
-            let rgba: RgbaImage = RgbImage::from_raw(
-                properties.width,
-                properties.height,
-                properties.pixels.clone(),
-            )
-            .unwrap()
-            .convert();
-
-            rgba.into_raw()
-        }
-        Format::B8G8R8 => {
-            let bgra: RgbaImage = BgrImage::from_raw(
-                properties.width,
-                properties.height,
-                properties.pixels.clone(),
-            )
-            .unwrap()
-            .convert();
 
-            bgra.into_raw()
-        }
-        Format::B8G8R8A8 => {
-            let bgra: RgbaImage = BgraImage::from_raw(
-                properties.width,
-                properties.height,
-                properties.pixels.clone(),
-            )
-            .unwrap()
-            .convert();
-
-            bgra.into_raw()
-        }
-        Format::R8G8B8A8 => properties.pixels.clone(),
-        _ => {
-            panic!("Unsupported texture format: {:?}", properties.format);
-        }
+    //Channel count, bytes per channel and whether red and blue are swapped
+    let (channels, channel_size, bgr) = match properties.format {
+        Format::R8 => (1, 1, false),
+        Format::R8G8 => (2, 1, false),
+        Format::R8G8B8 => (3, 1, false),
+        Format::R8G8B8A8 => (4, 1, false),
+        Format::B8G8R8 => (3, 1, true),
+        Format::B8G8R8A8 => (4, 1, true),
+        Format::R16 => (1, 2, false),
+        Format::R16G16 => (2, 2, false),
+        Format::R16G16B16 => (3, 2, false),
+        Format::R16G16B16A16 => (4, 2, false),
     };
 
-    let image_size = (std::mem::size_of::<u8>() as u32 * properties.width * properties.height * 4)
-        as vk::DeviceSize;
-
-    let buffer = Buffer::new_mapped_basic(
-        image_size,
-        vk::BufferUsageFlags::TRANSFER_SRC,
-        vk_mem::MemoryUsage::CpuOnly,
-        vulkan.context(),
-    );
-
-    buffer.upload_to_buffer::<u8>(&data, 0);
-    let mut image = Image::create_image(
-        vk::ImageCreateInfo {
-            s_type: vk::StructureType::IMAGE_CREATE_INFO,
-            image_type: vk::ImageType::TYPE_2D,
-            format,
-            extent: vk::Extent3D {
-                width: properties.width,
-                height: properties.height,
-                depth: 1,
-            },
-            mip_levels: 1,
-            array_layers: 1,
-            samples: vk::SampleCountFlags::TYPE_1,
-            tiling: vk::ImageTiling::OPTIMAL,
-            usage: vk::ImageUsageFlags::TRANSFER_SRC
-                | vk::ImageUsageFlags::TRANSFER_DST
-                | vk::ImageUsageFlags::SAMPLED,
-            sharing_mode: vk::SharingMode::EXCLUSIVE,
-            ..Default::default()
-        },
-        vk_mem::MemoryUsage::GpuOnly,
-        vulkan.context(),
-    );
-
-    vulkan.apply_pipeline_barrier(
-        vk::PipelineStageFlags::TOP_OF_PIPE,
-        vk::PipelineStageFlags::TRANSFER,
-        vk::ImageMemoryBarrier {
-            s_type: vk::StructureType::IMAGE_MEMORY_BARRIER,
-            src_access_mask: vk::AccessFlags::empty(),
-            dst_access_mask: vk::AccessFlags::TRANSFER_WRITE,
-            old_layout: vk::ImageLayout::UNDEFINED,
-            new_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-            src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
-            dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
-            image: image.image(),
-            subresource_range: vk::ImageSubresourceRange {
-                aspect_mask: vk::ImageAspectFlags::COLOR,
-                base_mip_level: 0,
-                level_count: 1,
-                base_array_layer: 0,
-                layer_count: 1,
-            },
-            ..Default::default()
-        },
-    );
-
-    vulkan.copy_buffer_to_image(
-        buffer.buffer,
-        image.image(),
-        vec![vk::BufferImageCopy {
-            image_subresource: vk::ImageSubresourceLayers {
-                aspect_mask: vk::ImageAspectFlags::COLOR,
-                mip_level: 0,
-                base_array_layer: 0,
-                layer_count: 1,
-            },
-            image_extent: vk::Extent3D {
-                width: properties.width,
-                height: properties.height,
-                depth: 1,
-            },
-            buffer_offset: 0,
-            buffer_image_height: 0,
-            buffer_row_length: 0,
-            image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
-        }],
-    );
-
-
-    vulkan.apply_pipeline_barrier(
-        vk::PipelineStageFlags::TRANSFER,
-        vk::PipelineStageFlags::FRAGMENT_SHADER,
-        vk::ImageMemoryBarrier {
-            s_type: vk::StructureType::IMAGE_MEMORY_BARRIER,
-            src_access_mask: vk::AccessFlags::TRANSFER_WRITE,
-            dst_access_mask: vk::AccessFlags::SHADER_READ,
-            old_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-            new_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
-            src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
-            dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
-            image: image.image(),
-            subresource_range: vk::ImageSubresourceRange {
-                aspect_mask: vk::ImageAspectFlags::COLOR,
-                base_mip_level: 0,
-                level_count: 1,
-                base_array_layer: 0,
-                layer_count: 1,
-            },
-            ..Default::default()
-        },
-    );
-
-    image.attach_view(vk::ImageViewCreateInfo {
-        s_type: vk::StructureType::IMAGE_VIEW_CREATE_INFO,
-        view_type: vk::ImageViewType::TYPE_2D,
-        format,
-        image: image.image(),
-        components: vk::ComponentMapping {
-            r: vk::ComponentSwizzle::IDENTITY,
-            g: vk::ComponentSwizzle::IDENTITY,
-            b: vk::ComponentSwizzle::IDENTITY,
-            a: vk::ComponentSwizzle::IDENTITY,
-        },
-        subresource_range: vk::ImageSubresourceRange {
-            aspect_mask: vk::ImageAspectFlags::COLOR,
-            base_mip_level: 0,
-            level_count: 1,
-            base_array_layer: 0,
-            layer_count: 1,
-        },
-        ..Default::default()
-    });
+    //Convert image format to R8G8B8A8_UNORM, 16 bit channels keep their high byte
+    let data: Vec<u8> = properties
+        .pixels
+        .chunks_exact(channels * channel_size)
+        .flat_map(|texel| {
+            let channel = |index: usize| match channel_size {
+                1 => texel[index],
+                _ => (u16::from_ne_bytes([texel[index * 2], texel[index * 2 + 1]]) >> 8) as u8,
+            };
+            let rgba = match channels {
+                1 => [channel(0), channel(0), channel(0), 255],
+                2 => [channel(0), channel(1), 0, 255],
+                3 => [channel(0), channel(1), channel(2), 255],
+                _ => [channel(0), channel(1), channel(2), channel(3)],
+            };
+            if bgr {
+                vec![rgba[2], rgba[1], rgba[0], rgba[3]]
+            } else {
+                rgba.to_vec()
+            }
+        })
+        .collect();
 
-    image
+    Image::from_rgba8(properties.width, properties.height, &data, vulkan)
 }
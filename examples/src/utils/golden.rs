@@ -0,0 +1,271 @@
+//Golden image regression harness. Examples started with --golden render one frame headless
+//at a fixed size, camera and time, and compare it against tests/golden/<name>.png.
+//A missing reference fails the check, UPDATE_GOLDEN=1 writes or overwrites them.
+use image::{ImageBuffer, Rgba, RgbaImage};
+use std::{env, fs, path::PathBuf, sync::Arc};
+use vulkan::{prelude::*, Context, OffscreenTarget, PipelineType, VkThread};
+
+pub const WIDTH: u32 = 320;
+pub const HEIGHT: u32 = 240;
+pub const FORMAT: vk::Format = vk::Format::R8G8B8A8_UNORM;
+//Animations are evaluated at this time instead of the frame delta
+pub const TIME: f32 = 0.5;
+
+//Largest per channel difference that still counts as the same pixel
+pub const CHANNEL_TOLERANCE: u8 = 3;
+//Share of pixels allowed to exceed CHANNEL_TOLERANCE, covers rasterization differences on edges
+pub const MAX_MISMATCH_RATIO: f64 = 0.005;
+//Lowest mean structural similarity (SSIM) of the luma channel
+pub const MIN_SSIM: f64 = 0.98;
+
+const SSIM_WINDOW: u32 = 8;
+
+pub fn requested() -> bool {
+    env::args().any(|arg| arg == "--golden")
+}
+
+//Headless context, a draw thread and the target to render the golden frame into
+pub fn setup(name: &str) -> (Arc<Context>, VkThread, OffscreenTarget) {
+    let context = Arc::new(Context::headless(name, false));
+    let vulkan = VkThread::new(PipelineType::Draw, context.clone());
+
+    let depth_format = context.find_depth_format(
+        &[
+            vk::Format::D32_SFLOAT,
+            vk::Format::D32_SFLOAT_S8_UINT,
+            vk::Format::D24_UNORM_S8_UINT,
+        ],
+        vk::ImageTiling::OPTIMAL,
+        vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT,
+    );
+
+    let target = OffscreenTarget::new(
+        vk::Extent2D {
+            width: WIDTH,
+            height: HEIGHT,
+        },
+        FORMAT,
+        Some(depth_format),
+        context.clone(),
+    );
+
+    (context, vulkan, target)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Comparison {
+    pub mismatched_pixels: usize,
+    pub mismatch_ratio: f64,
+    pub max_difference: u8,
+    pub ssim: f64,
+}
+
+impl Comparison {
+    pub fn passed(&self) -> bool {
+        self.mismatch_ratio <= MAX_MISMATCH_RATIO && self.ssim >= MIN_SSIM
+    }
+}
+
+pub fn compare(actual: &RgbaImage, reference: &RgbaImage) -> Comparison {
+    assert_eq!(
+        actual.dimensions(),
+        reference.dimensions(),
+        "Golden image sizes differ!"
+    );
+
+    let mut mismatched_pixels = 0;
+    let mut max_difference = 0;
+    for (actual, reference) in actual.pixels().zip(reference.pixels()) {
+        let difference = pixel_difference(actual, reference);
+        max_difference = max_difference.max(difference);
+        if difference > CHANNEL_TOLERANCE {
+            mismatched_pixels += 1;
+        }
+    }
+
+    let (width, height) = actual.dimensions();
+    Comparison {
+        mismatched_pixels,
+        mismatch_ratio: mismatched_pixels as f64 / (width * height) as f64,
+        max_difference,
+        ssim: ssim(actual, reference),
+    }
+}
+
+//Renders pixels over the tolerance red on top of the dimmed reference
+pub fn diff_image(actual: &RgbaImage, reference: &RgbaImage) -> RgbaImage {
+    let (width, height) = reference.dimensions();
+    ImageBuffer::from_fn(width, height, |x, y| {
+        let actual = actual.get_pixel(x, y);
+        let reference = reference.get_pixel(x, y);
+        if pixel_difference(actual, reference) > CHANNEL_TOLERANCE {
+            Rgba([255, 0, 0, 255])
+        } else {
+            let gray = (luma(reference) * 0.3) as u8;
+            Rgba([gray, gray, gray, 255])
+        }
+    })
+}
+
+//Compares the rendered RGBA8 pixels with the reference, writes the frame and a diff image
+//into target/golden on failure. Returns false when the frame does not match.
+pub fn check(name: &str, pixels: Vec<u8>) -> bool {
+    let actual: RgbaImage =
+        ImageBuffer::from_raw(WIDTH, HEIGHT, pixels).expect("Golden frame has the wrong size!");
+    let reference_path = reference_dir().join(format!("{}.png", name));
+
+    if env::var("UPDATE_GOLDEN").is_ok() {
+        fs::create_dir_all(reference_dir()).expect("Failed to create golden directory!");
+        actual
+            .save(&reference_path)
+            .expect("Failed to save golden reference!");
+        println!("{}: wrote reference {:?}", name, reference_path);
+        return true;
+    }
+
+    if !reference_path.exists() {
+        println!(
+            "{}: missing reference {:?}, run with UPDATE_GOLDEN=1 to create it",
+            name, reference_path
+        );
+        return false;
+    }
+
+    let reference = image::open(&reference_path)
+        .expect("Failed to load golden reference!")
+        .to_rgba();
+    let comparison = compare(&actual, &reference);
+    println!("{}: {:?}", name, comparison);
+
+    if comparison.passed() {
+        return true;
+    }
+
+    fs::create_dir_all(output_dir()).expect("Failed to create golden output directory!");
+    let actual_path = output_dir().join(format!("{}.png", name));
+    let diff_path = output_dir().join(format!("{}.diff.png", name));
+    actual
+        .save(&actual_path)
+        .expect("Failed to save golden frame!");
+    diff_image(&actual, &reference)
+        .save(&diff_path)
+        .expect("Failed to save golden diff!");
+    println!(
+        "{}: does not match reference, see {:?} and {:?}",
+        name, actual_path, diff_path
+    );
+    false
+}
+
+//Exits with an error code when the frame does not match, used by the example binaries
+pub fn finish(name: &str, pixels: Vec<u8>) {
+    if !check(name, pixels) {
+        std::process::exit(1);
+    }
+}
+
+fn reference_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+fn output_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../target/golden")
+}
+
+fn pixel_difference(a: &Rgba<u8>, b: &Rgba<u8>) -> u8 {
+    a.0.iter()
+        .zip(b.0.iter())
+        .map(|(a, b)| a.max(b) - a.min(b))
+        .max()
+        .unwrap_or(0)
+}
+
+fn luma(pixel: &Rgba<u8>) -> f64 {
+    0.299 * pixel.0[0] as f64 + 0.587 * pixel.0[1] as f64 + 0.114 * pixel.0[2] as f64
+}
+
+//Mean SSIM over non overlapping windows of the luma channel
+fn ssim(a: &RgbaImage, b: &RgbaImage) -> f64 {
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+    let (width, height) = a.dimensions();
+    let mut total = 0.0;
+    let mut windows = 0;
+
+    for window_y in (0..height).step_by(SSIM_WINDOW as usize) {
+        for window_x in (0..width).step_by(SSIM_WINDOW as usize) {
+            let mut samples = Vec::new();
+            for y in window_y..(window_y + SSIM_WINDOW).min(height) {
+                for x in window_x..(window_x + SSIM_WINDOW).min(width) {
+                    samples.push((luma(a.get_pixel(x, y)), luma(b.get_pixel(x, y))));
+                }
+            }
+
+            let count = samples.len() as f64;
+            let mean_a = samples.iter().map(|(a, _)| a).sum::<f64>() / count;
+            let mean_b = samples.iter().map(|(_, b)| b).sum::<f64>() / count;
+            let (mut variance_a, mut variance_b, mut covariance) = (0.0, 0.0, 0.0);
+            for (a, b) in samples.iter() {
+                variance_a += (a - mean_a) * (a - mean_a);
+                variance_b += (b - mean_b) * (b - mean_b);
+                covariance += (a - mean_a) * (b - mean_b);
+            }
+            variance_a /= count;
+            variance_b /= count;
+            covariance /= count;
+
+            total += ((2.0 * mean_a * mean_b + C1) * (2.0 * covariance + C2))
+                / ((mean_a * mean_a + mean_b * mean_b + C1) * (variance_a + variance_b + C2));
+            windows += 1;
+        }
+    }
+
+    total / windows as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient() -> RgbaImage {
+        ImageBuffer::from_fn(64, 64, |x, y| {
+            Rgba([(x * 4) as u8, (y * 4) as u8, 128, 255])
+        })
+    }
+
+    #[test]
+    fn identical_images_pass() {
+        let comparison = compare(&gradient(), &gradient());
+        assert_eq!(comparison.mismatched_pixels, 0);
+        assert!((comparison.ssim - 1.0).abs() < 1e-9);
+        assert!(comparison.passed());
+    }
+
+    #[test]
+    fn small_noise_is_tolerated() {
+        let mut noisy = gradient();
+        for (index, pixel) in noisy.pixels_mut().enumerate() {
+            pixel.0[0] = pixel.0[0].saturating_add((index % 3) as u8);
+        }
+        assert!(compare(&noisy, &gradient()).passed());
+    }
+
+    #[test]
+    fn changed_region_fails_and_shows_in_diff() {
+        let mut changed = gradient();
+        for y in 0..16 {
+            for x in 0..16 {
+                changed.put_pixel(x, y, Rgba([255, 255, 255, 255]));
+            }
+        }
+
+        let comparison = compare(&changed, &gradient());
+        assert!(!comparison.passed());
+        assert_eq!(comparison.mismatched_pixels, 256);
+        assert_eq!(
+            diff_image(&changed, &gradient()).get_pixel(0, 0).0,
+            [255, 0, 0, 255]
+        );
+    }
+}
@@ -1,5 +1,7 @@
 mod camera;
+pub mod culling;
 pub mod events;
+pub mod golden;
 pub mod gltf_importer;
 
 pub use camera::{Camera, CameraRaw};
@@ -0,0 +1,83 @@
+//Dispatches tests/shaders/multiply.comp through ComputePipeline and checks the results.
+//Needs a Vulkan device, software implementations like lavapipe or SwiftShader are enough.
+//Run with `cargo test -p examples --test compute -- --ignored`.
+use std::{mem, path::Path, sync::Arc};
+use vulkan::{
+    prelude::*, ComputePipeline, Context, Descriptor, DescriptorSet, PipelineType, StorageBuffer,
+    VkThread,
+};
+
+const LOCAL_SIZE: u32 = 64;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Constants {
+    count: u32,
+    factor: u32,
+}
+
+fn multiply(input: &[u32], count: u32, factor: u32) -> Vec<u32> {
+    let context = Arc::new(Context::headless("compute test", false));
+    let vulkan = VkThread::new(PipelineType::Compute, context.clone());
+
+    let input_buffer = StorageBuffer::new(input, context.clone());
+    let output_buffer = StorageBuffer::new(&vec![0u32; input.len()], context.clone());
+    let descriptor = Descriptor::new(
+        vec![
+            DescriptorSet {
+                bind_index: 0,
+                flag: vk::ShaderStageFlags::COMPUTE,
+                bind_type: vk::DescriptorType::STORAGE_BUFFER,
+                buffer_info: Some(vec![input_buffer.descriptor_info()]),
+                ..Default::default()
+            },
+            DescriptorSet {
+                bind_index: 1,
+                flag: vk::ShaderStageFlags::COMPUTE,
+                bind_type: vk::DescriptorType::STORAGE_BUFFER,
+                buffer_info: Some(vec![output_buffer.descriptor_info()]),
+                ..Default::default()
+            },
+        ],
+        context.clone(),
+    );
+
+    let pipeline = ComputePipeline::new(
+        Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/shaders/multiply.comp.spv"
+        )),
+        vec![descriptor],
+        mem::size_of::<Constants>() as u32,
+        context,
+    );
+
+    let groups = ComputePipeline::group_count(count, LOCAL_SIZE);
+    pipeline.run_and_read(
+        &vulkan,
+        [groups, 1, 1],
+        &Constants { count, factor },
+        output_buffer.buffer(),
+    )
+}
+
+#[test]
+#[ignore = "needs a Vulkan device"]
+fn every_element_is_written() {
+    //Not a multiple of the workgroup size, the last group skips its extra invocations
+    let input: Vec<u32> = (0..1000).collect();
+    let output = multiply(&input, input.len() as u32, 3);
+
+    let expected: Vec<u32> = input.iter().map(|value| value * 3).collect();
+    assert_eq!(output, expected);
+}
+
+#[test]
+#[ignore = "needs a Vulkan device"]
+fn elements_past_count_are_untouched() {
+    let input = vec![7u32; 200];
+    let output = multiply(&input, 130, 2);
+
+    assert!(output[..130].iter().all(|&value| value == 14));
+    assert!(output[130..].iter().all(|&value| value == 0));
+}
@@ -0,0 +1,48 @@
+//Renders every example scene headless with --golden and compares it against tests/golden/<name>.png.
+//Needs a Vulkan device, run with `cargo test -p examples --test golden -- --ignored`.
+//Set UPDATE_GOLDEN=1 to replace the references after an intended visual change.
+use std::process::Command;
+
+fn render_golden(binary: &str) {
+    let status = Command::new(binary)
+        .arg("--golden")
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .status()
+        .expect("Failed to run example!");
+
+    assert!(
+        status.success(),
+        "{} does not match its golden image, see target/golden",
+        binary
+    );
+}
+
+#[test]
+#[ignore = "needs a Vulkan device"]
+fn triangle() {
+    render_golden(env!("CARGO_BIN_EXE_triangle"));
+}
+
+#[test]
+#[ignore = "needs a Vulkan device"]
+fn texture() {
+    render_golden(env!("CARGO_BIN_EXE_texture"));
+}
+
+#[test]
+#[ignore = "needs a Vulkan device"]
+fn lights() {
+    render_golden(env!("CARGO_BIN_EXE_lights"));
+}
+
+#[test]
+#[ignore = "needs a Vulkan device"]
+fn load_gltf() {
+    render_golden(env!("CARGO_BIN_EXE_load_gltf"));
+}
+
+#[test]
+#[ignore = "needs a Vulkan device"]
+fn deferred_rendering() {
+    render_golden(env!("CARGO_BIN_EXE_deferred_rendering"));
+}
@@ -0,0 +1,92 @@
+//std140/std430 layouts computed by #[derive(ShaderLayout)], no Vulkan device needed
+use vulkan::{utilities::Align16, ShaderLayout};
+
+//vec3 followed by a scalar packs into the vec3 padding in both layouts
+#[repr(C)]
+#[derive(Clone, Copy, ShaderLayout)]
+struct Light {
+    position: [f32; 3],
+    intensity: f32,
+    color: [f32; 4],
+}
+
+//Two vec3 in a row, the second one starts at 16 in GLSL but at 12 in Rust
+#[repr(C)]
+#[derive(Clone, Copy, ShaderLayout)]
+struct Packed {
+    position: [f32; 3],
+    normal: [f32; 3],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, ShaderLayout)]
+struct Aligned {
+    position: [f32; 3],
+    normal: Align16<[f32; 3]>,
+}
+
+//vec2 array, std430 stride is 8 and std140 rounds it up to 16
+#[repr(C)]
+#[derive(Clone, Copy, ShaderLayout)]
+struct Offsets {
+    offsets: [[f32; 2]; 3],
+}
+
+//float array with a 16 byte Rust stride, only matches std140
+#[repr(C)]
+#[derive(Clone, Copy, ShaderLayout)]
+struct Weights {
+    weights: [Align16<f32>; 3],
+    count: u32,
+}
+
+//Nested struct is aligned to 16, the Rust side needs the padding spelled out
+#[repr(C)]
+#[derive(Clone, Copy, ShaderLayout)]
+struct Scene {
+    scale: f32,
+    padding_0: f32,
+    padding_1: f32,
+    padding_2: f32,
+    light: Light,
+    lights: [Light; 2],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, ShaderLayout)]
+struct Unpadded {
+    scale: f32,
+    light: Light,
+}
+
+fn layout<T: ShaderLayout>() -> [(usize, usize, bool); 2] {
+    [
+        (T::STD140.align, T::STD140.size, T::STD140.matches),
+        (T::STD430.align, T::STD430.size, T::STD430.matches),
+    ]
+}
+
+#[test]
+fn scalar_fills_vec3_padding() {
+    assert_eq!(layout::<Light>(), [(16, 32, true), (16, 32, true)]);
+}
+
+#[test]
+fn vec3_after_vec3_needs_alignment() {
+    assert_eq!(layout::<Packed>(), [(16, 32, false), (16, 32, false)]);
+    assert_eq!(layout::<Aligned>(), [(16, 32, true), (16, 32, true)]);
+}
+
+#[test]
+fn array_stride_depends_on_layout() {
+    assert_eq!(layout::<Offsets>(), [(16, 48, false), (8, 24, true)]);
+    //std430 packs the floats and count into 16 bytes
+    assert_eq!(layout::<Weights>(), [(16, 64, true), (4, 16, false)]);
+}
+
+#[test]
+fn nested_struct_is_aligned_to_its_members() {
+    //light at 16, lights at 48 with a 32 byte stride
+    assert_eq!(layout::<Scene>(), [(16, 112, true), (16, 112, true)]);
+    assert_eq!(layout::<Unpadded>(), [(16, 48, false), (16, 48, false)]);
+}
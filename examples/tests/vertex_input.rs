@@ -0,0 +1,77 @@
+//Attribute descriptions generated by #[derive(VertexInput)], no Vulkan device needed
+use cgmath::{Matrix4, Vector2};
+use std::mem;
+use vulkan::{memoffset::offset_of, prelude::*, VertexInput};
+
+#[repr(C)]
+#[derive(Clone, Copy, VertexInput)]
+struct Vertex {
+    position: [f32; 3],
+    uv: Vector2<f32>,
+    material: i32,
+    #[location = 5]
+    #[format = "R8G8B8A8_UNORM"]
+    color: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, VertexInput)]
+#[input_rate = "instance"]
+struct Instance {
+    transform: Matrix4<f32>,
+    id: u32,
+}
+
+fn attributes<T: VertexInput>(binding: u32) -> Vec<(u32, u32, vk::Format, u32)> {
+    T::attribute_descriptions(binding)
+        .iter()
+        .map(|attribute| {
+            (
+                attribute.binding,
+                attribute.location,
+                attribute.format,
+                attribute.offset,
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn formats_and_offsets_follow_the_fields() {
+    assert_eq!(
+        attributes::<Vertex>(0),
+        vec![
+            (0, 0, vk::Format::R32G32B32_SFLOAT, 0),
+            (0, 1, vk::Format::R32G32_SFLOAT, 12),
+            (0, 2, vk::Format::R32_SINT, 20),
+            (0, 5, vk::Format::R8G8B8A8_UNORM, 24),
+        ]
+    );
+    assert_eq!(offset_of!(Vertex, color), 24);
+}
+
+#[test]
+fn binding_description_uses_struct_size_and_rate() {
+    let vertex = Vertex::binding_description(0);
+    assert_eq!(vertex.stride as usize, mem::size_of::<Vertex>());
+    assert_eq!(vertex.input_rate, vk::VertexInputRate::VERTEX);
+
+    let instance = Instance::binding_description(1);
+    assert_eq!(instance.binding, 1);
+    assert_eq!(instance.stride as usize, mem::size_of::<Instance>());
+    assert_eq!(instance.input_rate, vk::VertexInputRate::INSTANCE);
+}
+
+#[test]
+fn matrix_takes_one_location_per_row() {
+    assert_eq!(
+        attributes::<Instance>(1),
+        vec![
+            (1, 0, vk::Format::R32G32B32A32_SFLOAT, 0),
+            (1, 1, vk::Format::R32G32B32A32_SFLOAT, 16),
+            (1, 2, vk::Format::R32G32B32A32_SFLOAT, 32),
+            (1, 3, vk::Format::R32G32B32A32_SFLOAT, 48),
+            (1, 4, vk::Format::R32_UINT, 64),
+        ]
+    );
+}
@@ -3,9 +3,19 @@ mod constants;
 pub mod utilities;
 pub mod prelude;
 pub use modules::instance::VkThread;
-pub use modules::swapchain::{Swapchain, Framebuffer};
+pub use modules::swapchain::{Swapchain, Framebuffer, FramebufferSet, FramebufferAttachment, RenderTarget};
+pub use modules::offscreen::OffscreenTarget;
+pub use modules::capabilities::DeviceCapabilities;
 pub use modules::context::Context;
+pub use modules::memory_stats::{AllocationStats, HeapStats, MemoryStats};
+pub use modules::parallel::{ParallelRecorder, RenderPassInheritance};
 pub use modules::queue::Queue;
-pub use utilities::{Image, Buffer, Descriptor, DescriptorSet, Shader, Renderpass, Pipeline };
+pub use modules::recording::{FrameCommands, RecordMode};
+pub use modules::report::{DeviceReport, FormatReport, MemoryHeapReport, QueueFamilyReport, QueuePicks};
+pub use modules::sampler_cache::SamplerCache;
+pub use utilities::{Image, Buffer, Descriptor, DescriptorSet, Shader, Renderpass, RenderpassBuilder, Pipeline };
 
 pub use constants::PipelineType;
+pub use utilities::{Access, Barrier, CommandEncoder, ComputePipeline, FrameAllocation, FrameAllocator, FrameTimeStats, FrameTimer, GpuProfiler, IndirectCommands, PipelineStatistics, QueryPool, ShaderLayout, StorageBuffer, TextureFile, UniformBuffer, VertexInput};
+pub use vulkan_derive::{ShaderLayout, VertexInput};
+pub use memoffset;
@@ -0,0 +1,139 @@
+use ash::{version::InstanceV1_0, vk};
+use std::{mem, slice};
+
+use super::device;
+
+//Features a device can not be used without, devices missing one are skipped
+pub fn required_features() -> vk::PhysicalDeviceFeatures {
+    //Everything beyond Vulkan 1.0 core is optional, see DeviceCapabilities
+    vk::PhysicalDeviceFeatures::default()
+}
+
+//Whether every feature set in `required` is also set in `supported`
+pub fn supports_features(
+    supported: &vk::PhysicalDeviceFeatures,
+    required: &vk::PhysicalDeviceFeatures,
+) -> bool {
+    feature_flags(supported)
+        .iter()
+        .zip(feature_flags(required))
+        .all(|(supported, required)| *required == vk::FALSE || *supported == vk::TRUE)
+}
+
+//PhysicalDeviceFeatures is a repr(C) struct of nothing but Bool32 fields
+fn feature_flags(features: &vk::PhysicalDeviceFeatures) -> &[vk::Bool32] {
+    unsafe {
+        slice::from_raw_parts(
+            features as *const vk::PhysicalDeviceFeatures as *const vk::Bool32,
+            mem::size_of::<vk::PhysicalDeviceFeatures>() / mem::size_of::<vk::Bool32>(),
+        )
+    }
+}
+
+//Optional features the device was created with. Every feature is enabled when the
+//device supports it, renderers check here before relying on one.
+#[derive(Debug, Clone, Copy)]
+pub struct DeviceCapabilities {
+    pub sampler_anisotropy: bool,
+    //1.0 without sampler_anisotropy
+    pub max_sampler_anisotropy: f32,
+    pub wide_lines: bool,
+    pub line_width_range: [f32; 2],
+    pub fill_mode_non_solid: bool,
+    pub geometry_shader: bool,
+    pub multi_draw_indirect: bool,
+    pub draw_indirect_first_instance: bool,
+    pub texture_compression_bc: bool,
+    pub texture_compression_etc2: bool,
+    pub texture_compression_astc_ldr: bool,
+    pub pipeline_statistics_query: bool,
+    pub occlusion_query_precise: bool,
+    //VK_KHR_draw_indirect_count, loaded into Context::draw_indirect_count
+    pub draw_indirect_count: bool,
+    //VK_EXT_memory_budget, read by Context::memory_stats
+    pub memory_budget: bool,
+}
+
+impl DeviceCapabilities {
+    pub fn new(instance: &ash::Instance, physical_device: vk::PhysicalDevice) -> Self {
+        let features = unsafe { instance.get_physical_device_features(physical_device) };
+        let limits = unsafe { instance.get_physical_device_properties(physical_device) }.limits;
+        let supported = |feature: vk::Bool32| feature == vk::TRUE;
+
+        let sampler_anisotropy = supported(features.sampler_anisotropy);
+        let wide_lines = supported(features.wide_lines);
+
+        DeviceCapabilities {
+            sampler_anisotropy,
+            max_sampler_anisotropy: if sampler_anisotropy {
+                limits.max_sampler_anisotropy
+            } else {
+                1.0
+            },
+            wide_lines,
+            line_width_range: if wide_lines {
+                limits.line_width_range
+            } else {
+                [1.0, 1.0]
+            },
+            fill_mode_non_solid: supported(features.fill_mode_non_solid),
+            geometry_shader: supported(features.geometry_shader),
+            multi_draw_indirect: supported(features.multi_draw_indirect),
+            draw_indirect_first_instance: supported(features.draw_indirect_first_instance),
+            texture_compression_bc: supported(features.texture_compression_bc),
+            texture_compression_etc2: supported(features.texture_compression_etc2),
+            texture_compression_astc_ldr: supported(features.texture_compression_astc_ldr),
+            pipeline_statistics_query: supported(features.pipeline_statistics_query),
+            occlusion_query_precise: supported(features.occlusion_query_precise),
+            draw_indirect_count: device::supports_device_extension(
+                instance,
+                physical_device,
+                vk::KhrDrawIndirectCountFn::name(),
+            ),
+            memory_budget: device::supports_device_extension(
+                instance,
+                physical_device,
+                vk::ExtMemoryBudgetFn::name(),
+            ),
+        }
+    }
+
+    //Required features plus every supported optional one, passed to vkCreateDevice
+    pub fn enabled_features(&self) -> vk::PhysicalDeviceFeatures {
+        let flag = |enabled: bool| if enabled { vk::TRUE } else { vk::FALSE };
+
+        vk::PhysicalDeviceFeatures {
+            sampler_anisotropy: flag(self.sampler_anisotropy),
+            wide_lines: flag(self.wide_lines),
+            fill_mode_non_solid: flag(self.fill_mode_non_solid),
+            geometry_shader: flag(self.geometry_shader),
+            multi_draw_indirect: flag(self.multi_draw_indirect),
+            draw_indirect_first_instance: flag(self.draw_indirect_first_instance),
+            texture_compression_bc: flag(self.texture_compression_bc),
+            texture_compression_etc2: flag(self.texture_compression_etc2),
+            texture_compression_astc_ldr: flag(self.texture_compression_astc_ldr),
+            pipeline_statistics_query: flag(self.pipeline_statistics_query),
+            occlusion_query_precise: flag(self.occlusion_query_precise),
+            ..required_features()
+        }
+    }
+
+    //Turns anisotropic filtering off without sampler_anisotropy and clamps it to the limit
+    pub fn sampler_info(&self, info: &vk::SamplerCreateInfo) -> vk::SamplerCreateInfo {
+        let mut info = *info;
+        if info.anisotropy_enable == vk::TRUE {
+            if self.sampler_anisotropy {
+                info.max_anisotropy = info.max_anisotropy.min(self.max_sampler_anisotropy);
+            } else {
+                info.anisotropy_enable = vk::FALSE;
+                info.max_anisotropy = 1.0;
+            }
+        }
+        info
+    }
+
+    //Line width the device can rasterize, 1.0 without wide_lines
+    pub fn line_width(&self, width: f32) -> f32 {
+        width.clamp(self.line_width_range[0], self.line_width_range[1])
+    }
+}
@@ -6,13 +6,17 @@ use ash::{
 
 use super::platform::{create_surface, extension_names};
 use super::{
+    capabilities::DeviceCapabilities,
     debug::{Debugger, ValidationInfo},
     device,
+    memory_stats::MemoryStats,
     queue::QueueFamilyIndices,
+    sampler_cache::SamplerCache,
 };
 use crate::constants::*;
+use crate::utilities::tools::vk_to_string;
 
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
 use winit::window::Window;
 
 pub struct Context {
@@ -31,6 +35,13 @@ pub struct Context {
 
     pub memory: vk_mem::Allocator,
     pub image_count: u32,
+    pub samplers: SamplerCache,
+    //Loaded when VK_KHR_draw_indirect_count is supported
+    pub draw_indirect_count: Option<vk::KhrDrawIndirectCountFn>,
+    //Loaded when VK_EXT_memory_budget is enabled, queries the heap budgets
+    properties2: Option<vk::KhrGetPhysicalDeviceProperties2Fn>,
+    //Optional features the device was created with
+    pub capabilities: DeviceCapabilities,
 }
 
 const VALIDATION_LAYERS:&str = "VK_LAYER_KHRONOS_validation";
@@ -43,6 +54,24 @@ impl Context {
         let surface =
             unsafe { create_surface(&entry, &instance, window).expect("Failed to create surface") };
 
+        Self::create(entry, instance, surface_loader, Some(surface), validation_enabled)
+    }
+
+    //Context without a window or swapchain, render into an OffscreenTarget instead
+    pub fn headless(app_name: &str, validation_enabled: bool) -> Self {
+        let (entry, instance) = create_entry(app_name);
+        let surface_loader = Surface::new(&entry, &instance);
+
+        Self::create(entry, instance, surface_loader, None, validation_enabled)
+    }
+
+    fn create(
+        entry: Entry,
+        instance: Instance,
+        surface_loader: Surface,
+        surface: Option<vk::SurfaceKHR>,
+        validation_enabled: bool,
+    ) -> Self {
         let physical_device =
             device::pick_physical_device(&instance, &surface_loader, surface, &DEVICE_EXTENSIONS);
 
@@ -51,15 +80,36 @@ impl Context {
             required_validation_layers: [VALIDATION_LAYERS],
         };
 
+        let mut capabilities = DeviceCapabilities::new(&instance, physical_device);
+        //The budget is queried through vkGetPhysicalDeviceMemoryProperties2KHR
+        capabilities.memory_budget &=
+            supports_instance_extension(&entry, vk::KhrGetPhysicalDeviceProperties2Fn::name());
         let (device, queue) = device::create_logical_device(
             &instance,
             physical_device,
             &validation,
             &DEVICE_EXTENSIONS,
             &surface_loader,
-            &surface,
+            surface,
+            &capabilities,
         );
 
+        let draw_indirect_count = if capabilities.draw_indirect_count {
+            Some(vk::KhrDrawIndirectCountFn::load(|name| unsafe {
+                std::mem::transmute(instance.get_device_proc_addr(device.handle(), name.as_ptr()))
+            }))
+        } else {
+            None
+        };
+
+        let properties2 = if capabilities.memory_budget {
+            Some(vk::KhrGetPhysicalDeviceProperties2Fn::load(|name| unsafe {
+                std::mem::transmute(entry.get_instance_proc_addr(instance.handle(), name.as_ptr()))
+            }))
+        } else {
+            None
+        };
+
         let memory_info = vk_mem::AllocatorCreateInfo {
             physical_device: physical_device,
             device: device.clone(),
@@ -72,17 +122,22 @@ impl Context {
             debugger = Some(Debugger::new(&entry, &instance));
         }
 
-        let capabilities = unsafe {
-            surface_loader
-                .get_physical_device_surface_capabilities(physical_device, surface)
-                .expect("Failed to query for surface capabilities.")
-        };
+        let image_count = match surface {
+            Some(surface) => {
+                let capabilities = unsafe {
+                    surface_loader
+                        .get_physical_device_surface_capabilities(physical_device, surface)
+                        .expect("Failed to query for surface capabilities.")
+                };
 
-        let image_count = capabilities.min_image_count + 1;
-        if capabilities.max_image_count > 0 {
-            image_count.min(capabilities.max_image_count)
-        } else {
-            image_count
+                let image_count = capabilities.min_image_count + 1;
+                if capabilities.max_image_count > 0 {
+                    image_count.min(capabilities.max_image_count)
+                } else {
+                    image_count
+                }
+            }
+            None => 1,
         };
 
         unsafe {
@@ -90,7 +145,7 @@ impl Context {
                 _entry: entry,
                 _debugger: debugger,
                 instance,
-                surface,
+                surface: surface.unwrap_or_else(vk::SurfaceKHR::null),
                 surface_loader,
                 physical_device,
                 graphics_queue: device.get_device_queue(queue.graphics_family.unwrap(), 0),
@@ -100,11 +155,58 @@ impl Context {
                 device,
                 memory: vk_mem::Allocator::new(&memory_info).unwrap(),
                 image_count,
+                samplers: SamplerCache::default(),
+                draw_indirect_count,
+                properties2,
+                capabilities,
             }
         }
     }
 
-    fn get_min_uniform_buffer_offset_alignment(&self) -> u32 {
+    //Shared sampler for `sampler_info`, owned by the context and must not be destroyed.
+    //Anisotropy is clamped to what the device supports.
+    pub fn sampler(&self, sampler_info: &vk::SamplerCreateInfo) -> vk::Sampler {
+        self.samplers
+            .get(&self.device, &self.capabilities.sampler_info(sampler_info))
+    }
+
+    //Per heap usage, budget and allocator statistics of every buffer and image
+    pub fn memory_stats(&self) -> MemoryStats {
+        let stats = self
+            .memory
+            .calculate_stats()
+            .expect("Failed to calculate memory stats!");
+        let properties = unsafe {
+            self.instance
+                .get_physical_device_memory_properties(self.physical_device)
+        };
+
+        let budget = self.properties2.as_ref().map(|properties2| {
+            let mut budget = vk::PhysicalDeviceMemoryBudgetPropertiesEXT::default();
+            let mut properties = vk::PhysicalDeviceMemoryProperties2::builder()
+                .push_next(&mut budget)
+                .build();
+            unsafe {
+                properties2
+                    .get_physical_device_memory_properties2_khr(self.physical_device, &mut properties);
+            }
+            budget
+        });
+        MemoryStats::new(&stats, &properties, budget.as_ref())
+    }
+
+    //JSON map of every memory block and the allocations inside it
+    pub fn memory_map_json(&self) -> String {
+        self.memory
+            .build_stats_string(true)
+            .expect("Failed to build memory map!")
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.surface == vk::SurfaceKHR::null()
+    }
+
+    pub fn get_min_uniform_buffer_offset_alignment(&self) -> u32 {
         let props = unsafe {
             self.instance
                 .get_physical_device_properties(self.physical_device)
@@ -112,6 +214,14 @@ impl Context {
         props.limits.min_uniform_buffer_offset_alignment as _
     }
 
+    pub fn get_min_storage_buffer_offset_alignment(&self) -> u32 {
+        let props = unsafe {
+            self.instance
+                .get_physical_device_properties(self.physical_device)
+        };
+        props.limits.min_storage_buffer_offset_alignment as _
+    }
+
     pub fn get_ubo_alignment<T>(&self) -> u32 {
         let min_alignment = self.get_min_uniform_buffer_offset_alignment();
         let t_size = std::mem::size_of::<T>() as u32;
@@ -123,29 +233,46 @@ impl Context {
         }
     }
 
+    //Highest sample count supported by both color and depth framebuffer attachments
+    pub fn get_max_usable_sample_count(&self) -> vk::SampleCountFlags {
+        let props = unsafe {
+            self.instance
+                .get_physical_device_properties(self.physical_device)
+        };
+        device::max_usable_sample_count(&props.limits)
+    }
+
     pub fn find_depth_format(
         &self,
         candidate_formats: &[vk::Format],
         tiling: vk::ImageTiling,
         features: vk::FormatFeatureFlags,
     ) -> vk::Format {
-        for &format in candidate_formats.iter() {
-            let format_properties = unsafe {
-                self.instance
-                    .get_physical_device_format_properties(self.physical_device, format)
-            };
-            if tiling == vk::ImageTiling::LINEAR
-                && format_properties.linear_tiling_features.contains(features)
-            {
-                return format.clone();
-            } else if tiling == vk::ImageTiling::OPTIMAL
-                && format_properties.optimal_tiling_features.contains(features)
-            {
-                return format.clone();
+        candidate_formats
+            .iter()
+            .find(|&&format| self.supports_format_features(format, tiling, features))
+            .cloned()
+            .expect("Failed to find supported format!")
+    }
+
+    //Whether images of `format` with `tiling` support all of `features`
+    pub fn supports_format_features(
+        &self,
+        format: vk::Format,
+        tiling: vk::ImageTiling,
+        features: vk::FormatFeatureFlags,
+    ) -> bool {
+        let format_properties = unsafe {
+            self.instance
+                .get_physical_device_format_properties(self.physical_device, format)
+        };
+        match tiling {
+            vk::ImageTiling::LINEAR => format_properties.linear_tiling_features.contains(features),
+            vk::ImageTiling::OPTIMAL => {
+                format_properties.optimal_tiling_features.contains(features)
             }
+            _ => false,
         }
-
-        panic!("Failed to find supported format!")
     }
 
     pub fn wait_idle(&self) {
@@ -161,7 +288,9 @@ impl Drop for Context {
     fn drop(&mut self) {
         unsafe {
             self.wait_idle();
-            self.surface_loader.destroy_surface(self.surface, None);
+            if !self.is_headless() {
+                self.surface_loader.destroy_surface(self.surface, None);
+            }
 
             if self._debugger.is_some() {
                 let debugger = self._debugger.as_ref().unwrap();
@@ -170,6 +299,7 @@ impl Drop for Context {
                     .destroy_debug_report_callback(debugger.reporter, None);
             }
 
+            self.samplers.destroy(&self.device);
             self.device.destroy_device(None);
             self.instance.destroy_instance(None);
         }
@@ -187,7 +317,11 @@ pub fn create_entry(app_name: &str) -> (Entry, Instance) {
         .map(|raw_name| raw_name.as_ptr())
         .collect();
 
-    let extension_names_raw = extension_names();
+    let mut extension_names_raw = extension_names();
+    //Needed for VK_EXT_memory_budget
+    if supports_instance_extension(&entry, vk::KhrGetPhysicalDeviceProperties2Fn::name()) {
+        extension_names_raw.push(vk::KhrGetPhysicalDeviceProperties2Fn::name().as_ptr());
+    }
 
     let appinfo = vk::ApplicationInfo::builder()
         .application_name(&app_name)
@@ -208,3 +342,11 @@ pub fn create_entry(app_name: &str) -> (Entry, Instance) {
         (entry, instance)
     }
 }
+
+fn supports_instance_extension(entry: &Entry, name: &CStr) -> bool {
+    entry
+        .enumerate_instance_extension_properties()
+        .expect("Failed to get instance extension properties.")
+        .iter()
+        .any(|extension| vk_to_string(&extension.extension_name) == name.to_string_lossy())
+}
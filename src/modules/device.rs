@@ -1,5 +1,10 @@
-use ash::{extensions::khr::Surface, version::InstanceV1_0, vk};
+use ash::{
+    extensions::khr::Surface,
+    version::InstanceV1_0,
+    vk,
+};
 
+use super::capabilities::{self, DeviceCapabilities};
 use super::platform::DeviceExtension;
 
 use super::queue::QueueFamilyIndices;
@@ -7,7 +12,7 @@ use super::queue::QueueFamilyIndices;
 use std::collections::HashSet;
 use std::ptr;
 
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
 
 pub struct SwapchainSupport {
@@ -16,10 +21,11 @@ pub struct SwapchainSupport {
     pub present_modes: Vec<vk::PresentModeKHR>,
 }
 
+//Without a surface (headless) presentation support and the swapchain extension are not required
 pub fn pick_physical_device(
     instance: &ash::Instance,
     surface_loader: &Surface,
-    surface: vk::SurfaceKHR,
+    surface: Option<vk::SurfaceKHR>,
     required_device_extensions: &DeviceExtension,
 ) -> vk::PhysicalDevice {
     let physical_devices = unsafe {
@@ -50,7 +56,7 @@ pub fn is_physical_device_suitable(
     instance: &ash::Instance,
     physical_device: vk::PhysicalDevice,
     surface_loader: &Surface,
-    surface: vk::SurfaceKHR,
+    surface: Option<vk::SurfaceKHR>,
     required_device_extensions: &DeviceExtension,
 ) -> bool {
     let device_features = unsafe { instance.get_physical_device_features(physical_device) };
@@ -58,20 +64,33 @@ pub fn is_physical_device_suitable(
     let indices = find_queue_family(instance, physical_device, surface_loader, surface);
 
     let is_queue_family_supported = indices.is_complete();
-    let is_device_extension_supported =
-        check_device_extension_support(instance, physical_device, required_device_extensions);
-    let is_swapchain_supported = if is_device_extension_supported {
-        let swapchain_support = query_swapchain_support(physical_device, surface_loader, surface);
-        !swapchain_support.formats.is_empty() && !swapchain_support.present_modes.is_empty()
-    } else {
-        false
+    let (is_device_extension_supported, is_swapchain_supported) = match surface {
+        Some(surface) => {
+            let is_device_extension_supported = check_device_extension_support(
+                instance,
+                physical_device,
+                required_device_extensions,
+            );
+            let is_swapchain_supported = if is_device_extension_supported {
+                let swapchain_support =
+                    query_swapchain_support(physical_device, surface_loader, surface);
+                !swapchain_support.formats.is_empty()
+                    && !swapchain_support.present_modes.is_empty()
+            } else {
+                false
+            };
+            (is_device_extension_supported, is_swapchain_supported)
+        }
+        None => (true, true),
     };
-    let is_support_sampler_anisotropy = device_features.sampler_anisotropy == 1;
+    //Optional features are checked through DeviceCapabilities instead
+    let is_required_features_supported =
+        capabilities::supports_features(&device_features, &capabilities::required_features());
 
     return is_queue_family_supported
         && is_device_extension_supported
         && is_swapchain_supported
-        && is_support_sampler_anisotropy;
+        && is_required_features_supported;
 }
 
 pub fn create_logical_device(
@@ -80,9 +99,10 @@ pub fn create_logical_device(
     validation: &super::debug::ValidationInfo,
     device_extensions: &DeviceExtension,
     surface_loader: &Surface,
-    surface: &vk::SurfaceKHR,
+    surface: Option<vk::SurfaceKHR>,
+    capabilities: &DeviceCapabilities,
 ) -> (ash::Device, QueueFamilyIndices) {
-    let indices = find_queue_family(instance, physical_device, surface_loader, *surface);
+    let indices = find_queue_family(instance, physical_device, surface_loader, surface);
 
     let mut unique_queue_families = HashSet::new();
     unique_queue_families.insert(indices.graphics_family.unwrap());
@@ -98,10 +118,9 @@ pub fn create_logical_device(
         queue_create_infos.push(queue_create_info);
     }
 
-    let physical_device_features = vk::PhysicalDeviceFeatures {
-        sampler_anisotropy: vk::TRUE, // enable anisotropy device feature from Chapter-24.
-        ..Default::default()
-    };
+    //Compressed formats are sampled directly by Image::from_texture_file, QueryPool and
+    //IndirectCommands use the query and indirect features when they are enabled
+    let physical_device_features = capabilities.enabled_features();
 
     let required_validation_layer_raw_names: Vec<CString> = validation
         .required_validation_layers
@@ -113,7 +132,18 @@ pub fn create_logical_device(
         .map(|layer_name| layer_name.as_ptr())
         .collect();
 
-    let enable_extension_names = device_extensions.get_extensions_raw_names();
+    //Device extensions are only needed for presenting
+    let mut enable_extension_names: Vec<*const c_char> = match surface {
+        Some(_) => device_extensions.get_extensions_raw_names().to_vec(),
+        None => Vec::new(),
+    };
+    //Optional, the Context loads it when supported
+    if capabilities.draw_indirect_count {
+        enable_extension_names.push(vk::KhrDrawIndirectCountFn::name().as_ptr());
+    }
+    if capabilities.memory_budget {
+        enable_extension_names.push(vk::ExtMemoryBudgetFn::name().as_ptr());
+    }
 
     let device_create_info = vk::DeviceCreateInfo {
         s_type: vk::StructureType::DEVICE_CREATE_INFO,
@@ -149,7 +179,7 @@ pub fn find_queue_family(
     instance: &ash::Instance,
     physical_device: vk::PhysicalDevice,
     surface_loader: &Surface,
-    surface: vk::SurfaceKHR,
+    surface: Option<vk::SurfaceKHR>,
 ) -> QueueFamilyIndices {
     let queue_families =
         unsafe { instance.get_physical_device_queue_family_properties(physical_device) };
@@ -159,12 +189,16 @@ pub fn find_queue_family(
     let mut index = 0;
     for queue_family in queue_families.iter() {
         if queue_family.queue_count > 0 {
-            let is_present_support = unsafe {
-                surface_loader.get_physical_device_surface_support(
-                    physical_device,
-                    index as u32,
-                    surface,
-                )
+            //Headless contexts "present" on the graphics queue, nothing is ever presented
+            let is_present_support = match surface {
+                Some(surface) => unsafe {
+                    surface_loader.get_physical_device_surface_support(
+                        physical_device,
+                        index as u32,
+                        surface,
+                    )
+                },
+                None => Ok(queue_family.queue_flags.contains(vk::QueueFlags::GRAPHICS)),
             };
 
             if queue_family.queue_flags.contains(vk::QueueFlags::GRAPHICS) {
@@ -227,6 +261,40 @@ pub fn check_device_extension_support(
     return required_extensions.is_empty();
 }
 
+pub fn supports_device_extension(
+    instance: &ash::Instance,
+    physical_device: vk::PhysicalDevice,
+    name: &CStr,
+) -> bool {
+    let available_extensions = unsafe {
+        instance
+            .enumerate_device_extension_properties(physical_device)
+            .expect("Failed to get device extension properties.")
+    };
+
+    available_extensions.iter().any(|extension| {
+        crate::utilities::tools::vk_to_string(&extension.extension_name) == name.to_string_lossy()
+    })
+}
+
+//Highest sample count supported by both color and depth framebuffer attachments
+pub fn max_usable_sample_count(limits: &vk::PhysicalDeviceLimits) -> vk::SampleCountFlags {
+    let counts = limits.framebuffer_color_sample_counts & limits.framebuffer_depth_sample_counts;
+
+    [
+        vk::SampleCountFlags::TYPE_64,
+        vk::SampleCountFlags::TYPE_32,
+        vk::SampleCountFlags::TYPE_16,
+        vk::SampleCountFlags::TYPE_8,
+        vk::SampleCountFlags::TYPE_4,
+        vk::SampleCountFlags::TYPE_2,
+    ]
+    .iter()
+    .find(|&&samples| counts.contains(samples))
+    .cloned()
+    .unwrap_or(vk::SampleCountFlags::TYPE_1)
+}
+
 pub fn query_swapchain_support(
     physical_device: vk::PhysicalDevice,
     surface_loader: &Surface,
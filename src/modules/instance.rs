@@ -5,13 +5,14 @@ use std::cmp::max;
 use std::ptr;
 use std::sync::Arc;
 
+use super::offscreen::OffscreenTarget;
 use crate::constants::PipelineType;
-use crate::utilities::Buffer;
+use crate::utilities::{readback, Barrier, Buffer};
 
 pub struct VkThread {
     pub context: Arc<Context>,
     pub command_pool: vk::CommandPool,
-    _family: PipelineType,
+    family: PipelineType,
 }
 
 impl VkThread {
@@ -25,7 +26,7 @@ impl VkThread {
         VkThread {
             context,
             command_pool,
-            _family: family,
+            family,
         }
     }
 }
@@ -38,6 +39,14 @@ impl VkThread {
         &self.context.device
     }
 
+    //Queue of the family the command pool was created for
+    pub fn queue(&self) -> vk::Queue {
+        match self.family {
+            PipelineType::Draw => self.context.graphics_queue,
+            PipelineType::Compute => self.context.compute_queue,
+        }
+    }
+
     pub fn create_command_buffers(&self, amount: usize) -> Vec<vk::CommandBuffer> {
         unsafe {
             self.context
@@ -95,10 +104,10 @@ impl VkThread {
         }
     }
 
-    pub fn copy_buffer_to_buffer(
+    pub fn copy_buffer_to_buffer<T: Copy>(
         &self,
-        src_buffer: Buffer,
-        dst_buffer: &Buffer,
+        src_buffer: Buffer<T>,
+        dst_buffer: &Buffer<T>,
         regions: Vec<vk::BufferCopy>,
     ) {
         let command_buffer = self.begin_single_time_command();
@@ -119,19 +128,17 @@ impl VkThread {
         &self,
         usage_flags: vk::BufferUsageFlags,
         data: &[T],
-    ) -> Buffer {
+    ) -> Buffer<T> {
         let size = (data.len() * std::mem::size_of::<T>()) as u64;
-        let staging_buffer = Buffer::new_mapped_basic(
-            size,
+        let staging_buffer = Buffer::from_data(
+            data,
             vk::BufferUsageFlags::TRANSFER_SRC,
             vk_mem::MemoryUsage::CpuOnly,
             self.context(),
         );
 
-        staging_buffer.upload_to_buffer::<T>(&data, 0);
-
-        let vertex_buffer = Buffer::new_mapped_basic(
-            size,
+        let vertex_buffer = Buffer::with_len(
+            data.len(),
             vk::BufferUsageFlags::TRANSFER_DST | usage_flags,
             vk_mem::MemoryUsage::GpuOnly,
             self.context(),
@@ -206,11 +213,11 @@ impl VkThread {
         unsafe {
             self.context
                 .device
-                .queue_submit(self.context.present_queue, &sumbit_infos, vk::Fence::null())
+                .queue_submit(self.queue(), &sumbit_infos, vk::Fence::null())
                 .expect("Failed to Queue Submit!");
             self.context
                 .device
-                .queue_wait_idle(self.context.present_queue)
+                .queue_wait_idle(self.queue())
                 .expect("Failed to wait Queue idle!");
             self.context
                 .device
@@ -238,27 +245,174 @@ impl VkThread {
         self.end_single_time_command(command_buffer);
     }
 
-    pub fn apply_pipeline_barrier(
+    //Copies the first mip level of an image into host memory and returns the raw texels.
+    //The image is moved to TRANSFER_SRC_OPTIMAL for the copy and back to `layout` afterwards.
+    pub fn read_image(
         &self,
-        src_stage: vk::PipelineStageFlags,
-        dst_stage: vk::PipelineStageFlags,
-        barrier: vk::ImageMemoryBarrier,
-    ) {
+        image: vk::Image,
+        format: vk::Format,
+        extent: vk::Extent3D,
+        layout: vk::ImageLayout,
+    ) -> Vec<u8> {
+        let texel_size = readback::format_size(format)
+            .unwrap_or_else(|| panic!("Unsupported readback format: {:?}", format));
+        let aspect_mask = if readback::is_depth_format(format) {
+            vk::ImageAspectFlags::DEPTH
+        } else {
+            vk::ImageAspectFlags::COLOR
+        };
+
+        let buffer = Buffer::<u8>::with_len(
+            (extent.width * extent.height * extent.depth) as usize * texel_size,
+            vk::BufferUsageFlags::TRANSFER_DST,
+            vk_mem::MemoryUsage::GpuToCpu,
+            self.context(),
+        );
+
+        let subresource_range = vk::ImageSubresourceRange {
+            aspect_mask,
+            base_mip_level: 0,
+            level_count: 1,
+            base_array_layer: 0,
+            layer_count: 1,
+        };
+
+        let to_transfer = vk::ImageMemoryBarrier::builder()
+            .src_access_mask(vk::AccessFlags::MEMORY_WRITE)
+            .dst_access_mask(vk::AccessFlags::TRANSFER_READ)
+            .old_layout(layout)
+            .new_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .image(image)
+            .subresource_range(subresource_range)
+            .build();
+
+        //Undefined contents can't be transitioned back to, leave them as transfer source
+        let restored_layout = if layout == vk::ImageLayout::UNDEFINED {
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL
+        } else {
+            layout
+        };
+
+        let from_transfer = vk::ImageMemoryBarrier::builder()
+            .src_access_mask(vk::AccessFlags::TRANSFER_READ)
+            .dst_access_mask(vk::AccessFlags::MEMORY_READ)
+            .old_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+            .new_layout(restored_layout)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .image(image)
+            .subresource_range(subresource_range)
+            .build();
+
+        let region = vk::BufferImageCopy {
+            buffer_offset: 0,
+            buffer_row_length: 0,
+            buffer_image_height: 0,
+            image_subresource: vk::ImageSubresourceLayers {
+                aspect_mask,
+                mip_level: 0,
+                base_array_layer: 0,
+                layer_count: 1,
+            },
+            image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
+            image_extent: extent,
+        };
+
         let command_buffer = self.begin_single_time_command();
         unsafe {
-            self.context.device.cmd_pipeline_barrier(
+            let device = &self.context.device;
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::ALL_COMMANDS,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[to_transfer],
+            );
+            device.cmd_copy_image_to_buffer(
+                command_buffer,
+                image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                buffer.buffer,
+                &[region],
+            );
+            device.cmd_pipeline_barrier(
                 command_buffer,
-                src_stage,
-                dst_stage,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::ALL_COMMANDS,
                 vk::DependencyFlags::empty(),
                 &[],
                 &[],
-                &[barrier],
+                &[from_transfer],
             );
         }
+        //Waits for the queue to go idle, so the buffer is ready to be read
+        self.end_single_time_command(command_buffer);
+
+        buffer.read()
+    }
+
+    //Headless frame: records one command buffer, submits it, waits on its fence and
+    //returns the RGBA8 pixels of `target`. `layout` is the final layout of the color image.
+    pub fn render_offscreen<F: Fn(vk::CommandBuffer, &ash::Device)>(
+        &self,
+        target: &OffscreenTarget,
+        layout: vk::ImageLayout,
+        record: F,
+    ) -> Vec<u8> {
+        let command_buffer = self.create_command_buffers(1)[0];
+        self.build_command(command_buffer, record);
+
+        let command_buffers = [command_buffer];
+        let submit_info = vk::SubmitInfo::builder()
+            .command_buffers(&command_buffers)
+            .build();
+
+        unsafe {
+            let fence = self
+                .context
+                .device
+                .create_fence(&vk::FenceCreateInfo::default(), None)
+                .expect("Failed to create Fence!");
+
+            self.context
+                .device
+                .queue_submit(self.context.graphics_queue, &[submit_info], fence)
+                .expect("Failed to execute queue submit.");
+            self.context
+                .device
+                .wait_for_fences(&[fence], true, std::u64::MAX)
+                .expect("Failed to wait for Fence!");
+
+            self.context.device.destroy_fence(fence, None);
+            self.context
+                .device
+                .free_command_buffers(self.command_pool, &command_buffers);
+        }
+
+        target.read_pixels(layout, self)
+    }
+
+    //Records `barrier` into its own command buffer and waits for it, meant for setup
+    //work. Inside a frame record the barrier into the frame's command buffer instead.
+    pub fn apply_barrier(&self, barrier: &Barrier) {
+        let command_buffer = self.begin_single_time_command();
+        barrier.record(command_buffer, &self.context.device);
         self.end_single_time_command(command_buffer);
     }
 
+    pub fn apply_pipeline_barrier(
+        &self,
+        src_stage: vk::PipelineStageFlags,
+        dst_stage: vk::PipelineStageFlags,
+        barrier: vk::ImageMemoryBarrier,
+    ) {
+        self.apply_barrier(&Barrier::new().image_barrier(src_stage, dst_stage, barrier));
+    }
+
     pub fn generate_mipmaps(
         &self,
         image: vk::Image,
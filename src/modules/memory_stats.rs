@@ -0,0 +1,130 @@
+use ash::vk;
+use std::fmt;
+
+const MIB: f64 = 1024.0 * 1024.0;
+
+//Allocator statistics of a heap or of all heaps combined
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AllocationStats {
+    pub block_count: u32,
+    pub allocation_count: u32,
+    pub used_bytes: vk::DeviceSize,
+    pub unused_bytes: vk::DeviceSize,
+    pub largest_unused_range: vk::DeviceSize,
+}
+
+impl AllocationStats {
+    //Share of free memory in blocks that is not part of the largest free range, 0 when unfragmented
+    pub fn fragmentation(&self) -> f64 {
+        if self.unused_bytes == 0 {
+            0.0
+        } else {
+            1.0 - self.largest_unused_range as f64 / self.unused_bytes as f64
+        }
+    }
+}
+
+impl From<&vk_mem::ffi::VmaStatInfo> for AllocationStats {
+    fn from(info: &vk_mem::ffi::VmaStatInfo) -> Self {
+        AllocationStats {
+            block_count: info.blockCount,
+            allocation_count: info.allocationCount,
+            used_bytes: info.usedBytes,
+            unused_bytes: info.unusedBytes,
+            largest_unused_range: info.unusedRangeSizeMax,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct HeapStats {
+    pub index: usize,
+    pub flags: vk::MemoryHeapFlags,
+    pub size: vk::DeviceSize,
+    //Reported by the driver with VK_EXT_memory_budget, otherwise estimated as 80% of
+    //the heap like VMA does and `budget_estimated` is set
+    pub budget: vk::DeviceSize,
+    pub budget_estimated: bool,
+    pub allocations: AllocationStats,
+}
+
+impl HeapStats {
+    pub fn usage(&self) -> vk::DeviceSize {
+        self.allocations.used_bytes + self.allocations.unused_bytes
+    }
+
+    pub fn is_device_local(&self) -> bool {
+        self.flags.contains(vk::MemoryHeapFlags::DEVICE_LOCAL)
+    }
+}
+
+//Snapshot of the memory used by buffers and images, returned by Context::memory_stats.
+//Display prints a single line meant for a per frame log.
+#[derive(Debug, Clone)]
+pub struct MemoryStats {
+    pub heaps: Vec<HeapStats>,
+    pub total: AllocationStats,
+}
+
+impl MemoryStats {
+    pub(crate) fn new(
+        stats: &vk_mem::ffi::VmaStats,
+        properties: &vk::PhysicalDeviceMemoryProperties,
+        budget: Option<&vk::PhysicalDeviceMemoryBudgetPropertiesEXT>,
+    ) -> Self {
+        let heaps = properties.memory_heaps[..properties.memory_heap_count as usize]
+            .iter()
+            .enumerate()
+            .map(|(index, heap)| HeapStats {
+                index,
+                flags: heap.flags,
+                size: heap.size,
+                budget: budget.map_or(heap.size / 10 * 8, |budget| budget.heap_budget[index]),
+                budget_estimated: budget.is_none(),
+                allocations: AllocationStats::from(&stats.memoryHeap[index]),
+            })
+            .collect();
+
+        MemoryStats {
+            heaps,
+            total: AllocationStats::from(&stats.total),
+        }
+    }
+
+    //Bytes allocated from device local heaps
+    pub fn device_local_usage(&self) -> vk::DeviceSize {
+        self.heaps
+            .iter()
+            .filter(|heap| heap.is_device_local())
+            .map(|heap| heap.usage())
+            .sum()
+    }
+}
+
+impl fmt::Display for MemoryStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "GPU memory: {:.1} MiB used in {} blocks, {} allocations, {:.0}% fragmented |",
+            self.total.used_bytes as f64 / MIB,
+            self.total.block_count,
+            self.total.allocation_count,
+            self.total.fragmentation() * 100.0
+        )?;
+        for heap in self.heaps.iter().filter(|heap| heap.usage() > 0) {
+            write!(
+                f,
+                " heap {}: {:.1}/{:.1} MiB{}",
+                heap.index,
+                heap.usage() as f64 / MIB,
+                heap.budget as f64 / MIB,
+                if heap.budget_estimated {
+                    " (estimated budget)"
+                } else {
+                    ""
+                }
+            )?;
+        }
+        Ok(())
+    }
+}
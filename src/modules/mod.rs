@@ -1,7 +1,14 @@
+pub mod capabilities;
 pub mod debug;
 pub mod device;
 pub mod instance;
+pub mod memory_stats;
+pub mod offscreen;
+pub mod parallel;
 pub mod queue;
+pub mod recording;
+pub mod report;
+pub mod sampler_cache;
 pub mod platform;
 pub mod swapchain;
 pub mod context;
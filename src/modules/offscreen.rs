@@ -0,0 +1,105 @@
+use super::context::Context;
+use super::instance::VkThread;
+use super::swapchain::RenderTarget;
+use crate::utilities::Image;
+use ash::vk;
+use std::sync::Arc;
+
+//Color and optional depth image to render into instead of the swapchain.
+//Builds framebuffers through FramebufferSet the same way a Swapchain does.
+pub struct OffscreenTarget {
+    color: Image,
+    depth: Option<Image>,
+    color_view: vk::ImageView,
+    pub format: vk::Format,
+    pub depth_format: Option<vk::Format>,
+    pub extent: vk::Extent2D,
+    context: Arc<Context>,
+}
+
+impl OffscreenTarget {
+    pub fn new(
+        extent: vk::Extent2D,
+        format: vk::Format,
+        depth_format: Option<vk::Format>,
+        context: Arc<Context>,
+    ) -> OffscreenTarget {
+        let color = Image::attachment(
+            extent.width,
+            extent.height,
+            format,
+            vk::SampleCountFlags::TYPE_1,
+            vk::ImageUsageFlags::TRANSFER_SRC | vk::ImageUsageFlags::SAMPLED,
+            context.clone(),
+        );
+
+        let depth = depth_format.map(|depth_format| {
+            Image::attachment(
+                extent.width,
+                extent.height,
+                depth_format,
+                vk::SampleCountFlags::TYPE_1,
+                vk::ImageUsageFlags::TRANSFER_SRC,
+                context.clone(),
+            )
+        });
+
+        OffscreenTarget {
+            color_view: color.view(),
+            color,
+            depth,
+            format,
+            depth_format,
+            extent,
+            context,
+        }
+    }
+
+    pub fn color(&self) -> &Image {
+        &self.color
+    }
+
+    pub fn depth(&self) -> Option<&Image> {
+        self.depth.as_ref()
+    }
+
+    pub fn depth_view(&self) -> vk::ImageView {
+        self.depth.as_ref().expect("No depth attachment").view()
+    }
+
+    pub fn width(&self) -> u32 {
+        self.extent.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.extent.height
+    }
+
+    //Reads the color image back as RGBA8, `layout` is the layout the render pass left it in
+    pub fn read_pixels(&self, layout: vk::ImageLayout, vulkan: &VkThread) -> Vec<u8> {
+        self.color.read_pixels(layout, vulkan)
+    }
+}
+
+impl RenderTarget for OffscreenTarget {
+    fn image_views(&self) -> &[vk::ImageView] {
+        std::slice::from_ref(&self.color_view)
+    }
+
+    fn extent(&self) -> vk::Extent2D {
+        self.extent
+    }
+
+    fn format(&self) -> vk::Format {
+        self.format
+    }
+
+    //Ready to be copied back by read_pixels
+    fn final_layout(&self) -> vk::ImageLayout {
+        vk::ImageLayout::TRANSFER_SRC_OPTIMAL
+    }
+
+    fn context(&self) -> Arc<Context> {
+        self.context.clone()
+    }
+}
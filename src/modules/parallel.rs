@@ -0,0 +1,189 @@
+use super::context::Context;
+use ash::{version::DeviceV1_0, vk};
+use std::{sync::Arc, thread};
+
+//Render pass, subpass and framebuffer the secondary command buffers are executed in,
+//a null framebuffer is allowed but may be slower on some drivers
+#[derive(Debug, Clone, Copy)]
+pub struct RenderPassInheritance {
+    pub render_pass: vk::RenderPass,
+    pub subpass: u32,
+    pub framebuffer: vk::Framebuffer,
+}
+
+//Command pool of one recording thread with a secondary command buffer per frame
+struct Worker {
+    command_pool: vk::CommandPool,
+    command_buffers: Vec<vk::CommandBuffer>,
+}
+
+//Records secondary command buffers on several threads. Every worker owns its command pool,
+//pools must not be used from two threads at the same time.
+pub struct ParallelRecorder {
+    workers: Vec<Worker>,
+    context: Arc<Context>,
+}
+
+impl ParallelRecorder {
+    //`frames` secondary command buffers per worker, usually one per swapchain image
+    pub fn new(threads: usize, frames: usize, context: Arc<Context>) -> ParallelRecorder {
+        let family_index = context.queue_family.graphics_family.unwrap();
+        let workers = (0..threads.max(1))
+            .map(|_| unsafe {
+                let command_pool = context
+                    .device
+                    .create_command_pool(
+                        &vk::CommandPoolCreateInfo::builder()
+                            .flags(vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER)
+                            .queue_family_index(family_index),
+                        None,
+                    )
+                    .expect("Failed to create Command Pool!");
+
+                Worker {
+                    command_pool,
+                    command_buffers: allocate_secondaries(&context, command_pool, frames),
+                }
+            })
+            .collect();
+
+        ParallelRecorder { workers, context }
+    }
+
+    //Allocates the missing secondary command buffers when the recreated swapchain
+    //has more images than before, `frames` passed to record have to be below this
+    pub fn resize(&mut self, frames: usize) {
+        for worker in &mut self.workers {
+            if frames > worker.command_buffers.len() {
+                let missing = frames - worker.command_buffers.len();
+                worker.command_buffers.extend(allocate_secondaries(
+                    &self.context,
+                    worker.command_pool,
+                    missing,
+                ));
+            }
+        }
+    }
+
+    pub fn frames(&self) -> usize {
+        self.workers[0].command_buffers.len()
+    }
+
+    //Threads the hardware runs in parallel, a reasonable worker count
+    pub fn available_threads() -> usize {
+        thread::available_parallelism()
+            .map(|threads| threads.get())
+            .unwrap_or(1)
+    }
+
+    pub fn threads(&self) -> usize {
+        self.workers.len()
+    }
+
+    //Splits `items` into one contiguous chunk per worker and records every chunk on its own
+    //thread into the worker's secondary command buffer of `frame`. Dynamic state is not
+    //inherited, `record` has to set viewports, scissors, pipelines and descriptor sets.
+    //Returns the command buffers in item order, see execute.
+    pub fn record<T, F>(
+        &self,
+        frame: usize,
+        inheritance: RenderPassInheritance,
+        items: &[T],
+        record: F,
+    ) -> Vec<vk::CommandBuffer>
+    where
+        T: Sync,
+        F: Fn(vk::CommandBuffer, &ash::Device, &[T]) + Sync,
+    {
+        assert!(
+            frame < self.frames(),
+            "Frame {} has no secondary Command Buffers, call resize after recreating the swapchain!",
+            frame
+        );
+        let chunk_size = items.len().div_ceil(self.workers.len()).max(1);
+        let device = &self.context.device;
+        let record = &record;
+
+        thread::scope(|scope| {
+            let threads: Vec<_> = items
+                .chunks(chunk_size)
+                .zip(&self.workers)
+                .map(|(chunk, worker)| {
+                    let command_buffer = worker.command_buffers[frame];
+                    scope.spawn(move || {
+                        let inheritance_info = vk::CommandBufferInheritanceInfo::builder()
+                            .render_pass(inheritance.render_pass)
+                            .subpass(inheritance.subpass)
+                            .framebuffer(inheritance.framebuffer);
+                        let begin_info = vk::CommandBufferBeginInfo::builder()
+                            .flags(
+                                vk::CommandBufferUsageFlags::RENDER_PASS_CONTINUE
+                                    | vk::CommandBufferUsageFlags::SIMULTANEOUS_USE,
+                            )
+                            .inheritance_info(&inheritance_info);
+
+                        unsafe {
+                            device
+                                .begin_command_buffer(command_buffer, &begin_info)
+                                .expect("Failed to begin secondary Command Buffer!");
+                            record(command_buffer, device, chunk);
+                            device
+                                .end_command_buffer(command_buffer)
+                                .expect("Failed to record secondary Command Buffer!");
+                        }
+                        command_buffer
+                    })
+                })
+                .collect();
+
+            threads
+                .into_iter()
+                .map(|thread| thread.join().expect("Recording thread panicked!"))
+                .collect()
+        })
+    }
+
+    //Executes recorded secondary command buffers, the render pass has to be begun
+    //with SubpassContents::SECONDARY_COMMAND_BUFFERS
+    pub fn execute(&self, command_buffer: vk::CommandBuffer, secondaries: &[vk::CommandBuffer]) {
+        if !secondaries.is_empty() {
+            unsafe {
+                self.context
+                    .device
+                    .cmd_execute_commands(command_buffer, secondaries);
+            }
+        }
+    }
+}
+
+fn allocate_secondaries(
+    context: &Context,
+    command_pool: vk::CommandPool,
+    count: usize,
+) -> Vec<vk::CommandBuffer> {
+    unsafe {
+        context
+            .device
+            .allocate_command_buffers(
+                &vk::CommandBufferAllocateInfo::builder()
+                    .command_pool(command_pool)
+                    .level(vk::CommandBufferLevel::SECONDARY)
+                    .command_buffer_count(count as u32),
+            )
+            .expect("Failed to allocate secondary Command Buffers!")
+    }
+}
+
+impl Drop for ParallelRecorder {
+    fn drop(&mut self) {
+        unsafe {
+            self.context.wait_idle();
+            for worker in &self.workers {
+                //Destroying the pool frees its command buffers
+                self.context
+                    .device
+                    .destroy_command_pool(worker.command_pool, None);
+            }
+        }
+    }
+}
@@ -119,6 +119,12 @@ impl Queue {
         command_buffer: vk::CommandBuffer,
         image: u32,
     ) {
+        self.submit_frame(command_buffer);
+        self.present_frame(swapchain, image);
+    }
+
+    //Submits the frame without presenting it, the image can be read back before present_frame
+    pub fn submit_frame(&self, command_buffer: vk::CommandBuffer) {
         let submit_info = vk::SubmitInfo::builder()
             .wait_semaphores(&[self.image_available_semaphores[self.current_frame]])
             .wait_dst_stage_mask(&[vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT])
@@ -141,7 +147,9 @@ impl Queue {
                 )
                 .expect("Failed to execute queue submit.");
         }
+    }
 
+    pub fn present_frame(&mut self, swapchain: &Swapchain, image: u32) {
         let present_info = vk::PresentInfoKHR::builder()
             .wait_semaphores(&[self.render_finished_semaphores[self.current_frame]])
             .swapchains(&[swapchain.swapchain])
@@ -0,0 +1,70 @@
+use super::{context::Context, instance::VkThread};
+use ash::vk;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordMode {
+    //Records before every submit, for frames with per frame commands or push constants
+    EveryFrame,
+    //Records once and again only after mark_dirty, per frame values have to come
+    //from uniform or storage buffers
+    WhenDirty,
+}
+
+//Primary command buffers, one per swapchain image, recorded through VkThread::build_command
+pub struct FrameCommands {
+    command_buffers: Vec<vk::CommandBuffer>,
+    dirty: Vec<bool>,
+    mode: RecordMode,
+    context: Arc<Context>,
+}
+
+impl FrameCommands {
+    pub fn new(vulkan: &VkThread, images: usize, mode: RecordMode) -> FrameCommands {
+        FrameCommands {
+            command_buffers: vulkan.create_command_buffers(images),
+            dirty: vec![true; images],
+            mode,
+            context: vulkan.context(),
+        }
+    }
+
+    pub fn mode(&self) -> RecordMode {
+        self.mode
+    }
+
+    //Every buffer has to be recorded again, after a scene change, a resize or when
+    //a pipeline was rebuilt. Waits for the device, so none of them is pending when
+    //it is recorded again.
+    pub fn mark_dirty(&mut self) {
+        self.context.wait_idle();
+        self.dirty.iter_mut().for_each(|dirty| *dirty = true);
+    }
+
+    pub fn is_dirty(&self, image_index: usize) -> bool {
+        self.mode == RecordMode::EveryFrame || self.dirty[image_index]
+    }
+
+    //Command buffer of `image_index`, recorded with `record` first when it is dirty.
+    //A recreated swapchain can have more images, their buffers are allocated here.
+    pub fn command_buffer<F: Fn(vk::CommandBuffer, &ash::Device)>(
+        &mut self,
+        vulkan: &VkThread,
+        image_index: usize,
+        record: F,
+    ) -> vk::CommandBuffer {
+        if image_index >= self.command_buffers.len() {
+            let missing = image_index + 1 - self.command_buffers.len();
+            self.command_buffers
+                .extend(vulkan.create_command_buffers(missing));
+            self.dirty.resize(image_index + 1, true);
+        }
+
+        let command_buffer = self.command_buffers[image_index];
+        if self.is_dirty(image_index) {
+            vulkan.build_command(command_buffer, record);
+            self.dirty[image_index] = false;
+        }
+        command_buffer
+    }
+}
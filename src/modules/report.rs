@@ -0,0 +1,176 @@
+use ash::{extensions::khr::Surface, version::InstanceV1_0, vk};
+
+use super::{capabilities::DeviceCapabilities, context::create_entry, device};
+use crate::utilities::tools::vk_to_string;
+
+//Raw value of ASTC_12X12_SRGB_BLOCK, the last format of Vulkan 1.0 core.
+//Extension formats are not listed.
+const LAST_CORE_FORMAT: i32 = 184;
+
+#[derive(Debug, Clone)]
+pub struct QueueFamilyReport {
+    pub index: u32,
+    pub flags: vk::QueueFlags,
+    pub queue_count: u32,
+    pub timestamp_valid_bits: u32,
+}
+
+//Families a Context on this device would use, present is the headless pick because
+//the report has no surface. There is no separate transfer pick, copies go through
+//the graphics queue.
+#[derive(Debug, Clone, Copy)]
+pub struct QueuePicks {
+    pub graphics: Option<u32>,
+    pub compute: Option<u32>,
+    pub present: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FormatReport {
+    pub format: vk::Format,
+    pub linear_tiling: vk::FormatFeatureFlags,
+    pub optimal_tiling: vk::FormatFeatureFlags,
+    pub buffer: vk::FormatFeatureFlags,
+}
+
+#[derive(Debug, Clone)]
+pub struct MemoryHeapReport {
+    pub size: vk::DeviceSize,
+    pub flags: vk::MemoryHeapFlags,
+    //Property flags of every memory type allocated from this heap
+    pub memory_types: Vec<vk::MemoryPropertyFlags>,
+}
+
+//Everything the renderers care about for one physical device, meant for bug reports
+#[derive(Debug, Clone)]
+pub struct DeviceReport {
+    pub name: String,
+    pub device_type: vk::PhysicalDeviceType,
+    pub vendor_id: u32,
+    pub device_id: u32,
+    pub api_version: [u32; 3],
+    pub driver_version: u32,
+    pub suitable: bool,
+    pub queue_families: Vec<QueueFamilyReport>,
+    pub picks: QueuePicks,
+    pub limits: vk::PhysicalDeviceLimits,
+    pub max_sample_count: vk::SampleCountFlags,
+    pub capabilities: DeviceCapabilities,
+    pub formats: Vec<FormatReport>,
+    pub memory_heaps: Vec<MemoryHeapReport>,
+    pub extensions: Vec<String>,
+}
+
+impl DeviceReport {
+    //Reports every physical device of a new instance, in enumeration order
+    pub fn collect_all(app_name: &str) -> Vec<DeviceReport> {
+        let (entry, instance) = create_entry(app_name);
+        let surface_loader = Surface::new(&entry, &instance);
+
+        let physical_devices = unsafe {
+            instance
+                .enumerate_physical_devices()
+                .expect("Failed to enumerate Physical Devices!")
+        };
+        let reports = physical_devices
+            .iter()
+            .map(|&physical_device| Self::collect(&instance, &surface_loader, physical_device))
+            .collect();
+
+        unsafe { instance.destroy_instance(None) };
+        reports
+    }
+
+    fn collect(
+        instance: &ash::Instance,
+        surface_loader: &Surface,
+        physical_device: vk::PhysicalDevice,
+    ) -> DeviceReport {
+        let properties = unsafe { instance.get_physical_device_properties(physical_device) };
+        let queue_families =
+            unsafe { instance.get_physical_device_queue_family_properties(physical_device) };
+        let memory = unsafe { instance.get_physical_device_memory_properties(physical_device) };
+        let extensions = unsafe {
+            instance
+                .enumerate_device_extension_properties(physical_device)
+                .expect("Failed to get device extension properties.")
+        };
+
+        let indices = device::find_queue_family(instance, physical_device, surface_loader, None);
+        let version = properties.api_version;
+
+        DeviceReport {
+            name: vk_to_string(&properties.device_name),
+            device_type: properties.device_type,
+            vendor_id: properties.vendor_id,
+            device_id: properties.device_id,
+            api_version: [
+                vk::version_major(version),
+                vk::version_minor(version),
+                vk::version_patch(version),
+            ],
+            driver_version: properties.driver_version,
+            suitable: device::is_physical_device_suitable(
+                instance,
+                physical_device,
+                surface_loader,
+                None,
+                &crate::constants::DEVICE_EXTENSIONS,
+            ),
+            queue_families: queue_families
+                .iter()
+                .enumerate()
+                .map(|(index, family)| QueueFamilyReport {
+                    index: index as u32,
+                    flags: family.queue_flags,
+                    queue_count: family.queue_count,
+                    timestamp_valid_bits: family.timestamp_valid_bits,
+                })
+                .collect(),
+            picks: QueuePicks {
+                graphics: indices.graphics_family,
+                compute: indices.compute_family,
+                present: indices.present_family,
+            },
+            limits: properties.limits,
+            max_sample_count: device::max_usable_sample_count(&properties.limits),
+            capabilities: DeviceCapabilities::new(instance, physical_device),
+            formats: (1..=LAST_CORE_FORMAT)
+                .map(vk::Format::from_raw)
+                .map(|format| {
+                    let properties = unsafe {
+                        instance.get_physical_device_format_properties(physical_device, format)
+                    };
+                    FormatReport {
+                        format,
+                        linear_tiling: properties.linear_tiling_features,
+                        optimal_tiling: properties.optimal_tiling_features,
+                        buffer: properties.buffer_features,
+                    }
+                })
+                .filter(|format| {
+                    !(format.linear_tiling.is_empty()
+                        && format.optimal_tiling.is_empty()
+                        && format.buffer.is_empty())
+                })
+                .collect(),
+            memory_heaps: memory.memory_heaps[..memory.memory_heap_count as usize]
+                .iter()
+                .enumerate()
+                .map(|(heap_index, heap)| MemoryHeapReport {
+                    size: heap.size,
+                    flags: heap.flags,
+                    memory_types: memory.memory_types[..memory.memory_type_count as usize]
+                        .iter()
+                        .filter(|memory_type| memory_type.heap_index as usize == heap_index)
+                        .map(|memory_type| memory_type.property_flags)
+                        .collect(),
+                })
+                .collect(),
+            extensions: extensions
+                .iter()
+                .map(|extension| vk_to_string(&extension.extension_name))
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use ash::{version::DeviceV1_0, vk, Device};
+use std::{collections::HashMap, sync::Mutex};
+
+//Hashable copy of the fields of a vk::SamplerCreateInfo, floats are compared by their bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SamplerKey {
+    flags: vk::SamplerCreateFlags,
+    mag_filter: vk::Filter,
+    min_filter: vk::Filter,
+    mipmap_mode: vk::SamplerMipmapMode,
+    address_mode: [vk::SamplerAddressMode; 3],
+    mip_lod_bias: u32,
+    anisotropy_enable: vk::Bool32,
+    max_anisotropy: u32,
+    compare_enable: vk::Bool32,
+    compare_op: vk::CompareOp,
+    min_lod: u32,
+    max_lod: u32,
+    border_color: vk::BorderColor,
+    unnormalized_coordinates: vk::Bool32,
+}
+
+impl From<&vk::SamplerCreateInfo> for SamplerKey {
+    fn from(info: &vk::SamplerCreateInfo) -> Self {
+        SamplerKey {
+            flags: info.flags,
+            mag_filter: info.mag_filter,
+            min_filter: info.min_filter,
+            mipmap_mode: info.mipmap_mode,
+            address_mode: [
+                info.address_mode_u,
+                info.address_mode_v,
+                info.address_mode_w,
+            ],
+            mip_lod_bias: info.mip_lod_bias.to_bits(),
+            anisotropy_enable: info.anisotropy_enable,
+            max_anisotropy: info.max_anisotropy.to_bits(),
+            compare_enable: info.compare_enable,
+            compare_op: info.compare_op,
+            min_lod: info.min_lod.to_bits(),
+            max_lod: info.max_lod.to_bits(),
+            border_color: info.border_color,
+            unnormalized_coordinates: info.unnormalized_coordinates,
+        }
+    }
+}
+
+//Samplers shared by every image with the same description, they live as long as the Context.
+//Keeps the number of samplers below maxSamplerAllocationCount.
+#[derive(Default)]
+pub struct SamplerCache {
+    samplers: Mutex<HashMap<SamplerKey, vk::Sampler>>,
+}
+
+impl SamplerCache {
+    //Returns the cached sampler for `info` or creates it, p_next chains are not part of the key
+    pub fn get(&self, device: &Device, info: &vk::SamplerCreateInfo) -> vk::Sampler {
+        *self
+            .samplers
+            .lock()
+            .unwrap()
+            .entry(SamplerKey::from(info))
+            .or_insert_with(|| unsafe {
+                device
+                    .create_sampler(info, None)
+                    .expect("Failed to create Sampler!")
+            })
+    }
+
+    pub fn len(&self) -> usize {
+        self.samplers.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    //Called by the Context before the device is destroyed
+    pub(crate) fn destroy(&self, device: &Device) {
+        for (_, sampler) in self.samplers.lock().unwrap().drain() {
+            unsafe { device.destroy_sampler(sampler, None) };
+        }
+    }
+}
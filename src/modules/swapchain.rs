@@ -1,5 +1,7 @@
 use super::context::Context;
-use std::sync::Arc;
+use super::instance::VkThread;
+use crate::utilities::readback;
+use std::{io, path::Path, sync::Arc};
 
 use ash::{version::DeviceV1_0, vk};
 
@@ -38,6 +40,140 @@ impl Drop for Framebuffer {
     }
 }
 
+//Image views framebuffers get built for, implemented by Swapchain and OffscreenTarget
+pub trait RenderTarget {
+    fn image_views(&self) -> &[vk::ImageView];
+    fn extent(&self) -> vk::Extent2D;
+    fn format(&self) -> vk::Format;
+    //Layout render passes leave the color image in, PRESENT_SRC_KHR for a swapchain
+    fn final_layout(&self) -> vk::ImageLayout;
+    fn context(&self) -> Arc<Context>;
+
+    fn width(&self) -> u32 {
+        self.extent().width
+    }
+
+    fn height(&self) -> u32 {
+        self.extent().height
+    }
+}
+
+impl<T: RenderTarget> RenderTarget for &T {
+    fn image_views(&self) -> &[vk::ImageView] {
+        (**self).image_views()
+    }
+
+    fn extent(&self) -> vk::Extent2D {
+        (**self).extent()
+    }
+
+    fn format(&self) -> vk::Format {
+        (**self).format()
+    }
+
+    fn final_layout(&self) -> vk::ImageLayout {
+        (**self).final_layout()
+    }
+
+    fn context(&self) -> Arc<Context> {
+        (**self).context()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum FramebufferAttachment {
+    //Replaced with the render target image view the framebuffer belongs to
+    Swapchain,
+    View(vk::ImageView),
+}
+
+//One framebuffer for every image of a render target
+pub struct FramebufferSet {
+    framebuffers: Vec<Framebuffer>,
+    render_pass: vk::RenderPass,
+    attachments: Vec<FramebufferAttachment>,
+    context: Arc<Context>,
+}
+
+impl FramebufferSet {
+    pub fn new<T: RenderTarget>(
+        target: &T,
+        render_pass: vk::RenderPass,
+        attachments: Vec<FramebufferAttachment>,
+    ) -> FramebufferSet {
+        let mut set = FramebufferSet {
+            framebuffers: Vec::new(),
+            render_pass,
+            attachments,
+            context: target.context(),
+        };
+        set.build(target);
+        set
+    }
+
+    fn build<T: RenderTarget>(&mut self, target: &T) {
+        let extent = target.extent();
+        self.framebuffers = target
+            .image_views()
+            .iter()
+            .map(|target_view| {
+                let views: Vec<vk::ImageView> = self
+                    .attachments
+                    .iter()
+                    .map(|attachment| match attachment {
+                        FramebufferAttachment::Swapchain => *target_view,
+                        FramebufferAttachment::View(view) => *view,
+                    })
+                    .collect();
+
+                Framebuffer::new(
+                    vk::FramebufferCreateInfo::builder()
+                        .layers(1)
+                        .render_pass(self.render_pass)
+                        .attachments(&views)
+                        .width(extent.width)
+                        .height(extent.height)
+                        .build(),
+                    self.context.clone(),
+                )
+            })
+            .collect();
+    }
+
+    //Call after the swapchain is recreated, extra attachments sized to the old swapchain have to be replaced
+    pub fn rebuild<T: RenderTarget>(
+        &mut self,
+        target: &T,
+        render_pass: vk::RenderPass,
+        attachments: Vec<FramebufferAttachment>,
+    ) {
+        self.framebuffers.clear();
+        self.render_pass = render_pass;
+        self.attachments = attachments;
+        self.build(target);
+    }
+
+    pub fn buffer(&self, image_index: usize) -> vk::Framebuffer {
+        self.framebuffers[image_index].buffer()
+    }
+
+    pub fn len(&self) -> usize {
+        self.framebuffers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.framebuffers.is_empty()
+    }
+}
+
+impl std::ops::Index<usize> for FramebufferSet {
+    type Output = Framebuffer;
+
+    fn index(&self, image_index: usize) -> &Framebuffer {
+        &self.framebuffers[image_index]
+    }
+}
+
 pub struct Swapchain {
     pub swapchain_loader: ash::extensions::khr::Swapchain,
     pub swapchain: vk::SwapchainKHR,
@@ -45,6 +181,8 @@ pub struct Swapchain {
     pub image_views: Vec<vk::ImageView>,
     pub format: vk::Format,
     pub extent: vk::Extent2D,
+    //Usage the images were created with, TRANSFER_SRC only when the surface supports it
+    pub usage: vk::ImageUsageFlags,
     pub context: Arc<Context>,
 }
 
@@ -79,6 +217,11 @@ impl Swapchain {
                     (vk::SharingMode::EXCLUSIVE, 0, vec![])
                 };
             let extent = swapchain_support.capabilities.current_extent;
+
+            //Transfer source allows reading rendered frames back for screenshots
+            let image_usage = vk::ImageUsageFlags::COLOR_ATTACHMENT
+                | (swapchain_support.capabilities.supported_usage_flags
+                    & vk::ImageUsageFlags::TRANSFER_SRC);
             let swapchain = swapchain_loader
                 .create_swapchain(
                     &vk::SwapchainCreateInfoKHR {
@@ -87,7 +230,7 @@ impl Swapchain {
                         image_color_space: surface_format.color_space,
                         image_format: surface_format.format,
                         image_extent: extent,
-                        image_usage: vk::ImageUsageFlags::COLOR_ATTACHMENT,
+                        image_usage,
                         image_sharing_mode,
                         p_queue_family_indices: queue_family_indices.as_ptr(),
                         queue_family_index_count: queue_family_indices.len() as u32,
@@ -142,6 +285,7 @@ impl Swapchain {
                 images: swapchain_images,
                 format: surface_format.format,
                 extent,
+                usage: image_usage,
                 image_views: swapchain_imageviews,
                 context,
             }
@@ -159,6 +303,69 @@ impl Swapchain {
     pub fn get_image(&self, image_index: usize) -> vk::ImageView {
         self.image_views[image_index]
     }
+
+    //Whether the images can be copied back, the surface may not allow TRANSFER_SRC
+    pub fn supports_readback(&self) -> bool {
+        self.usage.contains(vk::ImageUsageFlags::TRANSFER_SRC)
+    }
+
+    //Reads a presented swapchain image back as RGBA8, call once the frame has finished rendering
+    pub fn read_pixels(&self, image_index: usize, vulkan: &VkThread) -> Vec<u8> {
+        assert!(
+            self.supports_readback(),
+            "Swapchain images were created without TRANSFER_SRC usage"
+        );
+        let data = vulkan.read_image(
+            self.images[image_index],
+            self.format,
+            vk::Extent3D {
+                width: self.extent.width,
+                height: self.extent.height,
+                depth: 1,
+            },
+            vk::ImageLayout::PRESENT_SRC_KHR,
+        );
+        readback::to_rgba8(self.format, &data)
+    }
+
+    //Saves a presented swapchain image as PNG
+    pub fn capture<P: AsRef<Path>>(
+        &self,
+        image_index: usize,
+        vulkan: &VkThread,
+        path: P,
+    ) -> io::Result<()> {
+        if !self.supports_readback() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Surface does not support TRANSFER_SRC, swapchain images cannot be captured",
+            ));
+        }
+        let pixels = self.read_pixels(image_index, vulkan);
+        readback::save_png(path, self.width(), self.height(), &pixels)
+    }
+}
+
+impl RenderTarget for Swapchain {
+    fn image_views(&self) -> &[vk::ImageView] {
+        &self.image_views
+    }
+
+    fn extent(&self) -> vk::Extent2D {
+        self.extent
+    }
+
+    fn format(&self) -> vk::Format {
+        self.format
+    }
+
+    fn final_layout(&self) -> vk::ImageLayout {
+        vk::ImageLayout::PRESENT_SRC_KHR
+    }
+
+    fn context(&self) -> Arc<Context> {
+        self.context.clone()
+    }
 }
 
 impl Drop for Swapchain {
@@ -1,2 +1,2 @@
-pub use ash::{version::DeviceV1_0, vk};
+pub use ash::{version::DeviceV1_0, vk, Device};
 pub use vk_mem;
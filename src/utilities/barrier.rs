@@ -0,0 +1,326 @@
+use super::{images::layout_access, Buffer, Image};
+use ash::{version::DeviceV1_0, vk};
+
+//Common users of a resource, the stage and access mask of either side of a barrier
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    TransferRead,
+    TransferWrite,
+    //Vertex and index buffer reads
+    VertexInput,
+    IndirectRead,
+    UniformRead,
+    VertexShaderRead,
+    FragmentRead,
+    ComputeRead,
+    ComputeWrite,
+    ComputeReadWrite,
+    ColorAttachmentWrite,
+    DepthAttachmentWrite,
+    HostRead,
+    HostWrite,
+    Custom(vk::PipelineStageFlags, vk::AccessFlags),
+}
+
+impl Access {
+    pub fn stage_access(self) -> (vk::PipelineStageFlags, vk::AccessFlags) {
+        match self {
+            Access::TransferRead => (
+                vk::PipelineStageFlags::TRANSFER,
+                vk::AccessFlags::TRANSFER_READ,
+            ),
+            Access::TransferWrite => (
+                vk::PipelineStageFlags::TRANSFER,
+                vk::AccessFlags::TRANSFER_WRITE,
+            ),
+            Access::VertexInput => (
+                vk::PipelineStageFlags::VERTEX_INPUT,
+                vk::AccessFlags::VERTEX_ATTRIBUTE_READ | vk::AccessFlags::INDEX_READ,
+            ),
+            Access::IndirectRead => (
+                vk::PipelineStageFlags::DRAW_INDIRECT,
+                vk::AccessFlags::INDIRECT_COMMAND_READ,
+            ),
+            Access::UniformRead => (
+                vk::PipelineStageFlags::VERTEX_SHADER
+                    | vk::PipelineStageFlags::FRAGMENT_SHADER
+                    | vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::AccessFlags::UNIFORM_READ,
+            ),
+            Access::VertexShaderRead => (
+                vk::PipelineStageFlags::VERTEX_SHADER,
+                vk::AccessFlags::SHADER_READ,
+            ),
+            Access::FragmentRead => (
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::AccessFlags::SHADER_READ,
+            ),
+            Access::ComputeRead => (
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::AccessFlags::SHADER_READ,
+            ),
+            Access::ComputeWrite => (
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::AccessFlags::SHADER_WRITE,
+            ),
+            Access::ComputeReadWrite => (
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE,
+            ),
+            Access::ColorAttachmentWrite => (
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+            ),
+            Access::DepthAttachmentWrite => (
+                vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                    | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+                vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            ),
+            Access::HostRead => (vk::PipelineStageFlags::HOST, vk::AccessFlags::HOST_READ),
+            Access::HostWrite => (vk::PipelineStageFlags::HOST, vk::AccessFlags::HOST_WRITE),
+            Access::Custom(stage, access) => (stage, access),
+        }
+    }
+}
+
+//Global, buffer and image barriers recorded as one vkCmdPipelineBarrier. The stage masks
+//are the union of every added barrier, e.g.
+//Barrier::new().buffer(&lights, Access::ComputeWrite, Access::FragmentRead).record(..)
+#[derive(Default)]
+pub struct Barrier {
+    src_stage: vk::PipelineStageFlags,
+    dst_stage: vk::PipelineStageFlags,
+    memory: Vec<vk::MemoryBarrier>,
+    buffers: Vec<vk::BufferMemoryBarrier>,
+    images: Vec<vk::ImageMemoryBarrier>,
+}
+
+impl Barrier {
+    pub fn new() -> Self {
+        Barrier::default()
+    }
+
+    //Covers every resource, cheaper to record than many buffer barriers
+    pub fn global(mut self, src: Access, dst: Access) -> Self {
+        let (src_access_mask, dst_access_mask) = self.add_stages(src, dst);
+        self.memory.push(
+            vk::MemoryBarrier::builder()
+                .src_access_mask(src_access_mask)
+                .dst_access_mask(dst_access_mask)
+                .build(),
+        );
+        self
+    }
+
+    //Whole buffer
+    pub fn buffer<T: Copy>(self, buffer: &Buffer<T>, src: Access, dst: Access) -> Self {
+        self.buffer_range(buffer, 0, vk::WHOLE_SIZE, src, dst)
+    }
+
+    //`size` bytes from `offset`
+    pub fn buffer_range<T: Copy>(
+        mut self,
+        buffer: &Buffer<T>,
+        offset: vk::DeviceSize,
+        size: vk::DeviceSize,
+        src: Access,
+        dst: Access,
+    ) -> Self {
+        let (src_access_mask, dst_access_mask) = self.add_stages(src, dst);
+        self.buffers.push(
+            vk::BufferMemoryBarrier::builder()
+                .src_access_mask(src_access_mask)
+                .dst_access_mask(dst_access_mask)
+                .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .buffer(buffer.buffer)
+                .offset(offset)
+                .size(size)
+                .build(),
+        );
+        self
+    }
+
+    //Moves every mip level and layer of `image` from `old_layout` to `new_layout`,
+    //stages and access masks are those of the usual users of each layout
+    pub fn image(
+        self,
+        image: &Image,
+        old_layout: vk::ImageLayout,
+        new_layout: vk::ImageLayout,
+    ) -> Self {
+        let (src_access_mask, src_stage) = layout_access(old_layout);
+        let (dst_access_mask, dst_stage) = layout_access(new_layout);
+
+        self.image_barrier(
+            src_stage,
+            dst_stage,
+            vk::ImageMemoryBarrier::builder()
+                .src_access_mask(src_access_mask)
+                .dst_access_mask(dst_access_mask)
+                .old_layout(old_layout)
+                .new_layout(new_layout)
+                .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .image(image.image())
+                .subresource_range(image.subresource_range())
+                .build(),
+        )
+    }
+
+    //For subresources and layouts `image` does not cover
+    pub fn image_barrier(
+        mut self,
+        src_stage: vk::PipelineStageFlags,
+        dst_stage: vk::PipelineStageFlags,
+        barrier: vk::ImageMemoryBarrier,
+    ) -> Self {
+        self.src_stage |= src_stage;
+        self.dst_stage |= dst_stage;
+        self.images.push(barrier);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.memory.is_empty() && self.buffers.is_empty() && self.images.is_empty()
+    }
+
+    //Records outside of a render pass, an empty batch records nothing
+    pub fn record(&self, command_buffer: vk::CommandBuffer, device: &ash::Device) {
+        if self.is_empty() {
+            return;
+        }
+
+        unsafe {
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                non_empty(self.src_stage, vk::PipelineStageFlags::TOP_OF_PIPE),
+                non_empty(self.dst_stage, vk::PipelineStageFlags::BOTTOM_OF_PIPE),
+                vk::DependencyFlags::empty(),
+                &self.memory,
+                &self.buffers,
+                &self.images,
+            );
+        }
+    }
+
+    fn add_stages(&mut self, src: Access, dst: Access) -> (vk::AccessFlags, vk::AccessFlags) {
+        let (src_stage, src_access_mask) = src.stage_access();
+        let (dst_stage, dst_access_mask) = dst.stage_access();
+        self.src_stage |= src_stage;
+        self.dst_stage |= dst_stage;
+        (src_access_mask, dst_access_mask)
+    }
+}
+
+//A stage mask of 0 is invalid
+fn non_empty(
+    stage: vk::PipelineStageFlags,
+    fallback: vk::PipelineStageFlags,
+) -> vk::PipelineStageFlags {
+    if stage.is_empty() {
+        fallback
+    } else {
+        stage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_pair_stages_with_accesses() {
+        assert_eq!(
+            Access::TransferWrite.stage_access(),
+            (
+                vk::PipelineStageFlags::TRANSFER,
+                vk::AccessFlags::TRANSFER_WRITE
+            )
+        );
+        assert_eq!(
+            Access::VertexInput.stage_access(),
+            (
+                vk::PipelineStageFlags::VERTEX_INPUT,
+                vk::AccessFlags::VERTEX_ATTRIBUTE_READ | vk::AccessFlags::INDEX_READ
+            )
+        );
+        assert_eq!(
+            Access::ComputeReadWrite.stage_access(),
+            (
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE
+            )
+        );
+        assert_eq!(
+            Access::DepthAttachmentWrite.stage_access().0,
+            vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS
+        );
+        assert_eq!(
+            Access::UniformRead.stage_access().1,
+            vk::AccessFlags::UNIFORM_READ
+        );
+
+        let custom = (
+            vk::PipelineStageFlags::GEOMETRY_SHADER,
+            vk::AccessFlags::SHADER_READ,
+        );
+        assert_eq!(Access::Custom(custom.0, custom.1).stage_access(), custom);
+    }
+
+    #[test]
+    fn stage_masks_are_the_union_of_every_barrier() {
+        let mut barrier = Barrier::new()
+            .global(Access::ComputeWrite, Access::IndirectRead)
+            .global(Access::TransferWrite, Access::VertexInput);
+        //Buffer barriers add their stages the same way, without needing a device
+        let accesses = barrier.add_stages(Access::HostWrite, Access::FragmentRead);
+
+        assert_eq!(
+            accesses,
+            (vk::AccessFlags::HOST_WRITE, vk::AccessFlags::SHADER_READ)
+        );
+        assert_eq!(
+            barrier.src_stage,
+            vk::PipelineStageFlags::COMPUTE_SHADER
+                | vk::PipelineStageFlags::TRANSFER
+                | vk::PipelineStageFlags::HOST
+        );
+        assert_eq!(
+            barrier.dst_stage,
+            vk::PipelineStageFlags::DRAW_INDIRECT
+                | vk::PipelineStageFlags::VERTEX_INPUT
+                | vk::PipelineStageFlags::FRAGMENT_SHADER
+        );
+        assert_eq!(barrier.memory.len(), 2);
+        assert_eq!(
+            barrier.memory[1].dst_access_mask,
+            vk::AccessFlags::VERTEX_ATTRIBUTE_READ | vk::AccessFlags::INDEX_READ
+        );
+    }
+
+    #[test]
+    fn empty_stage_masks_fall_back_to_top_and_bottom_of_pipe() {
+        let barrier = Barrier::new().image_barrier(
+            vk::PipelineStageFlags::empty(),
+            vk::PipelineStageFlags::empty(),
+            vk::ImageMemoryBarrier::default(),
+        );
+        assert!(!barrier.is_empty());
+        assert_eq!(
+            non_empty(barrier.src_stage, vk::PipelineStageFlags::TOP_OF_PIPE),
+            vk::PipelineStageFlags::TOP_OF_PIPE
+        );
+        assert_eq!(
+            non_empty(barrier.dst_stage, vk::PipelineStageFlags::BOTTOM_OF_PIPE),
+            vk::PipelineStageFlags::BOTTOM_OF_PIPE
+        );
+
+        let compute = vk::PipelineStageFlags::COMPUTE_SHADER;
+        assert_eq!(
+            non_empty(compute, vk::PipelineStageFlags::TOP_OF_PIPE),
+            compute
+        );
+    }
+}
@@ -0,0 +1,238 @@
+//CPU decoders for BC1-BC5, used when the device can not sample a compressed texture
+use ash::vk;
+
+//RGBA8 format the decoded texels of `format` are uploaded as
+pub fn decoded_format(format: vk::Format) -> Option<vk::Format> {
+    match format {
+        vk::Format::BC1_RGB_UNORM_BLOCK
+        | vk::Format::BC1_RGBA_UNORM_BLOCK
+        | vk::Format::BC2_UNORM_BLOCK
+        | vk::Format::BC3_UNORM_BLOCK
+        | vk::Format::BC4_UNORM_BLOCK
+        | vk::Format::BC5_UNORM_BLOCK
+        | vk::Format::B8G8R8A8_UNORM => Some(vk::Format::R8G8B8A8_UNORM),
+        vk::Format::BC1_RGB_SRGB_BLOCK
+        | vk::Format::BC1_RGBA_SRGB_BLOCK
+        | vk::Format::BC2_SRGB_BLOCK
+        | vk::Format::BC3_SRGB_BLOCK
+        | vk::Format::B8G8R8A8_SRGB => Some(vk::Format::R8G8B8A8_SRGB),
+        _ => None,
+    }
+}
+
+//Decodes consecutive `width` x `height` surfaces of `format` into tightly packed RGBA8
+pub fn decode_rgba8(format: vk::Format, width: u32, height: u32, data: &[u8]) -> Vec<u8> {
+    if let vk::Format::B8G8R8A8_UNORM | vk::Format::B8G8R8A8_SRGB = format {
+        return data
+            .chunks_exact(4)
+            .flat_map(|texel| vec![texel[2], texel[1], texel[0], texel[3]])
+            .collect();
+    }
+
+    let block_bytes = match format {
+        vk::Format::BC1_RGB_UNORM_BLOCK
+        | vk::Format::BC1_RGB_SRGB_BLOCK
+        | vk::Format::BC1_RGBA_UNORM_BLOCK
+        | vk::Format::BC1_RGBA_SRGB_BLOCK
+        | vk::Format::BC4_UNORM_BLOCK => 8,
+        _ => 16,
+    };
+    let blocks_wide = width.div_ceil(4) as usize;
+    let blocks_high = height.div_ceil(4) as usize;
+    let surface_bytes = blocks_wide * blocks_high * block_bytes;
+
+    let mut pixels = Vec::with_capacity(data.len() / surface_bytes * (width * height * 4) as usize);
+    for surface in data.chunks_exact(surface_bytes) {
+        let mut decoded = vec![0; (width * height * 4) as usize];
+        for (index, block) in surface.chunks_exact(block_bytes).enumerate() {
+            let texels = decode_block(format, block);
+            let (block_x, block_y) = ((index % blocks_wide) * 4, (index / blocks_wide) * 4);
+
+            for (texel, rgba) in texels.iter().enumerate() {
+                let (x, y) = (block_x + texel % 4, block_y + texel / 4);
+                if x < width as usize && y < height as usize {
+                    let offset = (y * width as usize + x) * 4;
+                    decoded[offset..offset + 4].copy_from_slice(rgba);
+                }
+            }
+        }
+        pixels.extend_from_slice(&decoded);
+    }
+    pixels
+}
+
+fn decode_block(format: vk::Format, block: &[u8]) -> [[u8; 4]; 16] {
+    match format {
+        vk::Format::BC1_RGB_UNORM_BLOCK | vk::Format::BC1_RGB_SRGB_BLOCK => {
+            let mut texels = color_block(block, true);
+            texels.iter_mut().for_each(|texel| texel[3] = 255);
+            texels
+        }
+        vk::Format::BC1_RGBA_UNORM_BLOCK | vk::Format::BC1_RGBA_SRGB_BLOCK => {
+            color_block(block, true)
+        }
+        vk::Format::BC2_UNORM_BLOCK | vk::Format::BC2_SRGB_BLOCK => {
+            let mut texels = color_block(&block[8..], false);
+            for (index, texel) in texels.iter_mut().enumerate() {
+                let alpha = (block[index / 2] >> ((index % 2) * 4)) & 0xf;
+                texel[3] = alpha * 17;
+            }
+            texels
+        }
+        vk::Format::BC3_UNORM_BLOCK | vk::Format::BC3_SRGB_BLOCK => {
+            let mut texels = color_block(&block[8..], false);
+            let alpha = channel_block(&block[..8]);
+            for (texel, alpha) in texels.iter_mut().zip(alpha.iter()) {
+                texel[3] = *alpha;
+            }
+            texels
+        }
+        vk::Format::BC4_UNORM_BLOCK => {
+            let mut texels = [[0, 0, 0, 255]; 16];
+            for (texel, red) in texels.iter_mut().zip(channel_block(block).iter()) {
+                texel[0] = *red;
+            }
+            texels
+        }
+        vk::Format::BC5_UNORM_BLOCK => {
+            let mut texels = [[0, 0, 0, 255]; 16];
+            let red = channel_block(&block[..8]);
+            let green = channel_block(&block[8..]);
+            for (index, texel) in texels.iter_mut().enumerate() {
+                texel[0] = red[index];
+                texel[1] = green[index];
+            }
+            texels
+        }
+        _ => panic!("No CPU decoder for {:?}", format),
+    }
+}
+
+//Two RGB565 endpoints and 2 bit indices, BC1 switches to 3 colors and
+//transparent black when the first endpoint is not larger
+fn color_block(block: &[u8], punch_through: bool) -> [[u8; 4]; 16] {
+    let color0 = u16::from_le_bytes([block[0], block[1]]);
+    let color1 = u16::from_le_bytes([block[2], block[3]]);
+    let (c0, c1) = (rgb565(color0), rgb565(color1));
+
+    let mix = |a: u8, b: u8, wa: u32, wb: u32| ((a as u32 * wa + b as u32 * wb) / (wa + wb)) as u8;
+    let blend = |wa, wb| {
+        [
+            mix(c0[0], c1[0], wa, wb),
+            mix(c0[1], c1[1], wa, wb),
+            mix(c0[2], c1[2], wa, wb),
+            255,
+        ]
+    };
+
+    let palette = if color0 > color1 || !punch_through {
+        [c0, c1, blend(2, 1), blend(1, 2)]
+    } else {
+        [c0, c1, blend(1, 1), [0, 0, 0, 0]]
+    };
+
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    let mut texels = [[0; 4]; 16];
+    for (index, texel) in texels.iter_mut().enumerate() {
+        *texel = palette[((indices >> (index * 2)) & 0x3) as usize];
+    }
+    texels
+}
+
+//Two 8 bit endpoints and 3 bit indices, shared by BC3 alpha, BC4 and BC5
+fn channel_block(block: &[u8]) -> [u8; 16] {
+    let (a, b) = (block[0] as u32, block[1] as u32);
+    let mut palette = [0u8; 8];
+    palette[0] = a as u8;
+    palette[1] = b as u8;
+    if a > b {
+        for i in 1..7 {
+            palette[i + 1] = (((7 - i as u32) * a + i as u32 * b) / 7) as u8;
+        }
+    } else {
+        for i in 1..5 {
+            palette[i + 1] = (((5 - i as u32) * a + i as u32 * b) / 5) as u8;
+        }
+        palette[6] = 0;
+        palette[7] = 255;
+    }
+
+    let indices = block[2..8]
+        .iter()
+        .rev()
+        .fold(0u64, |bits, &byte| (bits << 8) | byte as u64);
+    let mut values = [0; 16];
+    for (index, value) in values.iter_mut().enumerate() {
+        *value = palette[((indices >> (index * 3)) & 0x7) as usize];
+    }
+    values
+}
+
+fn rgb565(color: u16) -> [u8; 4] {
+    let red = ((color >> 11) & 0x1f) as u32;
+    let green = ((color >> 5) & 0x3f) as u32;
+    let blue = (color & 0x1f) as u32;
+    [
+        (red * 255 / 31) as u8,
+        (green * 255 / 63) as u8,
+        (blue * 255 / 31) as u8,
+        255,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: u16 = 0xf800;
+    const BLUE: u16 = 0x001f;
+
+    //Texel i uses palette entry i % 4
+    fn bc1_block(color0: u16, color1: u16) -> Vec<u8> {
+        let mut block = color0.to_le_bytes().to_vec();
+        block.extend_from_slice(&color1.to_le_bytes());
+        block.extend_from_slice(&[0b1110_0100; 4]);
+        block
+    }
+
+    #[test]
+    fn bc1_four_color_block() {
+        let pixels = decode_rgba8(
+            vk::Format::BC1_RGBA_UNORM_BLOCK,
+            4,
+            4,
+            &bc1_block(RED, BLUE),
+        );
+        assert_eq!(pixels.len(), 64);
+        assert_eq!(
+            &pixels[..16],
+            &[255, 0, 0, 255, 0, 0, 255, 255, 170, 0, 85, 255, 85, 0, 170, 255]
+        );
+        assert_eq!(&pixels[48..52], &[255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn bc1_punch_through_alpha() {
+        let block = bc1_block(BLUE, RED);
+        assert_eq!(
+            &decode_rgba8(vk::Format::BC1_RGBA_UNORM_BLOCK, 4, 4, &block)[8..16],
+            &[127, 0, 127, 255, 0, 0, 0, 0]
+        );
+        //BC1 without alpha keeps the texel opaque
+        assert_eq!(
+            &decode_rgba8(vk::Format::BC1_RGB_UNORM_BLOCK, 4, 4, &block)[12..16],
+            &[0, 0, 0, 255]
+        );
+    }
+
+    #[test]
+    fn partial_blocks_are_cropped() {
+        let pixels = decode_rgba8(
+            vk::Format::BC1_RGBA_UNORM_BLOCK,
+            2,
+            1,
+            &bc1_block(RED, BLUE),
+        );
+        assert_eq!(pixels, vec![255, 0, 0, 255, 0, 0, 255, 255]);
+    }
+}
@@ -1,22 +1,30 @@
 use crate::Context;
 use ash::vk;
-use std::sync::Arc;
+use std::{marker::PhantomData, mem, ops::Range, ptr, sync::Arc};
 
-#[derive(Clone)]
-pub struct Buffer {
+//Buffer of `len` T elements, host visible memory stays mapped for its whole lifetime.
+//Not Clone, the buffer is destroyed when the owner is dropped, share it with Arc instead.
+pub struct Buffer<T: Copy = u8> {
     pub buffer: vk::Buffer,
     pub allocation: vk_mem::Allocation,
     pub allocation_info: vk_mem::AllocationInfo,
     pub size: vk::DeviceSize,
     pub context: Arc<Context>,
+    len: usize,
+    mapped: *mut u8,
+    _type: PhantomData<T>,
 }
 
-impl Buffer {
+//The mapped pointer is owned by the allocation, moving the buffer to another thread is fine.
+//Not Sync, write and upload_to_buffer copy through the pointer from &self.
+unsafe impl<T: Copy + Send> Send for Buffer<T> {}
+
+impl<T: Copy> Buffer<T> {
     pub fn new(
         allocation_create_info: &vk_mem::AllocationCreateInfo,
         buffer_create_info: &vk::BufferCreateInfo,
         context: Arc<Context>,
-    ) -> Buffer {
+    ) -> Buffer<T> {
         let (buffer, allocation, allocation_info) = context
             .memory
             .create_buffer(buffer_create_info, &allocation_create_info)
@@ -24,21 +32,33 @@ impl Buffer {
 
         Buffer {
             buffer,
+            mapped: allocation_info.get_mapped_data(),
             allocation,
             allocation_info,
             size: buffer_create_info.size,
             context,
+            len: buffer_create_info.size as usize / mem::size_of::<T>().max(1),
+            _type: PhantomData,
         }
     }
 
+    //Creates a buffer of `size` bytes, host visible memory usages are mapped persistently
     pub fn new_mapped_basic(
         size: vk::DeviceSize,
         buffer_usage: vk::BufferUsageFlags,
         memory_usage: vk_mem::MemoryUsage,
         context: Arc<Context>,
     ) -> Self {
+        let flags = match memory_usage {
+            vk_mem::MemoryUsage::GpuOnly | vk_mem::MemoryUsage::Unknown => {
+                vk_mem::AllocationCreateFlags::NONE
+            }
+            _ => vk_mem::AllocationCreateFlags::MAPPED,
+        };
+
         let allocation_create_info = vk_mem::AllocationCreateInfo {
             usage: memory_usage,
+            flags,
             ..Default::default()
         };
 
@@ -51,9 +71,71 @@ impl Buffer {
         Buffer::new(&allocation_create_info, &buffer_create_info, context)
     }
 
-    pub fn upload_to_buffer<T: Copy>(&self, data: &[T], offset: u64) {
-        let alignment = std::mem::align_of::<T>() as _;
-        let data_pointer = self.map_memory().expect("Failed to map memory!");
+    //Creates a buffer with room for `len` elements
+    pub fn with_len(
+        len: usize,
+        buffer_usage: vk::BufferUsageFlags,
+        memory_usage: vk_mem::MemoryUsage,
+        context: Arc<Context>,
+    ) -> Self {
+        Self::new_mapped_basic(
+            (len * mem::size_of::<T>()) as u64,
+            buffer_usage,
+            memory_usage,
+            context,
+        )
+    }
+
+    //Creates a host visible buffer holding `data`
+    pub fn from_data(
+        data: &[T],
+        buffer_usage: vk::BufferUsageFlags,
+        memory_usage: vk_mem::MemoryUsage,
+        context: Arc<Context>,
+    ) -> Self {
+        let buffer = Self::with_len(data.len(), buffer_usage, memory_usage, context);
+        buffer.write(0..data.len(), data);
+        buffer
+    }
+
+    //Writes `data` into the elements of `range` and flushes them
+    pub fn write(&self, range: Range<usize>, data: &[T]) {
+        assert_eq!(range.len(), data.len(), "Write range and data length differ!");
+        assert!(range.end <= self.len, "Write range is out of bounds!");
+
+        let offset = range.start * mem::size_of::<T>();
+        let size = mem::size_of_val(data);
+        let mapped = self.mapped_pointer();
+        unsafe {
+            ptr::copy_nonoverlapping(data.as_ptr() as *const u8, mapped.add(offset), size);
+        }
+        self.flush(offset, size).expect("Failed to flush memory!");
+        self.release_pointer();
+    }
+
+    //Copies the whole buffer back after invalidating the host cache
+    pub fn read(&self) -> Vec<T> {
+        let size = self.len * mem::size_of::<T>();
+        self.invalidate(0, size)
+            .expect("Failed to invalidate memory!");
+
+        let mapped = self.mapped_pointer();
+        let mut data = Vec::with_capacity(self.len);
+        unsafe {
+            ptr::copy_nonoverlapping(mapped, data.as_mut_ptr() as *mut u8, size);
+            data.set_len(self.len);
+        }
+        self.release_pointer();
+        data
+    }
+
+    //Writes data of any type at a byte offset
+    pub fn upload_to_buffer<U: Copy>(&self, data: &[U], offset: u64) {
+        let size = mem::size_of_val(data) as u64;
+        assert!(offset + size <= self.size, "Upload range is out of bounds!");
+
+        let alignment = std::mem::align_of::<U>() as _;
+        let data_pointer = self.mapped_pointer();
         unsafe {
             let mut align = ash::util::Align::new(
                 data_pointer.add(offset as usize) as _,
@@ -62,7 +144,22 @@ impl Buffer {
             );
             align.copy_from_slice(data);
         }
-        self.unmap_memory().unwrap();
+        self.release_pointer();
+    }
+
+    //Persistent mapping if there is one, otherwise a temporary one
+    fn mapped_pointer(&self) -> *mut u8 {
+        if self.mapped.is_null() {
+            self.map_memory().expect("Failed to map memory!")
+        } else {
+            self.mapped
+        }
+    }
+
+    fn release_pointer(&self) {
+        if self.mapped.is_null() {
+            self.unmap_memory().unwrap();
+        }
     }
 
     pub fn map_memory(&self) -> vk_mem::error::Result<*mut u8> {
@@ -79,6 +176,25 @@ impl Buffer {
             .flush_allocation(&self.allocation, offset, size)
     }
 
+    pub fn invalidate(&self, offset: usize, size: usize) -> vk_mem::error::Result<()> {
+        self.context
+            .memory
+            .invalidate_allocation(&self.allocation, offset, size)
+    }
+
+    pub fn is_mapped(&self) -> bool {
+        !self.mapped.is_null()
+    }
+
+    //Number of T elements
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
     pub fn allocation_size(&self) -> u64 {
         self.allocation_info.get_size() as u64
     }
@@ -88,7 +204,7 @@ impl Buffer {
     }
 }
 
-impl Drop for Buffer {
+impl<T: Copy> Drop for Buffer<T> {
     fn drop(&mut self) {
         self.context.wait_idle();
         self.context
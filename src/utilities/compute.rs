@@ -0,0 +1,199 @@
+use super::{tools::as_byte_slice, Access, Barrier, Buffer, Descriptor, Shader};
+use crate::{Context, VkThread};
+use ash::{version::DeviceV1_0, vk};
+use std::{ffi::CString, mem, path::Path, sync::Arc};
+
+//Compute shader with its descriptor sets, bound as sets 0..n in the given order,
+//and an optional push constant range starting at offset 0
+pub struct ComputePipeline {
+    pipeline: vk::Pipeline,
+    layout: vk::PipelineLayout,
+    descriptors: Vec<Descriptor>,
+    push_constant_size: u32,
+    context: Arc<Context>,
+}
+
+impl ComputePipeline {
+    pub fn new(
+        shader: &Path,
+        descriptors: Vec<Descriptor>,
+        push_constant_size: u32,
+        context: Arc<Context>,
+    ) -> ComputePipeline {
+        let set_layouts: Vec<vk::DescriptorSetLayout> = descriptors
+            .iter()
+            .map(|descriptor| descriptor.layout)
+            .collect();
+        let push_constant_ranges = Self::ranges(push_constant_size);
+
+        let layout = unsafe {
+            context
+                .device
+                .create_pipeline_layout(
+                    &vk::PipelineLayoutCreateInfo::builder()
+                        .set_layouts(&set_layouts)
+                        .push_constant_ranges(&push_constant_ranges)
+                        .build(),
+                    None,
+                )
+                .expect("Failed to create compute PipelineLayout!")
+        };
+
+        let entry_name = CString::new("main").unwrap();
+        let shader = Shader::new(
+            shader,
+            vk::ShaderStageFlags::COMPUTE,
+            &entry_name,
+            context.clone(),
+        );
+        let pipeline = unsafe {
+            context
+                .device
+                .create_compute_pipelines(
+                    vk::PipelineCache::null(),
+                    &[vk::ComputePipelineCreateInfo::builder()
+                        .stage(shader.info())
+                        .layout(layout)
+                        .build()],
+                    None,
+                )
+                .expect("Failed to create compute Pipeline!")
+        }[0];
+
+        ComputePipeline {
+            pipeline,
+            layout,
+            descriptors,
+            push_constant_size,
+            context,
+        }
+    }
+
+    //Workgroups needed to cover `items` invocations
+    pub fn group_count(items: u32, local_size: u32) -> u32 {
+        items.div_ceil(local_size)
+    }
+
+    pub fn pipeline(&self) -> vk::Pipeline {
+        self.pipeline
+    }
+
+    pub fn layout(&self) -> vk::PipelineLayout {
+        self.layout
+    }
+
+    pub fn descriptors(&self) -> &[Descriptor] {
+        &self.descriptors
+    }
+
+    pub fn push_constant_ranges(&self) -> Vec<vk::PushConstantRange> {
+        Self::ranges(self.push_constant_size)
+    }
+
+    fn ranges(push_constant_size: u32) -> Vec<vk::PushConstantRange> {
+        if push_constant_size > 0 {
+            vec![vk::PushConstantRange {
+                stage_flags: vk::ShaderStageFlags::COMPUTE,
+                offset: 0,
+                size: push_constant_size,
+            }]
+        } else {
+            Vec::new()
+        }
+    }
+
+    //Binds the pipeline and all descriptor sets
+    pub fn bind(&self, command_buffer: vk::CommandBuffer) {
+        let sets: Vec<vk::DescriptorSet> = self
+            .descriptors
+            .iter()
+            .map(|descriptor| descriptor.set)
+            .collect();
+
+        unsafe {
+            self.context.device.cmd_bind_pipeline(
+                command_buffer,
+                vk::PipelineBindPoint::COMPUTE,
+                self.pipeline,
+            );
+            if !sets.is_empty() {
+                self.context.device.cmd_bind_descriptor_sets(
+                    command_buffer,
+                    vk::PipelineBindPoint::COMPUTE,
+                    self.layout,
+                    0,
+                    &sets,
+                    &[],
+                );
+            }
+        }
+    }
+
+    pub fn push_constants<T: Copy>(&self, command_buffer: vk::CommandBuffer, constants: &T) {
+        assert!(
+            mem::size_of::<T>() as u32 <= self.push_constant_size,
+            "Push constants are larger than the pipeline range!"
+        );
+
+        unsafe {
+            self.context.device.cmd_push_constants(
+                command_buffer,
+                self.layout,
+                vk::ShaderStageFlags::COMPUTE,
+                0,
+                as_byte_slice(constants),
+            );
+        }
+    }
+
+    //Binds the pipeline and dispatches `groups` workgroups
+    pub fn dispatch(&self, command_buffer: vk::CommandBuffer, groups: [u32; 3]) {
+        self.bind(command_buffer);
+        unsafe {
+            self.context
+                .device
+                .cmd_dispatch(command_buffer, groups[0], groups[1], groups[2]);
+        }
+    }
+
+    //One dimensional dispatch of at least `items` invocations, the shader has to
+    //skip the invocations past `items` when it is not a multiple of `local_size`
+    pub fn dispatch_for(&self, command_buffer: vk::CommandBuffer, items: u32, local_size: u32) {
+        self.dispatch(command_buffer, [Self::group_count(items, local_size), 1, 1]);
+    }
+
+    //Dispatches once, waits for the queue of `vulkan` and returns the contents of `output`,
+    //which has to be host visible. Meant for tools and tests, not for per frame work.
+    pub fn run_and_read<P: Copy, T: Copy>(
+        &self,
+        vulkan: &VkThread,
+        groups: [u32; 3],
+        push_constants: &P,
+        output: &Buffer<T>,
+    ) -> Vec<T> {
+        let command_buffer = vulkan.begin_single_time_command();
+        if mem::size_of::<P>() > 0 {
+            self.push_constants(command_buffer, push_constants);
+        }
+        self.dispatch(command_buffer, groups);
+
+        Barrier::new()
+            .buffer(output, Access::ComputeWrite, Access::HostRead)
+            .record(command_buffer, &self.context.device);
+        vulkan.end_single_time_command(command_buffer);
+
+        output.read()
+    }
+}
+
+impl Drop for ComputePipeline {
+    fn drop(&mut self) {
+        unsafe {
+            self.context.wait_idle();
+            self.context.device.destroy_pipeline(self.pipeline, None);
+            self.context
+                .device
+                .destroy_pipeline_layout(self.layout, None);
+        }
+    }
+}
@@ -0,0 +1,309 @@
+use super::{
+    tools::as_byte_slice, Barrier, Buffer, ComputePipeline, Descriptor, Pipeline, Renderpass,
+};
+use crate::Framebuffer;
+use ash::{version::DeviceV1_0, vk};
+use std::mem;
+
+//Layout state of a bound pipeline, descriptor sets and push constants use it
+struct BoundPipeline {
+    layout: vk::PipelineLayout,
+    bind_point: vk::PipelineBindPoint,
+    //None when the layout is unknown, push constants are not checked then
+    push_constant_ranges: Option<Vec<vk::PushConstantRange>>,
+}
+
+//Records into a command buffer through the wrapper types instead of raw handles.
+//Debug builds check that a pipeline is bound before drawing or dispatching and that
+//push constants fit a range of its layout when the layout ranges are known.
+pub struct CommandEncoder<'a> {
+    command_buffer: vk::CommandBuffer,
+    device: &'a ash::Device,
+    //Graphics and compute pipelines are bound independently,
+    //sets and push constants go to the one bound last
+    graphics: Option<BoundPipeline>,
+    compute: Option<BoundPipeline>,
+    last_bind_point: vk::PipelineBindPoint,
+}
+
+impl<'a> CommandEncoder<'a> {
+    //Wraps a command buffer that is already recording, like the one passed
+    //to the VkThread::build_command callback
+    pub fn new(command_buffer: vk::CommandBuffer, device: &'a ash::Device) -> CommandEncoder<'a> {
+        CommandEncoder {
+            command_buffer,
+            device,
+            graphics: None,
+            compute: None,
+            last_bind_point: vk::PipelineBindPoint::GRAPHICS,
+        }
+    }
+
+    //For commands the encoder does not cover
+    pub fn command_buffer(&self) -> vk::CommandBuffer {
+        self.command_buffer
+    }
+
+    //Barriers have to be recorded outside of a render pass
+    pub fn pipeline_barrier(&mut self, barrier: &Barrier) {
+        barrier.record(self.command_buffer, self.device);
+    }
+
+    pub fn begin_render_pass(
+        &mut self,
+        renderpass: &Renderpass,
+        framebuffer: &Framebuffer,
+        render_area: vk::Rect2D,
+        clear_values: &[vk::ClearValue],
+        contents: vk::SubpassContents,
+    ) {
+        let begin_info = vk::RenderPassBeginInfo::builder()
+            .render_pass(renderpass.pass())
+            .framebuffer(framebuffer.buffer())
+            .render_area(render_area)
+            .clear_values(clear_values);
+
+        unsafe {
+            self.device
+                .cmd_begin_render_pass(self.command_buffer, &begin_info, contents);
+        }
+    }
+
+    pub fn next_subpass(&mut self, contents: vk::SubpassContents) {
+        unsafe {
+            self.device.cmd_next_subpass(self.command_buffer, contents);
+        }
+    }
+
+    pub fn end_render_pass(&mut self) {
+        unsafe {
+            self.device.cmd_end_render_pass(self.command_buffer);
+        }
+    }
+
+    //Sets viewport and scissor to cover `render_area` with a 0..1 depth range
+    pub fn set_viewport(&mut self, render_area: vk::Rect2D) {
+        let viewport = vk::Viewport {
+            x: render_area.offset.x as f32,
+            y: render_area.offset.y as f32,
+            width: render_area.extent.width as f32,
+            height: render_area.extent.height as f32,
+            min_depth: 0.0,
+            max_depth: 1.0,
+        };
+
+        unsafe {
+            self.device
+                .cmd_set_viewport(self.command_buffer, 0, &[viewport]);
+            self.device
+                .cmd_set_scissor(self.command_buffer, 0, &[render_area]);
+        }
+    }
+
+    //Binds the pipeline at `index` of `pipeline`
+    pub fn bind_pipeline(&mut self, pipeline: &Pipeline, index: usize) {
+        let bind_point = pipeline.bind_point(index);
+        unsafe {
+            self.device.cmd_bind_pipeline(
+                self.command_buffer,
+                bind_point,
+                pipeline.pipeline(index),
+            );
+        }
+
+        self.set_bound(BoundPipeline {
+            layout: pipeline.layout_of(index),
+            bind_point,
+            push_constant_ranges: pipeline
+                .push_constant_ranges(index)
+                .map(|ranges| ranges.to_vec()),
+        });
+    }
+
+    //Binds only the pipeline, bind its descriptor sets with bind_descriptor_sets
+    pub fn bind_compute_pipeline(&mut self, pipeline: &ComputePipeline) {
+        unsafe {
+            self.device.cmd_bind_pipeline(
+                self.command_buffer,
+                vk::PipelineBindPoint::COMPUTE,
+                pipeline.pipeline(),
+            );
+        }
+
+        self.set_bound(BoundPipeline {
+            layout: pipeline.layout(),
+            bind_point: vk::PipelineBindPoint::COMPUTE,
+            push_constant_ranges: Some(pipeline.push_constant_ranges()),
+        });
+    }
+
+    //Binds `descriptors` as the sets `first_set..` of the bound pipeline
+    pub fn bind_descriptor_sets(&mut self, first_set: u32, descriptors: &[&Descriptor]) {
+        let bound = self.bound();
+        let sets: Vec<vk::DescriptorSet> = descriptors
+            .iter()
+            .map(|descriptor| descriptor.set)
+            .collect();
+
+        unsafe {
+            self.device.cmd_bind_descriptor_sets(
+                self.command_buffer,
+                bound.bind_point,
+                bound.layout,
+                first_set,
+                &sets,
+                &[],
+            );
+        }
+    }
+
+    //Binds `buffers` to the bindings `first_binding..` with byte offsets
+    pub fn bind_vertex_buffers<T: Copy>(
+        &mut self,
+        first_binding: u32,
+        buffers: &[(&Buffer<T>, vk::DeviceSize)],
+    ) {
+        let (handles, offsets): (Vec<vk::Buffer>, Vec<vk::DeviceSize>) = buffers
+            .iter()
+            .map(|(buffer, offset)| (buffer.buffer, *offset))
+            .unzip();
+
+        unsafe {
+            self.device.cmd_bind_vertex_buffers(
+                self.command_buffer,
+                first_binding,
+                &handles,
+                &offsets,
+            );
+        }
+    }
+
+    //Index type follows the element type, u16 or u32
+    pub fn bind_index_buffer<T: Copy>(&mut self, buffer: &Buffer<T>, offset: vk::DeviceSize) {
+        let index_type = match mem::size_of::<T>() {
+            2 => vk::IndexType::UINT16,
+            4 => vk::IndexType::UINT32,
+            _ => panic!("Index buffers have to hold u16 or u32!"),
+        };
+
+        unsafe {
+            self.device.cmd_bind_index_buffer(
+                self.command_buffer,
+                buffer.buffer,
+                offset,
+                index_type,
+            );
+        }
+    }
+
+    pub fn push_constants<T: Copy>(
+        &mut self,
+        stages: vk::ShaderStageFlags,
+        offset: u32,
+        constants: &T,
+    ) {
+        let bound = self.bound();
+        let size = mem::size_of::<T>() as u32;
+        debug_assert!(
+            bound.push_constant_ranges.as_ref().is_none_or(|ranges| {
+                ranges.iter().any(|range| {
+                    range.stage_flags.contains(stages)
+                        && range.offset <= offset
+                        && offset + size <= range.offset + range.size
+                })
+            }),
+            "Push constants at {}..{} for {:?} are outside of the pipeline layout ranges!",
+            offset,
+            offset + size,
+            stages
+        );
+
+        unsafe {
+            self.device.cmd_push_constants(
+                self.command_buffer,
+                bound.layout,
+                stages,
+                offset,
+                as_byte_slice(constants),
+            );
+        }
+    }
+
+    pub fn draw(
+        &mut self,
+        vertex_count: u32,
+        instance_count: u32,
+        first_vertex: u32,
+        first_instance: u32,
+    ) {
+        self.debug_check_bound(vk::PipelineBindPoint::GRAPHICS);
+        unsafe {
+            self.device.cmd_draw(
+                self.command_buffer,
+                vertex_count,
+                instance_count,
+                first_vertex,
+                first_instance,
+            );
+        }
+    }
+
+    pub fn draw_indexed(
+        &mut self,
+        index_count: u32,
+        instance_count: u32,
+        first_index: u32,
+        vertex_offset: i32,
+        first_instance: u32,
+    ) {
+        self.debug_check_bound(vk::PipelineBindPoint::GRAPHICS);
+        unsafe {
+            self.device.cmd_draw_indexed(
+                self.command_buffer,
+                index_count,
+                instance_count,
+                first_index,
+                vertex_offset,
+                first_instance,
+            );
+        }
+    }
+
+    pub fn dispatch(&mut self, groups: [u32; 3]) {
+        self.debug_check_bound(vk::PipelineBindPoint::COMPUTE);
+        unsafe {
+            self.device
+                .cmd_dispatch(self.command_buffer, groups[0], groups[1], groups[2]);
+        }
+    }
+
+    fn slot(&self, bind_point: vk::PipelineBindPoint) -> Option<&BoundPipeline> {
+        if bind_point == vk::PipelineBindPoint::COMPUTE {
+            self.compute.as_ref()
+        } else {
+            self.graphics.as_ref()
+        }
+    }
+
+    fn set_bound(&mut self, bound: BoundPipeline) {
+        self.last_bind_point = bound.bind_point;
+        if bound.bind_point == vk::PipelineBindPoint::COMPUTE {
+            self.compute = Some(bound);
+        } else {
+            self.graphics = Some(bound);
+        }
+    }
+
+    fn bound(&self) -> &BoundPipeline {
+        self.slot(self.last_bind_point)
+            .expect("Bind a pipeline before its descriptor sets and push constants!")
+    }
+
+    fn debug_check_bound(&self, bind_point: vk::PipelineBindPoint) {
+        debug_assert!(
+            self.slot(bind_point).is_some(),
+            "No {:?} pipeline is bound!",
+            bind_point
+        );
+    }
+}
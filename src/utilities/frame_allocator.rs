@@ -0,0 +1,112 @@
+use super::layout::align_up;
+use super::Buffer;
+use crate::{constants::MAX_FRAMES_IN_FLIGHT, Context};
+use ash::vk;
+use std::{mem, sync::Arc};
+
+//Slice handed out by FrameAllocator, pass `offset` as the dynamic offset when binding
+#[derive(Debug, Clone, Copy)]
+pub struct FrameAllocation {
+    pub offset: u32,
+    pub size: u64,
+}
+
+//Per-frame linear allocator over one persistently mapped buffer.
+//Every frame in flight owns its own region, so data written for the current frame
+//never overwrites data the GPU still reads for the previous one.
+pub struct FrameAllocator {
+    buffer: Buffer,
+    frame_size: u64,
+    //minStorageBufferOffsetAlignment or minUniformBufferOffsetAlignment, depending on usage
+    min_alignment: u64,
+    storage: bool,
+    frame: usize,
+    cursor: u64,
+}
+
+impl FrameAllocator {
+    //`usage` has to be UNIFORM_BUFFER or STORAGE_BUFFER, frame_size is the capacity per frame
+    pub fn new(frame_size: u64, usage: vk::BufferUsageFlags, context: Arc<Context>) -> Self {
+        let storage = usage.contains(vk::BufferUsageFlags::STORAGE_BUFFER);
+        let min_alignment = if storage {
+            context.get_min_storage_buffer_offset_alignment() as u64
+        } else {
+            context.get_min_uniform_buffer_offset_alignment() as u64
+        };
+        let frame_size = align_up(frame_size as usize, min_alignment as usize) as u64;
+
+        let buffer = Buffer::new_mapped_basic(
+            frame_size * MAX_FRAMES_IN_FLIGHT as u64,
+            usage,
+            vk_mem::MemoryUsage::CpuToGpu,
+            context,
+        );
+
+        Self {
+            buffer,
+            frame_size,
+            min_alignment,
+            storage,
+            frame: 0,
+            cursor: 0,
+        }
+    }
+
+    //Starts writing into the region of `frame`, call after Queue::load_next_frame
+    //waited on that frame's fence, e.g. `allocator.reset(queue.current_frame)`
+    pub fn reset(&mut self, frame: usize) {
+        self.frame = frame % MAX_FRAMES_IN_FLIGHT;
+        self.cursor = 0;
+    }
+
+    //Copies `data` into the current frame region at the next aligned offset.
+    //Uniform allocations are padded to size_of::<T>() rounded up to the minimum alignment.
+    pub fn allocate<T: Copy>(&mut self, data: &[T]) -> FrameAllocation {
+        let size = mem::size_of_val(data) as u64;
+        let alignment = if self.storage {
+            self.min_alignment
+        } else {
+            align_up(mem::size_of::<T>(), self.min_alignment as usize) as u64
+        };
+        let stride = align_up(size as usize, alignment as usize) as u64;
+        assert!(
+            self.cursor + stride <= self.frame_size,
+            "Frame allocator is out of memory!"
+        );
+
+        let offset = self.frame as u64 * self.frame_size + self.cursor;
+        self.buffer.upload_to_buffer(data, offset);
+        self.buffer
+            .flush(offset as usize, size as usize)
+            .expect("Failed to flush memory!");
+        self.cursor += stride;
+
+        FrameAllocation {
+            offset: offset as u32,
+            size,
+        }
+    }
+
+    //Descriptor info for UNIFORM_BUFFER_DYNAMIC or STORAGE_BUFFER_DYNAMIC bindings,
+    //`range` is the size a single allocation is read as, usually size_of::<T>()
+    pub fn descriptor_info(&self, range: u64) -> vk::DescriptorBufferInfo {
+        vk::DescriptorBufferInfo {
+            buffer: self.buffer.buffer,
+            offset: 0,
+            range,
+        }
+    }
+
+    //Bytes used in the current frame
+    pub fn used(&self) -> u64 {
+        self.cursor
+    }
+
+    pub fn frame_size(&self) -> u64 {
+        self.frame_size
+    }
+
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+}
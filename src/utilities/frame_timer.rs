@@ -0,0 +1,247 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::hint;
+use std::thread;
+use std::time::{Duration, Instant};
+
+//Frames the percentiles are taken from, a few seconds at common refresh rates
+const DEFAULT_WINDOW: usize = 300;
+//thread::sleep can overshoot by about a scheduler tick, the rest of a limited frame is spun
+const SPIN_MARGIN: Duration = Duration::from_millis(2);
+//Steps run at most per tick, so a long stall does not have to be caught up all at once
+const MAX_FIXED_STEPS: u32 = 8;
+
+//Frame times of the rolling window. The percentiles are the frame time the slowest
+//1% and 5% of frames reach and the median.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameTimeStats {
+    pub fps: f32,
+    pub one_percent: Duration,
+    pub five_percent: Duration,
+    pub median: Duration,
+}
+
+impl fmt::Display for FrameTimeStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ms = |time: Duration| time.as_secs_f64() * 1000.0;
+        write!(
+            f,
+            "FPS: {:.1} 1%: {:.2}ms 5%: {:.2}ms 50%: {:.2}ms",
+            self.fps,
+            ms(self.one_percent),
+            ms(self.five_percent),
+            ms(self.median)
+        )
+    }
+}
+
+//Measures frame times, optionally limits the frame rate and runs a fixed timestep.
+//Call tick once per frame.
+pub struct FrameTimer {
+    last_tick: Instant,
+    delta: Duration,
+    samples: VecDeque<Duration>,
+    window: usize,
+    target_frame_time: Option<Duration>,
+    fixed_step: Duration,
+    accumulator: Duration,
+}
+
+impl FrameTimer {
+    pub fn new() -> FrameTimer {
+        FrameTimer::with_window(DEFAULT_WINDOW)
+    }
+
+    //Keeps the last `window` frame times for fps and stats
+    pub fn with_window(window: usize) -> FrameTimer {
+        FrameTimer {
+            last_tick: Instant::now(),
+            delta: Duration::ZERO,
+            samples: VecDeque::with_capacity(window.max(1)),
+            window: window.max(1),
+            target_frame_time: None,
+            fixed_step: Duration::from_secs(1) / 60,
+            accumulator: Duration::ZERO,
+        }
+    }
+
+    //None, a non positive rate or one too small for a Duration disables the limiter
+    pub fn set_target_fps(&mut self, fps: Option<f32>) {
+        self.target_frame_time = fps
+            .filter(|fps| *fps > 0.0)
+            .and_then(|fps| Duration::try_from_secs_f32(1.0 / fps).ok());
+    }
+
+    pub fn set_fixed_step(&mut self, step: Duration) {
+        assert!(step > Duration::ZERO, "The fixed step has to be positive!");
+        self.fixed_step = step;
+    }
+
+    pub fn fixed_step(&self) -> Duration {
+        self.fixed_step
+    }
+
+    //Ends the frame: records the time since the last tick and adds it to the fixed
+    //timestep accumulator
+    pub fn tick(&mut self) -> Duration {
+        let now = Instant::now();
+        self.record(now - self.last_tick);
+        self.last_tick = now;
+        self.delta
+    }
+
+    fn record(&mut self, delta: Duration) {
+        self.delta = delta;
+
+        if self.samples.len() == self.window {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(delta);
+
+        self.accumulator = (self.accumulator + delta).min(self.fixed_step * MAX_FIXED_STEPS);
+    }
+
+    //Time between the last two ticks
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    //Sleeps until the target frame time passed since the last tick, the last
+    //SPIN_MARGIN is spun because sleeping is not precise enough
+    pub fn limit(&self) {
+        if let Some(frame_time) = self.target_frame_time {
+            let deadline = self.last_tick + frame_time;
+            let now = Instant::now();
+            if deadline > now + SPIN_MARGIN {
+                thread::sleep(deadline - now - SPIN_MARGIN);
+            }
+            while Instant::now() < deadline {
+                hint::spin_loop();
+            }
+        }
+    }
+
+    //Number of fixed steps to simulate this frame, consumed from the accumulator
+    pub fn fixed_steps(&mut self) -> u32 {
+        let mut steps = 0;
+        while self.accumulator >= self.fixed_step {
+            self.accumulator -= self.fixed_step;
+            steps += 1;
+        }
+        steps
+    }
+
+    //Fraction of a fixed step left in the accumulator, to interpolate between
+    //the last two simulated states
+    pub fn alpha(&self) -> f32 {
+        self.accumulator.as_secs_f32() / self.fixed_step.as_secs_f32()
+    }
+
+    //Average frames per second of the window
+    pub fn fps(&self) -> f32 {
+        let total: Duration = self.samples.iter().sum();
+        if total.is_zero() {
+            0.0
+        } else {
+            self.samples.len() as f32 / total.as_secs_f32()
+        }
+    }
+
+    pub fn stats(&self) -> FrameTimeStats {
+        let mut sorted: Vec<Duration> = self.samples.iter().copied().collect();
+        sorted.sort_unstable();
+        //Frame time that `fraction` of the frames reach or exceed
+        let slowest = |fraction: f32| {
+            sorted
+                .len()
+                .checked_sub(1)
+                .map(|last| sorted[last - (last as f32 * fraction) as usize])
+                .unwrap_or_default()
+        };
+
+        FrameTimeStats {
+            fps: self.fps(),
+            one_percent: slowest(0.01),
+            five_percent: slowest(0.05),
+            median: slowest(0.5),
+        }
+    }
+}
+
+impl Default for FrameTimer {
+    fn default() -> FrameTimer {
+        FrameTimer::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn invalid_target_fps_disables_the_limiter() {
+        let mut timer = FrameTimer::new();
+        timer.set_target_fps(Some(50.0));
+        assert_eq!(timer.target_frame_time, Some(ms(20)));
+
+        for fps in &[0.0, -1.0, 1e-39, f32::NAN] {
+            timer.set_target_fps(Some(*fps));
+            assert_eq!(timer.target_frame_time, None);
+        }
+    }
+
+    #[test]
+    fn percentiles_pick_the_slowest_frames() {
+        let mut timer = FrameTimer::with_window(100);
+        for frame in 1..=100 {
+            timer.record(ms(frame));
+        }
+
+        let stats = timer.stats();
+        assert_eq!(stats.one_percent, ms(100));
+        assert_eq!(stats.five_percent, ms(96));
+        assert_eq!(stats.median, ms(51));
+        assert!((stats.fps - 100.0 / 5.05).abs() < 1e-3);
+    }
+
+    #[test]
+    fn window_keeps_the_latest_frames() {
+        let mut timer = FrameTimer::with_window(2);
+        timer.record(ms(100));
+        timer.record(ms(10));
+        timer.record(ms(10));
+        assert_eq!(timer.stats().one_percent, ms(10));
+        assert!((timer.fps() - 100.0).abs() < 1e-3);
+        assert_eq!(FrameTimer::new().stats().median, Duration::ZERO);
+    }
+
+    #[test]
+    fn accumulator_is_clamped_to_max_fixed_steps() {
+        let mut timer = FrameTimer::new();
+        timer.set_fixed_step(ms(10));
+        timer.record(Duration::from_secs(5));
+        assert_eq!(timer.fixed_steps(), MAX_FIXED_STEPS);
+        assert_eq!(timer.fixed_steps(), 0);
+    }
+
+    #[test]
+    fn alpha_is_the_fraction_of_a_step_left() {
+        let mut timer = FrameTimer::new();
+        timer.set_fixed_step(ms(10));
+        timer.record(ms(25));
+        assert_eq!(timer.fixed_steps(), 2);
+        assert!((timer.alpha() - 0.5).abs() < 1e-4);
+
+        timer.record(ms(5));
+        assert_eq!(timer.fixed_steps(), 1);
+        assert!(timer.alpha().abs() < 1e-4);
+    }
+}
@@ -0,0 +1,362 @@
+use crate::{constants::MAX_FRAMES_IN_FLIGHT, Context};
+use ash::{
+    version::{DeviceV1_0, InstanceV1_0},
+    vk,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    fs, io,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+//Timings kept per scope for averages and percentiles
+const HISTORY_LEN: usize = 240;
+//Events kept for the Chrome trace
+const TRACE_LEN: usize = 20_000;
+//Query ranges of older frames are read back, the GPU finished them by then
+const FRAME_SLOTS: usize = MAX_FRAMES_IN_FLIGHT + 1;
+
+//Timing statistics of one scope in milliseconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScopeStats {
+    pub samples: usize,
+    pub average: f64,
+    pub min: f64,
+    pub max: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+}
+
+impl ScopeStats {
+    pub fn from_samples(samples: &[f64]) -> Option<ScopeStats> {
+        if samples.is_empty() {
+            return None;
+        }
+
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        //Nearest rank percentile
+        let percentile = |p: f64| {
+            let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        };
+
+        Some(ScopeStats {
+            samples: sorted.len(),
+            average: sorted.iter().sum::<f64>() / sorted.len() as f64,
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            p50: percentile(50.0),
+            p95: percentile(95.0),
+            p99: percentile(99.0),
+        })
+    }
+}
+
+struct Scope {
+    name: String,
+    query: u32,
+}
+
+#[derive(Default)]
+struct FrameSlot {
+    scopes: Vec<Scope>,
+    queries: u32,
+    recorded: bool,
+}
+
+struct TraceEvent {
+    name: String,
+    start: f64,
+    duration: f64,
+}
+
+struct State {
+    slots: Vec<FrameSlot>,
+    frame: usize,
+    //Scope names in the order they were first seen
+    names: Vec<String>,
+    history: HashMap<String, VecDeque<f64>>,
+    trace: VecDeque<TraceEvent>,
+    //First timestamp read back, trace times are relative to it
+    origin: Option<u64>,
+}
+
+//Named GPU timings from timestamp queries written into command buffers.
+//Call begin_frame at the start of every recorded frame, then wrap passes in
+//begin_scope/end_scope. Results of a frame are read back FRAME_SLOTS frames later
+//without waiting on the GPU. When the graphics queue has no timestamp support every call is a no-op.
+pub struct GpuProfiler {
+    query_pool: vk::QueryPool,
+    max_scopes: u32,
+    //Nanoseconds per timestamp tick
+    timestamp_period: f64,
+    valid_mask: u64,
+    state: Mutex<State>,
+    context: Arc<Context>,
+}
+
+impl GpuProfiler {
+    pub fn new(max_scopes: u32, context: Arc<Context>) -> GpuProfiler {
+        let (limits, valid_bits) = unsafe {
+            let limits = context
+                .instance
+                .get_physical_device_properties(context.physical_device)
+                .limits;
+            let families = context
+                .instance
+                .get_physical_device_queue_family_properties(context.physical_device);
+            let graphics_family = context.queue_family.graphics_family.unwrap() as usize;
+            (limits, families[graphics_family].timestamp_valid_bits)
+        };
+
+        let query_pool = if valid_bits > 0 && limits.timestamp_period > 0.0 {
+            unsafe {
+                context
+                    .device
+                    .create_query_pool(
+                        &vk::QueryPoolCreateInfo {
+                            s_type: vk::StructureType::QUERY_POOL_CREATE_INFO,
+                            query_type: vk::QueryType::TIMESTAMP,
+                            query_count: max_scopes * 2 * FRAME_SLOTS as u32,
+                            ..Default::default()
+                        },
+                        None,
+                    )
+                    .expect("Failed to create timestamp QueryPool!")
+            }
+        } else {
+            vk::QueryPool::null()
+        };
+
+        GpuProfiler {
+            query_pool,
+            max_scopes,
+            timestamp_period: limits.timestamp_period as f64,
+            valid_mask: if valid_bits >= 64 {
+                u64::MAX
+            } else {
+                (1u64 << valid_bits) - 1
+            },
+            state: Mutex::new(State {
+                slots: (0..FRAME_SLOTS).map(|_| FrameSlot::default()).collect(),
+                frame: 0,
+                names: Vec::new(),
+                history: HashMap::new(),
+                trace: VecDeque::new(),
+                origin: None,
+            }),
+            context,
+        }
+    }
+
+    pub fn is_supported(&self) -> bool {
+        self.query_pool != vk::QueryPool::null()
+    }
+
+    //Collects the timings of the slot about to be reused and resets its queries,
+    //record outside of a render pass before any scope of the frame
+    pub fn begin_frame(&self, command_buffer: vk::CommandBuffer) {
+        if !self.is_supported() {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.frame = (state.frame + 1) % FRAME_SLOTS;
+        let frame = state.frame;
+        self.collect(&mut state, frame);
+
+        unsafe {
+            self.context.device.cmd_reset_query_pool(
+                command_buffer,
+                self.query_pool,
+                self.first_query(frame),
+                self.max_scopes * 2,
+            );
+        }
+        let slot = &mut state.slots[frame];
+        slot.scopes.clear();
+        slot.queries = 0;
+        slot.recorded = true;
+    }
+
+    //Writes the start timestamp of `name`, returns the scope to pass to end_scope
+    pub fn begin_scope(&self, command_buffer: vk::CommandBuffer, name: &str) -> Option<usize> {
+        if !self.is_supported() {
+            return None;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        let frame = state.frame;
+        let slot = &mut state.slots[frame];
+        if slot.queries + 2 > self.max_scopes * 2 {
+            return None;
+        }
+
+        let query = self.first_query(frame) + slot.queries;
+        slot.queries += 2;
+        slot.scopes.push(Scope {
+            name: name.to_string(),
+            query,
+        });
+        let scope = slot.scopes.len() - 1;
+
+        unsafe {
+            self.context.device.cmd_write_timestamp(
+                command_buffer,
+                vk::PipelineStageFlags::TOP_OF_PIPE,
+                self.query_pool,
+                query,
+            );
+        }
+        Some(scope)
+    }
+
+    pub fn end_scope(&self, command_buffer: vk::CommandBuffer, scope: Option<usize>) {
+        let scope = match scope {
+            Some(scope) => scope,
+            None => return,
+        };
+
+        let state = self.state.lock().unwrap();
+        let query = state.slots[state.frame].scopes[scope].query + 1;
+        unsafe {
+            self.context.device.cmd_write_timestamp(
+                command_buffer,
+                vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                self.query_pool,
+                query,
+            );
+        }
+    }
+
+    //Records `record` between the begin and end timestamps of `name`
+    pub fn scope<F: FnOnce()>(&self, command_buffer: vk::CommandBuffer, name: &str, record: F) {
+        let scope = self.begin_scope(command_buffer, name);
+        record();
+        self.end_scope(command_buffer, scope);
+    }
+
+    pub fn stats(&self, name: &str) -> Option<ScopeStats> {
+        let state = self.state.lock().unwrap();
+        let history = state.history.get(name)?;
+        ScopeStats::from_samples(&history.iter().cloned().collect::<Vec<_>>())
+    }
+
+    //Statistics of every scope in the order they were first recorded
+    pub fn report(&self) -> Vec<(String, ScopeStats)> {
+        let names = self.state.lock().unwrap().names.clone();
+        names
+            .into_iter()
+            .filter_map(|name| self.stats(&name).map(|stats| (name, stats)))
+            .collect()
+    }
+
+    //Short per pass averages for a log line, e.g. "shadow 0.21ms gbuffer 1.30ms"
+    pub fn summary(&self) -> String {
+        self.report()
+            .iter()
+            .map(|(name, stats)| format!("{} {:.2}ms", name, stats.average))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    //Recorded scopes in the Chrome trace event format, open in chrome://tracing or Perfetto
+    pub fn chrome_trace_json(&self) -> String {
+        let state = self.state.lock().unwrap();
+        let events: Vec<String> = state
+            .trace
+            .iter()
+            .map(|event| {
+                format!(
+                    "{{\"name\":\"{}\",\"cat\":\"gpu\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\"pid\":0,\"tid\":0}}",
+                    event.name.replace('\\', "\\\\").replace('"', "\\\""),
+                    event.start * 1000.0,
+                    event.duration * 1000.0
+                )
+            })
+            .collect();
+        format!("{{\"traceEvents\":[{}]}}", events.join(","))
+    }
+
+    pub fn save_chrome_trace<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.chrome_trace_json())
+    }
+
+    fn first_query(&self, frame: usize) -> u32 {
+        frame as u32 * self.max_scopes * 2
+    }
+
+    //Reads the timestamps of `frame` if they are available, drops them otherwise
+    fn collect(&self, state: &mut State, frame: usize) {
+        let slot = &mut state.slots[frame];
+        if !slot.recorded || slot.queries == 0 {
+            return;
+        }
+        slot.recorded = false;
+
+        let mut timestamps = vec![0u64; slot.queries as usize];
+        let available = unsafe {
+            self.context.device.get_query_pool_results(
+                self.query_pool,
+                self.first_query(frame),
+                slot.queries,
+                &mut timestamps,
+                vk::QueryResultFlags::TYPE_64,
+            )
+        };
+        if available.is_err() {
+            return;
+        }
+
+        let first_query = self.first_query(frame);
+        let scopes: Vec<(String, u64, u64)> = slot
+            .scopes
+            .drain(..)
+            .map(|scope| {
+                let index = (scope.query - first_query) as usize;
+                (scope.name, timestamps[index], timestamps[index + 1])
+            })
+            .collect();
+
+        for (name, begin, end) in scopes {
+            let to_ms = |ticks: u64| ticks as f64 * self.timestamp_period / 1_000_000.0;
+            let origin = *state.origin.get_or_insert(begin);
+            let duration = to_ms(end.wrapping_sub(begin) & self.valid_mask);
+            let start = to_ms(begin.wrapping_sub(origin) & self.valid_mask);
+
+            if !state.history.contains_key(&name) {
+                state.names.push(name.clone());
+            }
+            let history = state.history.entry(name.clone()).or_default();
+            if history.len() == HISTORY_LEN {
+                history.pop_front();
+            }
+            history.push_back(duration);
+
+            if state.trace.len() == TRACE_LEN {
+                state.trace.pop_front();
+            }
+            state.trace.push_back(TraceEvent {
+                name,
+                start,
+                duration,
+            });
+        }
+    }
+}
+
+impl Drop for GpuProfiler {
+    fn drop(&mut self) {
+        if self.is_supported() {
+            unsafe {
+                self.context.wait_idle();
+                self.context
+                    .device
+                    .destroy_query_pool(self.query_pool, None);
+            }
+        }
+    }
+}
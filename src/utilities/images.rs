@@ -1,13 +1,17 @@
-use crate::Context;
+use super::{block_decode, mipmap, readback, Barrier, Buffer, TextureFile};
+use crate::{Context, VkThread};
 use ash::version::DeviceV1_0;
 use ash::vk;
-use std::sync::Arc;
+use std::{io, sync::Arc};
 
 pub struct Image {
     image: vk::Image,
     pub allocation: vk_mem::Allocation,
     pub allication_info: vk_mem::AllocationInfo,
     pub format: vk::Format,
+    extent: vk::Extent3D,
+    mip_levels: u32,
+    array_layers: u32,
     image_view: Option<vk::ImageView>,
     sampler: Option<vk::Sampler>,
     context: Arc<Context>,
@@ -33,13 +37,439 @@ impl Image {
             allocation,
             allication_info: info,
             format: image_info.format,
+            extent: image_info.extent,
+            mip_levels: image_info.mip_levels,
+            array_layers: image_info.array_layers,
             image_view: None,
             sampler: None,
             context: context.clone(),
         }
     }
 
+    //2D texture sampled in shaders, with a view and a linear repeating sampler
+    pub fn texture_2d(
+        width: u32,
+        height: u32,
+        mip_levels: u32,
+        format: vk::Format,
+        context: Arc<Context>,
+    ) -> Image {
+        Self::texture(
+            vk::ImageViewType::TYPE_2D,
+            vk::Extent3D {
+                width,
+                height,
+                depth: 1,
+            },
+            mip_levels,
+            1,
+            format,
+            context,
+        )
+    }
+
+    //Layered 2D texture, sampled as sampler2DArray
+    pub fn texture_array(
+        width: u32,
+        height: u32,
+        layers: u32,
+        mip_levels: u32,
+        format: vk::Format,
+        context: Arc<Context>,
+    ) -> Image {
+        Self::texture(
+            vk::ImageViewType::TYPE_2D_ARRAY,
+            vk::Extent3D {
+                width,
+                height,
+                depth: 1,
+            },
+            mip_levels,
+            layers,
+            format,
+            context,
+        )
+    }
+
+    //Six square layers in +X, -X, +Y, -Y, +Z, -Z order, sampled as samplerCube
+    pub fn cubemap(size: u32, mip_levels: u32, format: vk::Format, context: Arc<Context>) -> Image {
+        Self::texture(
+            vk::ImageViewType::CUBE,
+            vk::Extent3D {
+                width: size,
+                height: size,
+                depth: 1,
+            },
+            mip_levels,
+            6,
+            format,
+            context,
+        )
+    }
+
+    //3D texture, sampled as sampler3D
+    pub fn volume(
+        width: u32,
+        height: u32,
+        depth: u32,
+        format: vk::Format,
+        context: Arc<Context>,
+    ) -> Image {
+        Self::texture(
+            vk::ImageViewType::TYPE_3D,
+            vk::Extent3D {
+                width,
+                height,
+                depth,
+            },
+            1,
+            1,
+            format,
+            context,
+        )
+    }
+
+    //Render target, COLOR_ATTACHMENT or DEPTH_STENCIL_ATTACHMENT usage is added from the format.
+    //Gets a view but no sampler, attach one when the attachment is sampled later.
+    pub fn attachment(
+        width: u32,
+        height: u32,
+        format: vk::Format,
+        samples: vk::SampleCountFlags,
+        usage: vk::ImageUsageFlags,
+        context: Arc<Context>,
+    ) -> Image {
+        let attachment_usage = if readback::is_depth_format(format) {
+            vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT
+        } else {
+            vk::ImageUsageFlags::COLOR_ATTACHMENT
+        };
+
+        let mut image = Image::create_image(
+            vk::ImageCreateInfo {
+                s_type: vk::StructureType::IMAGE_CREATE_INFO,
+                image_type: vk::ImageType::TYPE_2D,
+                format,
+                extent: vk::Extent3D {
+                    width,
+                    height,
+                    depth: 1,
+                },
+                mip_levels: 1,
+                array_layers: 1,
+                samples,
+                tiling: vk::ImageTiling::OPTIMAL,
+                usage: usage | attachment_usage,
+                sharing_mode: vk::SharingMode::EXCLUSIVE,
+                ..Default::default()
+            },
+            vk_mem::MemoryUsage::GpuOnly,
+            context,
+        );
+        image.attach_view(image.view_info(vk::ImageViewType::TYPE_2D));
+        image
+    }
+
+    //Uploads tightly packed R8G8B8A8 pixels into a 2D texture with a full mip chain,
+    //left in SHADER_READ_ONLY_OPTIMAL. Mips are blitted on the GPU when the format supports
+    //linear filtering, otherwise they are generated on the CPU.
+    pub fn from_rgba8(width: u32, height: u32, pixels: &[u8], vulkan: &VkThread) -> Image {
+        assert_eq!(
+            pixels.len(),
+            (width * height * 4) as usize,
+            "Pixel data does not match the image size!"
+        );
+
+        let format = vk::Format::R8G8B8A8_UNORM;
+        let mip_levels = mipmap::mip_levels(width, height);
+        let image = Image::texture_2d(width, height, mip_levels, format, vulkan.context());
+
+        if mip_levels == 1 {
+            image.upload(pixels, vulkan);
+            image.transition(
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                vulkan,
+            );
+        } else if vulkan.context().supports_format_features(
+            format,
+            vk::ImageTiling::OPTIMAL,
+            vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR,
+        ) {
+            image.upload(pixels, vulkan);
+            vulkan.generate_mipmaps(image.image, width, height, mip_levels);
+        } else {
+            image.upload_mips(&mipmap::generate_rgba8(width, height, pixels), vulkan);
+            image.transition(
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                vulkan,
+            );
+        }
+        image
+    }
+
+    //Uploads a KTX2 or DDS texture with all of its mips, faces and layers, left in
+    //SHADER_READ_ONLY_OPTIMAL. Formats the device can not sample are decoded into RGBA8,
+    //formats without a CPU decoder are an error.
+    pub fn from_texture_file(file: &TextureFile, vulkan: &VkThread) -> io::Result<Image> {
+        let context = vulkan.context();
+        let supported = |format| {
+            context.supports_format_features(
+                format,
+                vk::ImageTiling::OPTIMAL,
+                vk::FormatFeatureFlags::SAMPLED_IMAGE,
+            )
+        };
+
+        let (format, levels) = if supported(file.format) {
+            (file.format, file.levels.clone())
+        } else {
+            let format = block_decode::decoded_format(file.format)
+                .filter(|&format| supported(format))
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Texture format {:?} is not supported!", file.format),
+                    )
+                })?;
+            let levels = file
+                .levels
+                .iter()
+                .enumerate()
+                .map(|(level, data)| {
+                    let (width, height) =
+                        mipmap::level_extent(file.extent.width, file.extent.height, level as u32);
+                    block_decode::decode_rgba8(file.format, width, height, data)
+                })
+                .collect();
+            (format, levels)
+        };
+
+        let image = Self::texture(
+            file.view_type(),
+            file.extent,
+            file.mip_levels,
+            file.array_layers,
+            format,
+            context,
+        );
+        image.upload_mips(&levels, vulkan);
+        image.transition(
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            vulkan,
+        );
+        Ok(image)
+    }
+
+    fn texture(
+        view_type: vk::ImageViewType,
+        extent: vk::Extent3D,
+        mip_levels: u32,
+        array_layers: u32,
+        format: vk::Format,
+        context: Arc<Context>,
+    ) -> Image {
+        let (image_type, flags) = match view_type {
+            vk::ImageViewType::TYPE_3D => (vk::ImageType::TYPE_3D, vk::ImageCreateFlags::empty()),
+            vk::ImageViewType::CUBE | vk::ImageViewType::CUBE_ARRAY => (
+                vk::ImageType::TYPE_2D,
+                vk::ImageCreateFlags::CUBE_COMPATIBLE,
+            ),
+            _ => (vk::ImageType::TYPE_2D, vk::ImageCreateFlags::empty()),
+        };
+
+        let mut image = Image::create_image(
+            vk::ImageCreateInfo {
+                s_type: vk::StructureType::IMAGE_CREATE_INFO,
+                flags,
+                image_type,
+                format,
+                extent,
+                mip_levels,
+                array_layers,
+                samples: vk::SampleCountFlags::TYPE_1,
+                tiling: vk::ImageTiling::OPTIMAL,
+                //TRANSFER_SRC so mip levels can be blitted from the level above
+                usage: vk::ImageUsageFlags::TRANSFER_SRC
+                    | vk::ImageUsageFlags::TRANSFER_DST
+                    | vk::ImageUsageFlags::SAMPLED,
+                sharing_mode: vk::SharingMode::EXCLUSIVE,
+                ..Default::default()
+            },
+            vk_mem::MemoryUsage::GpuOnly,
+            context,
+        );
+
+        let address_mode = match view_type {
+            vk::ImageViewType::CUBE | vk::ImageViewType::CUBE_ARRAY => {
+                vk::SamplerAddressMode::CLAMP_TO_EDGE
+            }
+            _ => vk::SamplerAddressMode::REPEAT,
+        };
+
+        image.attach_view(image.view_info(view_type));
+        image.attach_sampler(vk::SamplerCreateInfo {
+            s_type: vk::StructureType::SAMPLER_CREATE_INFO,
+            mag_filter: vk::Filter::LINEAR,
+            min_filter: vk::Filter::LINEAR,
+            mipmap_mode: vk::SamplerMipmapMode::LINEAR,
+            address_mode_u: address_mode,
+            address_mode_v: address_mode,
+            address_mode_w: address_mode,
+            min_lod: 0.0,
+            max_lod: mip_levels as f32,
+            mip_lod_bias: 0.0,
+            anisotropy_enable: vk::TRUE,
+            max_anisotropy: 16.0,
+            ..Default::default()
+        });
+        image
+    }
+
+    //View over every mip level and layer of the image
+    pub fn view_info(&self, view_type: vk::ImageViewType) -> vk::ImageViewCreateInfo {
+        let mut subresource_range = self.subresource_range();
+        //Views of depth stencil images may only select one aspect
+        if subresource_range
+            .aspect_mask
+            .contains(vk::ImageAspectFlags::DEPTH)
+        {
+            subresource_range.aspect_mask = vk::ImageAspectFlags::DEPTH;
+        }
+
+        vk::ImageViewCreateInfo {
+            s_type: vk::StructureType::IMAGE_VIEW_CREATE_INFO,
+            view_type,
+            format: self.format,
+            image: self.image,
+            components: vk::ComponentMapping {
+                r: vk::ComponentSwizzle::IDENTITY,
+                g: vk::ComponentSwizzle::IDENTITY,
+                b: vk::ComponentSwizzle::IDENTITY,
+                a: vk::ComponentSwizzle::IDENTITY,
+            },
+            subresource_range,
+            ..Default::default()
+        }
+    }
+
+    //Whole image, aspect mask derived from the format
+    pub fn subresource_range(&self) -> vk::ImageSubresourceRange {
+        vk::ImageSubresourceRange {
+            aspect_mask: aspect_mask(self.format),
+            base_mip_level: 0,
+            level_count: self.mip_levels,
+            base_array_layer: 0,
+            layer_count: self.array_layers,
+        }
+    }
+
+    //Moves every mip level and layer from `old_layout` to `new_layout`
+    pub fn transition(
+        &self,
+        old_layout: vk::ImageLayout,
+        new_layout: vk::ImageLayout,
+        vulkan: &VkThread,
+    ) {
+        vulkan.apply_barrier(&Barrier::new().image(self, old_layout, new_layout));
+    }
+
+    //Copies tightly packed texels of every layer into mip level 0 and leaves the image
+    //in TRANSFER_DST_OPTIMAL, ready for mip generation or a transition to shader reads
+    pub fn upload<T: Copy>(&self, data: &[T], vulkan: &VkThread) {
+        let buffer = Buffer::from_data(
+            data,
+            vk::BufferUsageFlags::TRANSFER_SRC,
+            vk_mem::MemoryUsage::CpuOnly,
+            vulkan.context(),
+        );
+
+        self.transition(
+            vk::ImageLayout::UNDEFINED,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            vulkan,
+        );
+
+        vulkan.copy_buffer_to_image(
+            buffer.buffer,
+            self.image,
+            vec![vk::BufferImageCopy {
+                image_subresource: vk::ImageSubresourceLayers {
+                    aspect_mask: aspect_mask(self.format),
+                    mip_level: 0,
+                    base_array_layer: 0,
+                    layer_count: self.array_layers,
+                },
+                image_extent: self.extent,
+                buffer_offset: 0,
+                buffer_image_height: 0,
+                buffer_row_length: 0,
+                image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
+            }],
+        );
+    }
+
+    //Copies one tightly packed buffer per mip level, each holding every layer of the level,
+    //and leaves the image in TRANSFER_DST_OPTIMAL
+    pub fn upload_mips<T: Copy>(&self, levels: &[Vec<T>], vulkan: &VkThread) {
+        assert_eq!(
+            levels.len(),
+            self.mip_levels as usize,
+            "Mip data does not match the mip level count!"
+        );
+
+        let data: Vec<T> = levels.iter().flatten().cloned().collect();
+        let buffer = Buffer::from_data(
+            &data,
+            vk::BufferUsageFlags::TRANSFER_SRC,
+            vk_mem::MemoryUsage::CpuOnly,
+            vulkan.context(),
+        );
+
+        self.transition(
+            vk::ImageLayout::UNDEFINED,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            vulkan,
+        );
+
+        let mut buffer_offset = 0;
+        let regions = levels
+            .iter()
+            .enumerate()
+            .map(|(level, texels)| {
+                let (width, height) =
+                    mipmap::level_extent(self.extent.width, self.extent.height, level as u32);
+                let region = vk::BufferImageCopy {
+                    image_subresource: vk::ImageSubresourceLayers {
+                        aspect_mask: aspect_mask(self.format),
+                        mip_level: level as u32,
+                        base_array_layer: 0,
+                        layer_count: self.array_layers,
+                    },
+                    image_extent: vk::Extent3D {
+                        width,
+                        height,
+                        depth: (self.extent.depth >> level).max(1),
+                    },
+                    buffer_offset,
+                    buffer_image_height: 0,
+                    buffer_row_length: 0,
+                    image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
+                };
+                buffer_offset += (texels.len() * std::mem::size_of::<T>()) as vk::DeviceSize;
+                region
+            })
+            .collect();
+
+        vulkan.copy_buffer_to_image(buffer.buffer, self.image, regions);
+    }
+
     pub fn attach_view(&mut self, image_info: vk::ImageViewCreateInfo) {
+        if let Some(view) = self.image_view.take() {
+            unsafe { self.context.device.destroy_image_view(view, None) };
+        }
         self.image_view = Some(unsafe {
             self.context
                 .device
@@ -48,13 +478,9 @@ impl Image {
         });
     }
 
+    //Replaces the sampler with a shared one from the context's SamplerCache
     pub fn attach_sampler(&mut self, sampler_info: vk::SamplerCreateInfo) {
-        self.sampler = Some(unsafe {
-            self.context
-                .device
-                .create_sampler(&sampler_info, None)
-                .expect("Failed to create Sampler!")
-        });
+        self.sampler = Some(self.context.sampler(&sampler_info));
     }
 
     pub fn image(&self) -> vk::Image {
@@ -69,16 +495,36 @@ impl Image {
         self.sampler.expect("No sampler attached")
     }
 
+    pub fn extent(&self) -> vk::Extent3D {
+        self.extent
+    }
+
+    pub fn mip_levels(&self) -> u32 {
+        self.mip_levels
+    }
+
+    pub fn array_layers(&self) -> u32 {
+        self.array_layers
+    }
+
+    //Copies the image back as RGBA8 rows, `layout` is the layout the image is currently in
+    pub fn read_pixels(&self, layout: vk::ImageLayout, vulkan: &VkThread) -> Vec<u8> {
+        let data = vulkan.read_image(self.image, self.format, self.extent, layout);
+        readback::to_rgba8(self.format, &data)
+    }
+
+    //Same as read_pixels but keeps full precision for float and depth formats
+    pub fn read_pixels_f32(&self, layout: vk::ImageLayout, vulkan: &VkThread) -> Vec<f32> {
+        let data = vulkan.read_image(self.image, self.format, self.extent, layout);
+        readback::to_rgba32f(self.format, &data)
+    }
+
+    //The sampler is owned by the context's SamplerCache
     pub fn create_sampler(
         context: Arc<Context>,
         sampler_info: vk::SamplerCreateInfo,
     ) -> vk::Sampler {
-        unsafe {
-            context
-                .device
-                .create_sampler(&sampler_info, None)
-                .expect("Failed to create Sampler!")
-        }
+        context.sampler(&sampler_info)
     }
 
     pub fn create_view(
@@ -107,11 +553,58 @@ impl Drop for Image {
                     .device
                     .destroy_image_view(self.image_view.unwrap(), None);
             }
-            if self.sampler.is_some() {
-                self.context
-                    .device
-                    .destroy_sampler(self.sampler.unwrap(), None);
-            }
         }
     }
 }
+
+fn aspect_mask(format: vk::Format) -> vk::ImageAspectFlags {
+    match format {
+        vk::Format::D16_UNORM_S8_UINT
+        | vk::Format::D24_UNORM_S8_UINT
+        | vk::Format::D32_SFLOAT_S8_UINT => {
+            vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL
+        }
+        _ if readback::is_depth_format(format) => vk::ImageAspectFlags::DEPTH,
+        _ => vk::ImageAspectFlags::COLOR,
+    }
+}
+
+//Access mask and pipeline stage of the usual users of a layout
+pub(super) fn layout_access(layout: vk::ImageLayout) -> (vk::AccessFlags, vk::PipelineStageFlags) {
+    match layout {
+        vk::ImageLayout::UNDEFINED | vk::ImageLayout::PREINITIALIZED => (
+            vk::AccessFlags::empty(),
+            vk::PipelineStageFlags::TOP_OF_PIPE,
+        ),
+        vk::ImageLayout::TRANSFER_DST_OPTIMAL => (
+            vk::AccessFlags::TRANSFER_WRITE,
+            vk::PipelineStageFlags::TRANSFER,
+        ),
+        vk::ImageLayout::TRANSFER_SRC_OPTIMAL => (
+            vk::AccessFlags::TRANSFER_READ,
+            vk::PipelineStageFlags::TRANSFER,
+        ),
+        vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL => (
+            vk::AccessFlags::SHADER_READ,
+            vk::PipelineStageFlags::FRAGMENT_SHADER | vk::PipelineStageFlags::COMPUTE_SHADER,
+        ),
+        vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL => (
+            vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+        ),
+        vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL => (
+            vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ
+                | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+        ),
+        vk::ImageLayout::GENERAL => (
+            vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE,
+            vk::PipelineStageFlags::COMPUTE_SHADER,
+        ),
+        _ => (
+            vk::AccessFlags::MEMORY_READ | vk::AccessFlags::MEMORY_WRITE,
+            vk::PipelineStageFlags::ALL_COMMANDS,
+        ),
+    }
+}
@@ -0,0 +1,134 @@
+use super::{Access, Barrier, Buffer};
+use crate::Context;
+use ash::{version::DeviceV1_0, vk};
+use std::{mem, sync::Arc};
+
+const STRIDE: u32 = mem::size_of::<vk::DrawIndexedIndirectCommand>() as u32;
+
+//DrawIndexedIndirectCommands and a draw count written by a compute shader, e.g. GPU culling.
+//Record reset before the dispatch and finish_writes after it, both outside of a render pass,
+//then draw inside the render pass.
+pub struct IndirectCommands {
+    commands: Buffer<vk::DrawIndexedIndirectCommand>,
+    count: Buffer<u32>,
+    max_draws: u32,
+    multi_draw: bool,
+    context: Arc<Context>,
+}
+
+impl IndirectCommands {
+    pub fn new(max_draws: u32, context: Arc<Context>) -> IndirectCommands {
+        let usage = vk::BufferUsageFlags::STORAGE_BUFFER
+            | vk::BufferUsageFlags::INDIRECT_BUFFER
+            | vk::BufferUsageFlags::TRANSFER_DST;
+        IndirectCommands {
+            commands: Buffer::with_len(
+                max_draws.max(1) as usize,
+                usage,
+                vk_mem::MemoryUsage::GpuOnly,
+                context.clone(),
+            ),
+            count: Buffer::with_len(1, usage, vk_mem::MemoryUsage::GpuOnly, context.clone()),
+            max_draws,
+            multi_draw: context.capabilities.multi_draw_indirect,
+            context,
+        }
+    }
+
+    //Needs drawIndirectFirstInstance, shaders find their per draw data through firstInstance
+    pub fn is_supported(context: &Context) -> bool {
+        context.capabilities.draw_indirect_first_instance
+    }
+
+    //Whether draw skips the commands after the GPU written count
+    pub fn supports_draw_count(&self) -> bool {
+        self.context.draw_indirect_count.is_some()
+    }
+
+    pub fn max_draws(&self) -> u32 {
+        self.max_draws
+    }
+
+    pub fn commands_info(&self) -> vk::DescriptorBufferInfo {
+        vk::DescriptorBufferInfo {
+            buffer: self.commands.buffer,
+            offset: 0,
+            range: self.commands.size,
+        }
+    }
+
+    pub fn count_info(&self) -> vk::DescriptorBufferInfo {
+        vk::DescriptorBufferInfo {
+            buffer: self.count.buffer,
+            offset: 0,
+            range: self.count.size,
+        }
+    }
+
+    //Zeroes the commands and the count, commands that are not written draw 0 instances
+    pub fn reset(&self, command_buffer: vk::CommandBuffer) {
+        let device = &self.context.device;
+        unsafe {
+            device.cmd_fill_buffer(command_buffer, self.commands.buffer, 0, vk::WHOLE_SIZE, 0);
+            device.cmd_fill_buffer(command_buffer, self.count.buffer, 0, vk::WHOLE_SIZE, 0);
+        }
+        Barrier::new()
+            .buffer(
+                &self.commands,
+                Access::TransferWrite,
+                Access::ComputeReadWrite,
+            )
+            .buffer(&self.count, Access::TransferWrite, Access::ComputeReadWrite)
+            .record(command_buffer, device);
+    }
+
+    //Makes the commands written by the compute shader visible to draw
+    pub fn finish_writes(&self, command_buffer: vk::CommandBuffer) {
+        Barrier::new()
+            .buffer(&self.commands, Access::ComputeWrite, Access::IndirectRead)
+            .buffer(&self.count, Access::ComputeWrite, Access::IndirectRead)
+            //Other compute output the vertex shader reads
+            .global(Access::ComputeWrite, Access::VertexShaderRead)
+            .record(command_buffer, &self.context.device);
+    }
+
+    //Uses the GPU written count with VK_KHR_draw_indirect_count, otherwise
+    //all max_draws commands are submitted and the zeroed ones draw nothing
+    pub fn draw(&self, command_buffer: vk::CommandBuffer) {
+        let device = &self.context.device;
+        unsafe {
+            match &self.context.draw_indirect_count {
+                Some(draw_indirect_count) => {
+                    draw_indirect_count.cmd_draw_indexed_indirect_count_khr(
+                        command_buffer,
+                        self.commands.buffer,
+                        0,
+                        self.count.buffer,
+                        0,
+                        self.max_draws,
+                        STRIDE,
+                    );
+                }
+                None if self.multi_draw => device.cmd_draw_indexed_indirect(
+                    command_buffer,
+                    self.commands.buffer,
+                    0,
+                    self.max_draws,
+                    STRIDE,
+                ),
+                //Without multiDrawIndirect the draw count has to be 0 or 1
+                None => {
+                    for draw in 0..self.max_draws {
+                        device.cmd_draw_indexed_indirect(
+                            command_buffer,
+                            self.commands.buffer,
+                            (draw * STRIDE) as u64,
+                            1,
+                            STRIDE,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
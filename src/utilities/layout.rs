@@ -0,0 +1,175 @@
+//GLSL std140/std430 block layout rules, used to check that #[repr(C)] structs
+//can be uploaded as raw bytes into uniform and storage buffers.
+
+#[derive(Debug, Clone, Copy)]
+pub struct LayoutInfo {
+    pub align: usize,
+    pub size: usize,
+    //Rust layout places every member at the same offset as GLSL does
+    pub matches: bool,
+}
+
+impl LayoutInfo {
+    pub const fn new(align: usize, size: usize) -> Self {
+        Self {
+            align,
+            size,
+            matches: true,
+        }
+    }
+}
+
+pub const fn align_up(value: usize, align: usize) -> usize {
+    value.div_ceil(align) * align
+}
+
+const fn max(a: usize, b: usize) -> usize {
+    if a > b {
+        a
+    } else {
+        b
+    }
+}
+
+//Walks struct members in order, tracking GLSL and Rust offsets side by side
+#[derive(Debug, Clone, Copy)]
+pub struct LayoutBuilder {
+    pub std140: bool,
+    pub offset: usize,
+    pub rust_offset: usize,
+    pub align: usize,
+    pub matches: bool,
+}
+
+impl LayoutBuilder {
+    pub const fn new(std140: bool) -> Self {
+        Self {
+            std140,
+            offset: 0,
+            rust_offset: 0,
+            align: 1,
+            matches: true,
+        }
+    }
+
+    pub const fn field(self, glsl: LayoutInfo, rust_align: usize, rust_size: usize) -> Self {
+        let offset = align_up(self.offset, glsl.align);
+        let rust_offset = align_up(self.rust_offset, rust_align);
+
+        Self {
+            std140: self.std140,
+            offset: offset + glsl.size,
+            rust_offset: rust_offset + rust_size,
+            align: max(self.align, glsl.align),
+            matches: self.matches && glsl.matches && offset == rust_offset,
+        }
+    }
+
+    //Fixed size array member, std140 rounds the element stride up to 16 bytes
+    pub const fn array(
+        self,
+        element: LayoutInfo,
+        rust_align: usize,
+        rust_size: usize,
+        len: usize,
+    ) -> Self {
+        let align = if self.std140 {
+            align_up(element.align, 16)
+        } else {
+            element.align
+        };
+        let stride = align_up(element.size, align);
+
+        let array = LayoutInfo {
+            align,
+            size: stride * len,
+            matches: element.matches && stride == rust_size,
+        };
+
+        self.field(array, rust_align, rust_size * len)
+    }
+
+    pub const fn finish(self, rust_size: usize) -> LayoutInfo {
+        let align = if self.std140 {
+            align_up(self.align, 16)
+        } else {
+            self.align
+        };
+        let size = align_up(self.offset, align);
+
+        LayoutInfo {
+            align,
+            size,
+            matches: self.matches && size == rust_size,
+        }
+    }
+}
+
+/// Type with a known GLSL block layout, implement with `#[derive(ShaderLayout)]`.
+///
+/// # Safety
+///
+/// Buffers upload the Rust bytes as is when `matches` is set. Implementors
+/// guarantee that every field of the `#[repr(C)]` type sits at the offset the
+/// std140/std430 rules compute, and that `STD140`/`STD430` report the true
+/// alignment and size of the type under those rules.
+pub unsafe trait ShaderLayout: Copy {
+    const STD140: LayoutInfo;
+    const STD430: LayoutInfo;
+}
+
+macro_rules! impl_shader_layout {
+    ($($ty:ty => $align:expr, $size:expr;)*) => {
+        $(
+            unsafe impl ShaderLayout for $ty {
+                const STD140: LayoutInfo = LayoutInfo::new($align, $size);
+                const STD430: LayoutInfo = LayoutInfo::new($align, $size);
+            }
+        )*
+    };
+}
+
+impl_shader_layout! {
+    f32 => 4, 4;
+    i32 => 4, 4;
+    u32 => 4, 4;
+    [f32; 2] => 8, 8;
+    [i32; 2] => 8, 8;
+    [u32; 2] => 8, 8;
+    [f32; 3] => 16, 12;
+    [i32; 3] => 16, 12;
+    [u32; 3] => 16, 12;
+    [f32; 4] => 16, 16;
+    [i32; 4] => 16, 16;
+    [u32; 4] => 16, 16;
+    [[f32; 4]; 4] => 16, 64;
+    cgmath::Vector2<f32> => 8, 8;
+    cgmath::Vector3<f32> => 16, 12;
+    cgmath::Vector4<f32> => 16, 16;
+    cgmath::Point3<f32> => 16, 12;
+    cgmath::Matrix4<f32> => 16, 64;
+}
+
+//mat3 columns are padded to vec4, so a packed Rust Matrix3 never matches
+unsafe impl ShaderLayout for cgmath::Matrix3<f32> {
+    const STD140: LayoutInfo = LayoutInfo {
+        align: 16,
+        size: 48,
+        matches: false,
+    };
+    const STD430: LayoutInfo = LayoutInfo {
+        align: 16,
+        size: 48,
+        matches: false,
+    };
+}
+
+//Forces 16 byte alignment on the Rust side, e.g. to place a vec3 after another vec3
+#[repr(C, align(16))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Align16<T: Copy>(pub T);
+
+unsafe impl<T: ShaderLayout> ShaderLayout for Align16<T> {
+    const STD140: LayoutInfo = T::STD140;
+    const STD430: LayoutInfo = T::STD430;
+}
@@ -0,0 +1,44 @@
+//Mip chain helpers, used when a format can not be blitted with linear filtering
+
+//Number of levels down to 1x1
+pub fn mip_levels(width: u32, height: u32) -> u32 {
+    32 - width.max(height).max(1).leading_zeros()
+}
+
+//Size of `level`, never smaller than one texel
+pub fn level_extent(width: u32, height: u32, level: u32) -> (u32, u32) {
+    ((width >> level).max(1), (height >> level).max(1))
+}
+
+//Box filters tightly packed R8G8B8A8 pixels into every level below level 0.
+//The returned chain starts with a copy of level 0.
+pub fn generate_rgba8(width: u32, height: u32, pixels: &[u8]) -> Vec<Vec<u8>> {
+    let mut levels = vec![pixels.to_vec()];
+
+    for level in 1..mip_levels(width, height) {
+        let (src_width, src_height) = level_extent(width, height, level - 1);
+        let (dst_width, dst_height) = level_extent(width, height, level);
+        let src = &levels[level as usize - 1];
+
+        let mut dst = Vec::with_capacity((dst_width * dst_height * 4) as usize);
+        for y in 0..dst_height {
+            for x in 0..dst_width {
+                //Odd sizes repeat the last row or column
+                let xs = [(x * 2).min(src_width - 1), (x * 2 + 1).min(src_width - 1)];
+                let ys = [(y * 2).min(src_height - 1), (y * 2 + 1).min(src_height - 1)];
+
+                for channel in 0..4 {
+                    let sum: u32 = ys
+                        .iter()
+                        .flat_map(|&sy| xs.iter().map(move |&sx| (sx, sy)))
+                        .map(|(sx, sy)| src[((sy * src_width + sx) * 4 + channel) as usize] as u32)
+                        .sum();
+                    dst.push(((sum + 2) / 4) as u8);
+                }
+            }
+        }
+        levels.push(dst);
+    }
+
+    levels
+}
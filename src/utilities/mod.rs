@@ -1,18 +1,43 @@
+mod barrier;
+mod block_decode;
 mod buffer;
+mod compute;
 mod descriptor;
-pub mod fps_meter;
+mod encoder;
+mod frame_allocator;
+pub mod frame_timer;
+mod gpu_profiler;
 mod images;
+mod indirect;
+pub mod layout;
+pub mod mipmap;
 mod pipeline;
+mod query_pool;
+pub mod readback;
 mod renderpass;
 pub mod tools;
 
 mod shader;
+mod texture_file;
+mod uniform;
+mod vertex;
 
+pub use barrier::{Access, Barrier};
 pub use buffer::Buffer;
+pub use compute::ComputePipeline;
 pub use descriptor::{Descriptor, DescriptorSet};
-pub use fps_meter::FPSLimiter;
+pub use encoder::CommandEncoder;
+pub use frame_allocator::{FrameAllocation, FrameAllocator};
+pub use frame_timer::{FrameTimeStats, FrameTimer};
+pub use gpu_profiler::{GpuProfiler, ScopeStats};
 pub use images::Image;
+pub use indirect::IndirectCommands;
+pub use layout::{Align16, LayoutBuilder, LayoutInfo, ShaderLayout};
 pub use pipeline::Pipeline;
-pub use renderpass::Renderpass;
+pub use query_pool::{PipelineStatistics, QueryPool};
+pub use renderpass::{AttachmentKind, Renderpass, RenderpassBuilder, Subpass};
 pub use shader::Shader;
+pub use texture_file::TextureFile;
 pub use tools::as_byte_slice;
+pub use uniform::{StorageBuffer, UniformBuffer};
+pub use vertex::VertexInput;
@@ -1,11 +1,15 @@
 use crate::Context;
 use ash::version::DeviceV1_0;
 use ash::vk;
-use std::sync::Arc;
+use std::{slice, sync::Arc};
 
 pub struct Pipeline {
     layouts: Vec<vk::PipelineLayout>,
+    //Push constant ranges of every layout, checked by CommandEncoder
+    push_constant_ranges: Vec<Vec<vk::PushConstantRange>>,
     pipelines: Vec<vk::Pipeline>,
+    //Layout and bind point every pipeline was created with
+    bindings: Vec<(vk::PipelineLayout, vk::PipelineBindPoint)>,
     ctx: Arc<Context>,
 }
 
@@ -13,7 +17,9 @@ impl Pipeline {
     pub fn new(ctx: Arc<Context>) -> Self {
         Self {
             layouts: Vec::new(),
+            push_constant_ranges: Vec::new(),
             pipelines: Vec::new(),
+            bindings: Vec::new(),
             ctx: ctx.clone(),
         }
     }
@@ -25,6 +31,18 @@ impl Pipeline {
                 .create_pipeline_layout(&layout, None)
                 .unwrap()
         });
+        self.push_constant_ranges
+            .push(if layout.push_constant_range_count == 0 {
+                Vec::new()
+            } else {
+                unsafe {
+                    slice::from_raw_parts(
+                        layout.p_push_constant_ranges,
+                        layout.push_constant_range_count as usize,
+                    )
+                }
+                .to_vec()
+            });
     }
 
     pub fn add_pipeline(&mut self, info: vk::GraphicsPipelineCreateInfo) {
@@ -35,6 +53,8 @@ impl Pipeline {
                 .expect("Unable to create graphics pipeline")
         };
         self.pipelines.push(pipeline[0]);
+        self.bindings
+            .push((info.layout, vk::PipelineBindPoint::GRAPHICS));
     }
 
     pub fn add_compute(&mut self, info: vk::ComputePipelineCreateInfo) {
@@ -42,9 +62,11 @@ impl Pipeline {
             self.ctx
                 .device
                 .create_compute_pipelines(vk::PipelineCache::null(), &[info], None)
-                .expect("Unable to create graphics pipeline")
+                .expect("Unable to create compute pipeline")
         };
         self.pipelines.push(pipeline[0]);
+        self.bindings
+            .push((info.layout, vk::PipelineBindPoint::COMPUTE));
     }
 
     pub fn default(&self) -> vk::Pipeline {
@@ -58,6 +80,25 @@ impl Pipeline {
     pub fn layout(&self, index: usize) -> vk::PipelineLayout {
         self.layouts[index]
     }
+
+    //Layout the pipeline at `index` was created with
+    pub fn layout_of(&self, index: usize) -> vk::PipelineLayout {
+        self.bindings[index].0
+    }
+
+    pub fn bind_point(&self, index: usize) -> vk::PipelineBindPoint {
+        self.bindings[index].1
+    }
+
+    //Push constant ranges of the layout of the pipeline at `index`,
+    //None when the layout was not added through add_layout
+    pub fn push_constant_ranges(&self, index: usize) -> Option<&[vk::PushConstantRange]> {
+        let layout = self.layout_of(index);
+        self.layouts
+            .iter()
+            .position(|added| *added == layout)
+            .map(|layout_index| self.push_constant_ranges[layout_index].as_slice())
+    }
 }
 
 impl Drop for Pipeline {
@@ -0,0 +1,312 @@
+use crate::{constants::MAX_FRAMES_IN_FLIGHT, Context};
+use ash::{version::DeviceV1_0, vk};
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+//Every frame writes its own range, older ranges are read back without waiting
+const FRAME_SLOTS: usize = MAX_FRAMES_IN_FLIGHT + 1;
+
+//Counters of a pipeline statistics query, counters that were not requested stay 0
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PipelineStatistics {
+    pub input_assembly_vertices: u64,
+    pub input_assembly_primitives: u64,
+    pub vertex_shader_invocations: u64,
+    pub geometry_shader_invocations: u64,
+    pub geometry_shader_primitives: u64,
+    pub clipping_invocations: u64,
+    pub clipping_primitives: u64,
+    pub fragment_shader_invocations: u64,
+    pub tessellation_control_shader_patches: u64,
+    pub tessellation_evaluation_shader_invocations: u64,
+    pub compute_shader_invocations: u64,
+}
+
+impl PipelineStatistics {
+    //Results are written in the bit order of the requested flags
+    pub fn from_results(flags: vk::QueryPipelineStatisticFlags, results: &[u64]) -> Self {
+        use vk::QueryPipelineStatisticFlags as Flag;
+
+        let mut statistics = PipelineStatistics::default();
+        let mut values = results.iter().cloned();
+        let fields: [(Flag, &mut u64); 11] = [
+            (
+                Flag::INPUT_ASSEMBLY_VERTICES,
+                &mut statistics.input_assembly_vertices,
+            ),
+            (
+                Flag::INPUT_ASSEMBLY_PRIMITIVES,
+                &mut statistics.input_assembly_primitives,
+            ),
+            (
+                Flag::VERTEX_SHADER_INVOCATIONS,
+                &mut statistics.vertex_shader_invocations,
+            ),
+            (
+                Flag::GEOMETRY_SHADER_INVOCATIONS,
+                &mut statistics.geometry_shader_invocations,
+            ),
+            (
+                Flag::GEOMETRY_SHADER_PRIMITIVES,
+                &mut statistics.geometry_shader_primitives,
+            ),
+            (
+                Flag::CLIPPING_INVOCATIONS,
+                &mut statistics.clipping_invocations,
+            ),
+            (
+                Flag::CLIPPING_PRIMITIVES,
+                &mut statistics.clipping_primitives,
+            ),
+            (
+                Flag::FRAGMENT_SHADER_INVOCATIONS,
+                &mut statistics.fragment_shader_invocations,
+            ),
+            (
+                Flag::TESSELLATION_CONTROL_SHADER_PATCHES,
+                &mut statistics.tessellation_control_shader_patches,
+            ),
+            (
+                Flag::TESSELLATION_EVALUATION_SHADER_INVOCATIONS,
+                &mut statistics.tessellation_evaluation_shader_invocations,
+            ),
+            (
+                Flag::COMPUTE_SHADER_INVOCATIONS,
+                &mut statistics.compute_shader_invocations,
+            ),
+        ];
+
+        for (flag, field) in fields {
+            if flags.contains(flag) {
+                *field = values.next().unwrap_or(0);
+            }
+        }
+        statistics
+    }
+}
+
+impl fmt::Display for PipelineStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "vertices {} primitives {} vs {} clipped {} fs {} cs {}",
+            self.input_assembly_vertices,
+            self.input_assembly_primitives,
+            self.vertex_shader_invocations,
+            self.clipping_primitives,
+            self.fragment_shader_invocations,
+            self.compute_shader_invocations
+        )
+    }
+}
+
+struct State {
+    frame: usize,
+    recorded: [bool; FRAME_SLOTS],
+}
+
+//Occlusion or pipeline statistics queries with one range of `count` queries per frame.
+//Call begin_frame outside of a render pass at the start of every recorded frame, wrap draws
+//in begin/end and read the newest finished frame with results, which never waits on the GPU.
+pub struct QueryPool {
+    pool: vk::QueryPool,
+    query_type: vk::QueryType,
+    statistics: vk::QueryPipelineStatisticFlags,
+    count: u32,
+    precise: bool,
+    state: Mutex<State>,
+    context: Arc<Context>,
+}
+
+impl QueryPool {
+    //Samples passing depth and stencil tests, zero means the draws were occluded
+    pub fn occlusion(count: u32, context: Arc<Context>) -> QueryPool {
+        Self::new(
+            vk::QueryType::OCCLUSION,
+            vk::QueryPipelineStatisticFlags::empty(),
+            count,
+            context,
+        )
+    }
+
+    pub fn pipeline_statistics(
+        count: u32,
+        statistics: vk::QueryPipelineStatisticFlags,
+        context: Arc<Context>,
+    ) -> QueryPool {
+        assert!(
+            Self::supports_pipeline_statistics(&context),
+            "Pipeline statistics queries are not supported!"
+        );
+        Self::new(
+            vk::QueryType::PIPELINE_STATISTICS,
+            statistics,
+            count,
+            context,
+        )
+    }
+
+    pub fn supports_pipeline_statistics(context: &Context) -> bool {
+        context.capabilities.pipeline_statistics_query
+    }
+
+    fn new(
+        query_type: vk::QueryType,
+        statistics: vk::QueryPipelineStatisticFlags,
+        count: u32,
+        context: Arc<Context>,
+    ) -> QueryPool {
+        let pool = unsafe {
+            context
+                .device
+                .create_query_pool(
+                    &vk::QueryPoolCreateInfo {
+                        s_type: vk::StructureType::QUERY_POOL_CREATE_INFO,
+                        query_type,
+                        query_count: count * FRAME_SLOTS as u32,
+                        pipeline_statistics: statistics,
+                        ..Default::default()
+                    },
+                    None,
+                )
+                .expect("Failed to create QueryPool!")
+        };
+
+        QueryPool {
+            pool,
+            query_type,
+            statistics,
+            count,
+            precise: query_type == vk::QueryType::OCCLUSION
+                && context.capabilities.occlusion_query_precise,
+            state: Mutex::new(State {
+                frame: 0,
+                recorded: [false; FRAME_SLOTS],
+            }),
+            context,
+        }
+    }
+
+    pub fn pool(&self) -> vk::QueryPool {
+        self.pool
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    //Moves to the next frame range and resets it
+    pub fn begin_frame(&self, command_buffer: vk::CommandBuffer) {
+        let mut state = self.state.lock().unwrap();
+        state.frame = (state.frame + 1) % FRAME_SLOTS;
+        let frame = state.frame;
+        state.recorded[frame] = true;
+
+        unsafe {
+            self.context.device.cmd_reset_query_pool(
+                command_buffer,
+                self.pool,
+                self.first_query(frame),
+                self.count,
+            );
+        }
+    }
+
+    //Occlusion queries count exact samples, otherwise a non zero value only means visible
+    pub fn begin(&self, command_buffer: vk::CommandBuffer, index: u32) {
+        let flags = if self.precise {
+            vk::QueryControlFlags::PRECISE
+        } else {
+            vk::QueryControlFlags::empty()
+        };
+
+        unsafe {
+            self.context.device.cmd_begin_query(
+                command_buffer,
+                self.pool,
+                self.current_query(index),
+                flags,
+            );
+        }
+    }
+
+    pub fn end(&self, command_buffer: vk::CommandBuffer, index: u32) {
+        unsafe {
+            self.context
+                .device
+                .cmd_end_query(command_buffer, self.pool, self.current_query(index));
+        }
+    }
+
+    //Raw values of the newest frame the GPU finished, `count` times the values per query.
+    //None until a frame is available.
+    pub fn results(&self) -> Option<Vec<u64>> {
+        let state = self.state.lock().unwrap();
+        let values_per_query = self.values_per_query();
+
+        //The frame being recorded is skipped, then newest to oldest
+        (1..FRAME_SLOTS)
+            .map(|age| (state.frame + FRAME_SLOTS - age) % FRAME_SLOTS)
+            .filter(|&frame| state.recorded[frame])
+            .find_map(|frame| {
+                let mut results = vec![0u64; (self.count * values_per_query) as usize];
+                unsafe {
+                    self.context.device.get_query_pool_results(
+                        self.pool,
+                        self.first_query(frame),
+                        self.count,
+                        &mut results,
+                        vk::QueryResultFlags::TYPE_64,
+                    )
+                }
+                .ok()
+                .map(|_| results)
+            })
+    }
+
+    //Per query statistics of the newest finished frame
+    pub fn statistics(&self) -> Option<Vec<PipelineStatistics>> {
+        let values_per_query = self.values_per_query() as usize;
+        self.results().map(|results| {
+            results
+                .chunks_exact(values_per_query)
+                .map(|values| PipelineStatistics::from_results(self.statistics, values))
+                .collect()
+        })
+    }
+
+    //Whether each occlusion query passed any samples in the newest finished frame,
+    //everything counts as visible until results are available
+    pub fn visibility(&self) -> Vec<bool> {
+        self.results()
+            .map(|samples| samples.iter().map(|&samples| samples > 0).collect())
+            .unwrap_or_else(|| vec![true; self.count as usize])
+    }
+
+    fn values_per_query(&self) -> u32 {
+        match self.query_type {
+            vk::QueryType::PIPELINE_STATISTICS => self.statistics.as_raw().count_ones(),
+            _ => 1,
+        }
+    }
+
+    fn first_query(&self, frame: usize) -> u32 {
+        frame as u32 * self.count
+    }
+
+    fn current_query(&self, index: u32) -> u32 {
+        assert!(index < self.count, "Query index out of range!");
+        self.first_query(self.state.lock().unwrap().frame) + index
+    }
+}
+
+impl Drop for QueryPool {
+    fn drop(&mut self) {
+        unsafe {
+            self.context.wait_idle();
+            self.context.device.destroy_query_pool(self.pool, None);
+        }
+    }
+}
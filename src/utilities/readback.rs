@@ -0,0 +1,145 @@
+//Conversion of raw image bytes copied back from the GPU into RGBA pixels
+use ash::vk;
+use std::{fs::File, io, io::BufWriter, path::Path};
+
+//Bytes per texel of a format supported by readback, depth formats count their depth aspect only
+pub fn format_size(format: vk::Format) -> Option<usize> {
+    match format {
+        vk::Format::R8_UNORM => Some(1),
+        vk::Format::D16_UNORM => Some(2),
+        vk::Format::R8G8B8A8_UNORM
+        | vk::Format::R8G8B8A8_SRGB
+        | vk::Format::B8G8R8A8_UNORM
+        | vk::Format::B8G8R8A8_SRGB
+        | vk::Format::R32_SFLOAT
+        | vk::Format::D32_SFLOAT
+        | vk::Format::D32_SFLOAT_S8_UINT
+        | vk::Format::X8_D24_UNORM_PACK32
+        | vk::Format::D24_UNORM_S8_UINT => Some(4),
+        vk::Format::R16G16B16A16_SFLOAT => Some(8),
+        vk::Format::R32G32B32A32_SFLOAT => Some(16),
+        _ => None,
+    }
+}
+
+pub fn is_depth_format(format: vk::Format) -> bool {
+    matches!(
+        format,
+        vk::Format::D16_UNORM
+            | vk::Format::D32_SFLOAT
+            | vk::Format::D32_SFLOAT_S8_UINT
+            | vk::Format::X8_D24_UNORM_PACK32
+            | vk::Format::D24_UNORM_S8_UINT
+    )
+}
+
+//Converts texels into RGBA8, 8 bit formats are copied without rounding
+pub fn to_rgba8(format: vk::Format, data: &[u8]) -> Vec<u8> {
+    match format {
+        vk::Format::R8G8B8A8_UNORM | vk::Format::R8G8B8A8_SRGB => data.to_vec(),
+        vk::Format::B8G8R8A8_UNORM | vk::Format::B8G8R8A8_SRGB => data
+            .chunks_exact(4)
+            .flat_map(|texel| vec![texel[2], texel[1], texel[0], texel[3]])
+            .collect(),
+        vk::Format::R8_UNORM => data
+            .iter()
+            .flat_map(|&value| vec![value, value, value, 255])
+            .collect(),
+        _ => to_rgba32f(format, data)
+            .iter()
+            .map(|value| (value.clamp(0.0, 1.0) * 255.0).round() as u8)
+            .collect(),
+    }
+}
+
+//Converts texels into RGBA32F, single channel formats are spread over RGB
+pub fn to_rgba32f(format: vk::Format, data: &[u8]) -> Vec<f32> {
+    let texel_size = format_size(format)
+        .unwrap_or_else(|| panic!("Unsupported readback format: {:?}", format));
+
+    data.chunks_exact(texel_size)
+        .flat_map(|texel| {
+            let rgba = match format {
+                vk::Format::R8G8B8A8_UNORM | vk::Format::R8G8B8A8_SRGB => [
+                    unorm8(texel[0]),
+                    unorm8(texel[1]),
+                    unorm8(texel[2]),
+                    unorm8(texel[3]),
+                ],
+                vk::Format::B8G8R8A8_UNORM | vk::Format::B8G8R8A8_SRGB => [
+                    unorm8(texel[2]),
+                    unorm8(texel[1]),
+                    unorm8(texel[0]),
+                    unorm8(texel[3]),
+                ],
+                vk::Format::R8_UNORM => gray(unorm8(texel[0])),
+                vk::Format::D16_UNORM => {
+                    gray(u16::from_le_bytes([texel[0], texel[1]]) as f32 / 65535.0)
+                }
+                vk::Format::X8_D24_UNORM_PACK32 | vk::Format::D24_UNORM_S8_UINT => {
+                    let value = u32::from_le_bytes([texel[0], texel[1], texel[2], texel[3]]);
+                    gray((value & 0x00ff_ffff) as f32 / 16_777_215.0)
+                }
+                vk::Format::R32_SFLOAT | vk::Format::D32_SFLOAT | vk::Format::D32_SFLOAT_S8_UINT => {
+                    gray(f32_at(texel, 0))
+                }
+                vk::Format::R16G16B16A16_SFLOAT => [
+                    half_to_f32(u16::from_le_bytes([texel[0], texel[1]])),
+                    half_to_f32(u16::from_le_bytes([texel[2], texel[3]])),
+                    half_to_f32(u16::from_le_bytes([texel[4], texel[5]])),
+                    half_to_f32(u16::from_le_bytes([texel[6], texel[7]])),
+                ],
+                _ => [
+                    f32_at(texel, 0),
+                    f32_at(texel, 4),
+                    f32_at(texel, 8),
+                    f32_at(texel, 12),
+                ],
+            };
+            rgba.to_vec()
+        })
+        .collect()
+}
+
+//Writes RGBA8 pixels into a PNG file
+pub fn save_png<P: AsRef<Path>>(path: P, width: u32, height: u32, pixels: &[u8]) -> io::Result<()> {
+    let file = File::create(path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::RGBA);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(pixels)?;
+    Ok(())
+}
+
+fn unorm8(value: u8) -> f32 {
+    value as f32 / 255.0
+}
+
+fn gray(value: f32) -> [f32; 4] {
+    [value, value, value, 1.0]
+}
+
+fn f32_at(texel: &[u8], offset: usize) -> f32 {
+    f32::from_le_bytes([
+        texel[offset],
+        texel[offset + 1],
+        texel[offset + 2],
+        texel[offset + 3],
+    ])
+}
+
+//IEEE 754 half precision to f32
+fn half_to_f32(half: u16) -> f32 {
+    let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((half >> 10) & 0x1f) as i32;
+    let mantissa = (half & 0x3ff) as f32;
+
+    match exponent {
+        0 => sign * mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0.0 => sign * f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
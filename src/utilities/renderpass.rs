@@ -21,6 +21,10 @@ impl Renderpass {
         }
     }
 
+    pub fn builder() -> RenderpassBuilder {
+        RenderpassBuilder::new()
+    }
+
     pub fn pass(&self) -> vk::RenderPass {
         self.pass
     }
@@ -34,3 +38,259 @@ impl Drop for Renderpass {
         }
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AttachmentKind {
+    Color,
+    Depth,
+    Resolve,
+}
+
+impl AttachmentKind {
+    //Layout the attachment has while a subpass is using it
+    fn subpass_layout(&self) -> vk::ImageLayout {
+        match self {
+            AttachmentKind::Color | AttachmentKind::Resolve => {
+                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL
+            }
+            AttachmentKind::Depth => vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Subpass {
+    pub colors: Vec<u32>,
+    pub depth: Option<u32>,
+    pub resolves: Vec<u32>,
+    pub inputs: Vec<u32>,
+}
+
+//Color, depth, resolve and input references of one subpass
+type SubpassReferences = (
+    Vec<vk::AttachmentReference>,
+    Option<vk::AttachmentReference>,
+    Vec<vk::AttachmentReference>,
+    Vec<vk::AttachmentReference>,
+);
+
+//Describes renderpass attachments, subpasses and dependencies.
+//Subpasses and dependencies are optional, without them a single subpass
+//using every attachment and an external color dependency is created.
+pub struct RenderpassBuilder {
+    attachments: Vec<(AttachmentKind, vk::AttachmentDescription)>,
+    subpasses: Vec<Subpass>,
+    dependencies: Vec<vk::SubpassDependency>,
+}
+
+impl Default for RenderpassBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RenderpassBuilder {
+    pub fn new() -> Self {
+        Self {
+            attachments: Vec::new(),
+            subpasses: Vec::new(),
+            dependencies: Vec::new(),
+        }
+    }
+
+    pub fn add_attachment(
+        mut self,
+        kind: AttachmentKind,
+        description: vk::AttachmentDescription,
+    ) -> Self {
+        self.attachments.push((kind, description));
+        self
+    }
+
+    pub fn color_attachment(
+        self,
+        format: vk::Format,
+        samples: vk::SampleCountFlags,
+        load_op: vk::AttachmentLoadOp,
+        store_op: vk::AttachmentStoreOp,
+        final_layout: vk::ImageLayout,
+    ) -> Self {
+        self.add_attachment(
+            AttachmentKind::Color,
+            vk::AttachmentDescription {
+                flags: vk::AttachmentDescriptionFlags::empty(),
+                format,
+                samples,
+                load_op,
+                store_op,
+                stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+                stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+                initial_layout: vk::ImageLayout::UNDEFINED,
+                final_layout,
+            },
+        )
+    }
+
+    pub fn depth_attachment(
+        self,
+        format: vk::Format,
+        samples: vk::SampleCountFlags,
+        load_op: vk::AttachmentLoadOp,
+        store_op: vk::AttachmentStoreOp,
+        final_layout: vk::ImageLayout,
+    ) -> Self {
+        self.add_attachment(
+            AttachmentKind::Depth,
+            vk::AttachmentDescription {
+                flags: vk::AttachmentDescriptionFlags::empty(),
+                format,
+                samples,
+                load_op,
+                store_op,
+                stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+                stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+                initial_layout: vk::ImageLayout::UNDEFINED,
+                final_layout,
+            },
+        )
+    }
+
+    //Single sampled target a multisampled color attachment gets resolved into
+    pub fn resolve_attachment(self, format: vk::Format, final_layout: vk::ImageLayout) -> Self {
+        self.add_attachment(
+            AttachmentKind::Resolve,
+            vk::AttachmentDescription {
+                flags: vk::AttachmentDescriptionFlags::empty(),
+                format,
+                samples: vk::SampleCountFlags::TYPE_1,
+                load_op: vk::AttachmentLoadOp::DONT_CARE,
+                store_op: vk::AttachmentStoreOp::STORE,
+                stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+                stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+                initial_layout: vk::ImageLayout::UNDEFINED,
+                final_layout,
+            },
+        )
+    }
+
+    pub fn subpass(mut self, subpass: Subpass) -> Self {
+        self.subpasses.push(subpass);
+        self
+    }
+
+    pub fn dependency(mut self, dependency: vk::SubpassDependency) -> Self {
+        self.dependencies.push(dependency);
+        self
+    }
+
+    pub fn attachment_count(&self) -> usize {
+        self.attachments.len()
+    }
+
+    fn default_subpass(&self) -> Subpass {
+        let indices_of = |kind: AttachmentKind| -> Vec<u32> {
+            self.attachments
+                .iter()
+                .enumerate()
+                .filter(|(_, (attachment, _))| *attachment == kind)
+                .map(|(index, _)| index as u32)
+                .collect()
+        };
+
+        Subpass {
+            colors: indices_of(AttachmentKind::Color),
+            depth: indices_of(AttachmentKind::Depth).first().cloned(),
+            resolves: indices_of(AttachmentKind::Resolve),
+            inputs: Vec::new(),
+        }
+    }
+
+    fn reference(&self, index: u32) -> vk::AttachmentReference {
+        vk::AttachmentReference {
+            attachment: index,
+            layout: self.attachments[index as usize].0.subpass_layout(),
+        }
+    }
+
+    pub fn build(self, ctx: Arc<Context>) -> Renderpass {
+        let subpasses = if self.subpasses.is_empty() {
+            vec![self.default_subpass()]
+        } else {
+            self.subpasses.clone()
+        };
+
+        let dependencies = if self.dependencies.is_empty() {
+            vec![vk::SubpassDependency {
+                src_subpass: vk::SUBPASS_EXTERNAL,
+                dst_subpass: 0,
+                src_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                dst_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                src_access_mask: vk::AccessFlags::empty(),
+                dst_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_READ
+                    | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                dependency_flags: vk::DependencyFlags::empty(),
+            }]
+        } else {
+            self.dependencies.clone()
+        };
+
+        //References have to outlive the subpass descriptions pointing at them
+        let references: Vec<SubpassReferences> = subpasses
+            .iter()
+            .map(|subpass| {
+                if !subpass.resolves.is_empty() {
+                    assert_eq!(
+                        subpass.resolves.len(),
+                        subpass.colors.len(),
+                        "Every color attachment needs a resolve attachment"
+                    );
+                }
+                (
+                    subpass.colors.iter().map(|i| self.reference(*i)).collect(),
+                    subpass.depth.map(|i| self.reference(i)),
+                    subpass.resolves.iter().map(|i| self.reference(*i)).collect(),
+                    subpass
+                        .inputs
+                        .iter()
+                        .map(|i| vk::AttachmentReference {
+                            attachment: *i,
+                            layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                        })
+                        .collect(),
+                )
+            })
+            .collect();
+
+        let subpass_descriptions: Vec<vk::SubpassDescription> = references
+            .iter()
+            .map(|(colors, depth, resolves, inputs)| {
+                let mut description = vk::SubpassDescription::builder()
+                    .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+                    .color_attachments(colors)
+                    .input_attachments(inputs);
+                if !resolves.is_empty() {
+                    description = description.resolve_attachments(resolves);
+                }
+                if let Some(depth) = depth {
+                    description = description.depth_stencil_attachment(depth);
+                }
+                description.build()
+            })
+            .collect();
+
+        let attachments: Vec<vk::AttachmentDescription> = self
+            .attachments
+            .iter()
+            .map(|(_, description)| *description)
+            .collect();
+
+        Renderpass::new(
+            vk::RenderPassCreateInfo::builder()
+                .attachments(&attachments)
+                .subpasses(&subpass_descriptions)
+                .dependencies(&dependencies)
+                .build(),
+            ctx,
+        )
+    }
+}
@@ -0,0 +1,460 @@
+//KTX2 and DDS texture containers with precomputed mip chains, cube faces and array layers
+use ash::vk;
+use std::{fs, io, path::Path};
+
+const KTX2_IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+const DDS_MAGIC: &[u8; 4] = b"DDS ";
+
+//DDS header flags
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_RGB: u32 = 0x40;
+const DDSCAPS2_CUBEMAP: u32 = 0x200;
+const DDSCAPS2_VOLUME: u32 = 0x20_0000;
+const DDS_RESOURCE_MISC_TEXTURECUBE: u32 = 0x4;
+const DDS_DIMENSION_TEXTURE3D: u32 = 4;
+
+pub struct TextureFile {
+    pub format: vk::Format,
+    pub extent: vk::Extent3D,
+    pub mip_levels: u32,
+    //Array layers times cube faces, faces of a layer are next to each other
+    pub array_layers: u32,
+    pub cubemap: bool,
+    //Tightly packed texels of every mip level, each holding all of its layers in order
+    pub levels: Vec<Vec<u8>>,
+}
+
+impl TextureFile {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<TextureFile> {
+        Self::parse(&fs::read(path)?)
+    }
+
+    //Detects the container from its magic bytes
+    pub fn parse(bytes: &[u8]) -> io::Result<TextureFile> {
+        if bytes.starts_with(&KTX2_IDENTIFIER) {
+            parse_ktx2(bytes)
+        } else if bytes.starts_with(DDS_MAGIC) {
+            parse_dds(bytes)
+        } else {
+            Err(invalid("Unknown texture container"))
+        }
+    }
+
+    pub fn view_type(&self) -> vk::ImageViewType {
+        if self.cubemap {
+            if self.array_layers > 6 {
+                vk::ImageViewType::CUBE_ARRAY
+            } else {
+                vk::ImageViewType::CUBE
+            }
+        } else if self.extent.depth > 1 {
+            vk::ImageViewType::TYPE_3D
+        } else if self.array_layers > 1 {
+            vk::ImageViewType::TYPE_2D_ARRAY
+        } else {
+            vk::ImageViewType::TYPE_2D
+        }
+    }
+}
+
+//Texel block width, height and size in bytes of the formats the loader understands
+pub fn block_size(format: vk::Format) -> Option<(u32, u32, usize)> {
+    let size = match format {
+        vk::Format::R8_UNORM => (1, 1, 1),
+        vk::Format::R8G8_UNORM => (1, 1, 2),
+        vk::Format::R8G8B8A8_UNORM
+        | vk::Format::R8G8B8A8_SRGB
+        | vk::Format::B8G8R8A8_UNORM
+        | vk::Format::B8G8R8A8_SRGB => (1, 1, 4),
+        vk::Format::R16G16B16A16_SFLOAT => (1, 1, 8),
+        vk::Format::R32G32B32A32_SFLOAT => (1, 1, 16),
+        vk::Format::BC1_RGB_UNORM_BLOCK
+        | vk::Format::BC1_RGB_SRGB_BLOCK
+        | vk::Format::BC1_RGBA_UNORM_BLOCK
+        | vk::Format::BC1_RGBA_SRGB_BLOCK
+        | vk::Format::BC4_UNORM_BLOCK
+        | vk::Format::BC4_SNORM_BLOCK
+        | vk::Format::ETC2_R8G8B8_UNORM_BLOCK
+        | vk::Format::ETC2_R8G8B8_SRGB_BLOCK
+        | vk::Format::ETC2_R8G8B8A1_UNORM_BLOCK
+        | vk::Format::ETC2_R8G8B8A1_SRGB_BLOCK => (4, 4, 8),
+        vk::Format::BC2_UNORM_BLOCK
+        | vk::Format::BC2_SRGB_BLOCK
+        | vk::Format::BC3_UNORM_BLOCK
+        | vk::Format::BC3_SRGB_BLOCK
+        | vk::Format::BC5_UNORM_BLOCK
+        | vk::Format::BC5_SNORM_BLOCK
+        | vk::Format::BC6H_UFLOAT_BLOCK
+        | vk::Format::BC6H_SFLOAT_BLOCK
+        | vk::Format::BC7_UNORM_BLOCK
+        | vk::Format::BC7_SRGB_BLOCK
+        | vk::Format::ETC2_R8G8B8A8_UNORM_BLOCK
+        | vk::Format::ETC2_R8G8B8A8_SRGB_BLOCK => (4, 4, 16),
+        vk::Format::ASTC_4X4_UNORM_BLOCK | vk::Format::ASTC_4X4_SRGB_BLOCK => (4, 4, 16),
+        vk::Format::ASTC_5X5_UNORM_BLOCK | vk::Format::ASTC_5X5_SRGB_BLOCK => (5, 5, 16),
+        vk::Format::ASTC_6X6_UNORM_BLOCK | vk::Format::ASTC_6X6_SRGB_BLOCK => (6, 6, 16),
+        vk::Format::ASTC_8X8_UNORM_BLOCK | vk::Format::ASTC_8X8_SRGB_BLOCK => (8, 8, 16),
+        vk::Format::ASTC_10X10_UNORM_BLOCK | vk::Format::ASTC_10X10_SRGB_BLOCK => (10, 10, 16),
+        vk::Format::ASTC_12X12_UNORM_BLOCK | vk::Format::ASTC_12X12_SRGB_BLOCK => (12, 12, 16),
+        _ => return None,
+    };
+    Some(size)
+}
+
+//Bytes of one layer of a mip level, `level` has to be below max_mip_levels(extent)
+pub fn level_size(format: vk::Format, extent: vk::Extent3D, level: u32) -> io::Result<usize> {
+    let (block_width, block_height, block_bytes) = block_size(format)
+        .ok_or_else(|| invalid(&format!("Unsupported texture format {:?}", format)))?;
+    let width = (extent.width >> level).max(1);
+    let height = (extent.height >> level).max(1);
+    let depth = (extent.depth >> level).max(1);
+
+    (width.div_ceil(block_width) as usize)
+        .checked_mul(height.div_ceil(block_height) as usize)
+        .and_then(|size| size.checked_mul(depth as usize))
+        .and_then(|size| size.checked_mul(block_bytes))
+        .ok_or_else(|| invalid("Texture level is too large"))
+}
+
+//Length of the full mip chain of the largest dimension, 0 for an empty extent
+pub fn max_mip_levels(extent: vk::Extent3D) -> u32 {
+    32 - extent
+        .width
+        .max(extent.height)
+        .max(extent.depth)
+        .leading_zeros()
+}
+
+//Rejects header values that would overflow or allocate more than the file can hold
+fn validate(extent: vk::Extent3D, mip_levels: u32) -> io::Result<()> {
+    if extent.width == 0 {
+        return Err(invalid("Texture has no width"));
+    }
+    if mip_levels > max_mip_levels(extent) {
+        return Err(invalid(
+            "Texture has more mip levels than its extent allows",
+        ));
+    }
+    Ok(())
+}
+
+fn parse_ktx2(bytes: &[u8]) -> io::Result<TextureFile> {
+    let format = vk::Format::from_raw(read_u32(bytes, 12)? as i32);
+    let width = read_u32(bytes, 20)?;
+    let height = read_u32(bytes, 24)?.max(1);
+    let depth = read_u32(bytes, 28)?.max(1);
+    let layers = read_u32(bytes, 32)?.max(1);
+    let faces = read_u32(bytes, 36)?.max(1);
+    let mip_levels = read_u32(bytes, 40)?.max(1);
+    let supercompression = read_u32(bytes, 44)?;
+
+    if format == vk::Format::UNDEFINED {
+        return Err(invalid("Basis Universal KTX2 files are not supported"));
+    }
+    if supercompression != 0 {
+        return Err(invalid("Supercompressed KTX2 files are not supported"));
+    }
+    if block_size(format).is_none() {
+        return Err(invalid(&format!("Unsupported KTX2 format {:?}", format)));
+    }
+
+    let extent = vk::Extent3D {
+        width,
+        height,
+        depth,
+    };
+    validate(extent, mip_levels)?;
+    let array_layers = layers
+        .checked_mul(faces)
+        .ok_or_else(|| invalid("KTX2 layer count is too large"))?;
+
+    //The level index follows the 80 byte header, level 0 comes first
+    let levels = (0..mip_levels)
+        .map(|level| {
+            let entry = 80 + level as usize * 24;
+            let offset = read_u64(bytes, entry)? as usize;
+            let length = read_u64(bytes, entry + 8)? as usize;
+            let expected = level_size(format, extent, level)?
+                .checked_mul(array_layers as usize)
+                .ok_or_else(|| invalid("KTX2 level is too large"))?;
+            if length != expected {
+                return Err(invalid("KTX2 level size does not match its extent"));
+            }
+            slice(bytes, offset, length).map(|data| data.to_vec())
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+
+    Ok(TextureFile {
+        format,
+        extent,
+        mip_levels,
+        array_layers,
+        cubemap: faces == 6,
+        levels,
+    })
+}
+
+fn parse_dds(bytes: &[u8]) -> io::Result<TextureFile> {
+    //Header fields are read relative to the 124 byte header after the magic
+    let header = |offset: usize| read_u32(bytes, 4 + offset);
+
+    let height = header(8)?;
+    let width = header(12)?;
+    let depth = header(20)?.max(1);
+    let mip_levels = header(24)?.max(1);
+    let pixel_flags = header(76)?;
+    let four_cc = slice(bytes, 4 + 80, 4)?;
+    let caps2 = header(108)?;
+
+    let mut data_offset = 128;
+    let mut cubemap = caps2 & DDSCAPS2_CUBEMAP != 0;
+    let mut volume = caps2 & DDSCAPS2_VOLUME != 0;
+    let mut array_size = 1;
+
+    let format = if pixel_flags & DDPF_FOURCC != 0 {
+        match four_cc {
+            b"DXT1" => vk::Format::BC1_RGBA_UNORM_BLOCK,
+            b"DXT3" => vk::Format::BC2_UNORM_BLOCK,
+            b"DXT5" => vk::Format::BC3_UNORM_BLOCK,
+            b"ATI1" | b"BC4U" => vk::Format::BC4_UNORM_BLOCK,
+            b"BC4S" => vk::Format::BC4_SNORM_BLOCK,
+            b"ATI2" | b"BC5U" => vk::Format::BC5_UNORM_BLOCK,
+            b"BC5S" => vk::Format::BC5_SNORM_BLOCK,
+            b"DX10" => {
+                data_offset += 20;
+                let misc_flag = read_u32(bytes, 136)?;
+                cubemap = misc_flag & DDS_RESOURCE_MISC_TEXTURECUBE != 0;
+                volume = read_u32(bytes, 132)? == DDS_DIMENSION_TEXTURE3D;
+                array_size = read_u32(bytes, 140)?.max(1);
+                dxgi_format(read_u32(bytes, 128)?)?
+            }
+            _ => {
+                return Err(invalid(&format!(
+                    "Unsupported DDS FourCC {:?}",
+                    String::from_utf8_lossy(four_cc)
+                )))
+            }
+        }
+    } else if pixel_flags & DDPF_RGB != 0 && header(84)? == 32 {
+        match header(88)? {
+            0x0000_00ff => vk::Format::R8G8B8A8_UNORM,
+            0x00ff_0000 => vk::Format::B8G8R8A8_UNORM,
+            _ => return Err(invalid("Unsupported DDS channel masks")),
+        }
+    } else {
+        return Err(invalid("Unsupported DDS pixel format"));
+    };
+
+    let extent = vk::Extent3D {
+        width,
+        height,
+        depth: if volume { depth } else { 1 },
+    };
+    validate(extent, mip_levels)?;
+    let array_layers = array_size
+        .checked_mul(if cubemap { 6 } else { 1 })
+        .ok_or_else(|| invalid("DDS array size is too large"))?;
+
+    //DDS stores the full mip chain of each layer one after another, regroup it per level
+    let mut levels = vec![Vec::new(); mip_levels as usize];
+    let mut offset = data_offset;
+    for _ in 0..array_layers {
+        for (level, data) in levels.iter_mut().enumerate() {
+            let size = level_size(format, extent, level as u32)?;
+            data.extend_from_slice(slice(bytes, offset, size)?);
+            offset += size;
+        }
+    }
+
+    Ok(TextureFile {
+        format,
+        extent,
+        mip_levels,
+        array_layers,
+        cubemap,
+        levels,
+    })
+}
+
+fn dxgi_format(format: u32) -> io::Result<vk::Format> {
+    let format = match format {
+        2 => vk::Format::R32G32B32A32_SFLOAT,
+        10 => vk::Format::R16G16B16A16_SFLOAT,
+        28 => vk::Format::R8G8B8A8_UNORM,
+        29 => vk::Format::R8G8B8A8_SRGB,
+        49 => vk::Format::R8G8_UNORM,
+        61 => vk::Format::R8_UNORM,
+        71 => vk::Format::BC1_RGBA_UNORM_BLOCK,
+        72 => vk::Format::BC1_RGBA_SRGB_BLOCK,
+        74 => vk::Format::BC2_UNORM_BLOCK,
+        75 => vk::Format::BC2_SRGB_BLOCK,
+        77 => vk::Format::BC3_UNORM_BLOCK,
+        78 => vk::Format::BC3_SRGB_BLOCK,
+        80 => vk::Format::BC4_UNORM_BLOCK,
+        81 => vk::Format::BC4_SNORM_BLOCK,
+        83 => vk::Format::BC5_UNORM_BLOCK,
+        84 => vk::Format::BC5_SNORM_BLOCK,
+        87 => vk::Format::B8G8R8A8_UNORM,
+        91 => vk::Format::B8G8R8A8_SRGB,
+        95 => vk::Format::BC6H_UFLOAT_BLOCK,
+        96 => vk::Format::BC6H_SFLOAT_BLOCK,
+        98 => vk::Format::BC7_UNORM_BLOCK,
+        99 => vk::Format::BC7_SRGB_BLOCK,
+        _ => return Err(invalid(&format!("Unsupported DXGI format {}", format))),
+    };
+    Ok(format)
+}
+
+fn slice(bytes: &[u8], offset: usize, length: usize) -> io::Result<&[u8]> {
+    offset
+        .checked_add(length)
+        .and_then(|end| bytes.get(offset..end))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "Texture file is truncated"))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> io::Result<u32> {
+    let data = slice(bytes, offset, 4)?;
+    Ok(u32::from_le_bytes([data[0], data[1], data[2], data[3]]))
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> io::Result<u64> {
+    Ok(read_u32(bytes, offset)? as u64 | (read_u32(bytes, offset + 4)? as u64) << 32)
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //4x4 RGBA8 KTX2 with `mip_levels` levels, the level data follows the level index
+    fn ktx2(mip_levels: u32) -> Vec<u8> {
+        let mut bytes = KTX2_IDENTIFIER.to_vec();
+        for value in &[
+            vk::Format::R8G8B8A8_UNORM.as_raw() as u32,
+            1,
+            4,
+            4,
+            0,
+            0,
+            1,
+            mip_levels,
+            0,
+        ] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.resize(80, 0);
+
+        let levels = mip_levels.min(3);
+        let mut offset = 80 + levels as u64 * 24;
+        for level in 0..levels {
+            let length = (4u64 >> level).pow(2) * 4;
+            bytes.extend_from_slice(&offset.to_le_bytes());
+            bytes.extend_from_slice(&length.to_le_bytes());
+            bytes.extend_from_slice(&length.to_le_bytes());
+            offset += length;
+        }
+        for level in 0..levels {
+            bytes.extend(vec![
+                level as u8;
+                ((4 >> level) * (4 >> level) * 4) as usize
+            ]);
+        }
+        bytes
+    }
+
+    //8x8 DXT1 DDS header, `mip_levels` and the header flags are written as given
+    fn dds(mip_levels: u32, caps2: u32, four_cc: &[u8; 4]) -> Vec<u8> {
+        let mut bytes = DDS_MAGIC.to_vec();
+        bytes.resize(128, 0);
+        let mut write = |offset: usize, value: u32| {
+            bytes[4 + offset..8 + offset].copy_from_slice(&value.to_le_bytes())
+        };
+        write(8, 8);
+        write(12, 8);
+        write(24, mip_levels);
+        write(76, DDPF_FOURCC);
+        write(108, caps2);
+        bytes[84..88].copy_from_slice(four_cc);
+        bytes
+    }
+
+    fn error_kind(bytes: &[u8]) -> io::ErrorKind {
+        match TextureFile::parse(bytes) {
+            Ok(_) => panic!("Texture file should be rejected"),
+            Err(error) => error.kind(),
+        }
+    }
+
+    #[test]
+    fn parses_ktx2_mip_chain() {
+        let file = TextureFile::parse(&ktx2(3)).unwrap();
+        assert_eq!(file.format, vk::Format::R8G8B8A8_UNORM);
+        assert_eq!(file.mip_levels, 3);
+        assert_eq!(file.view_type(), vk::ImageViewType::TYPE_2D);
+        let sizes: Vec<usize> = file.levels.iter().map(|level| level.len()).collect();
+        assert_eq!(sizes, vec![64, 16, 4]);
+        assert!(file.levels[2].iter().all(|&byte| byte == 2));
+    }
+
+    #[test]
+    fn parses_dds_levels() {
+        let mut bytes = dds(2, 0, b"DXT1");
+        bytes.extend(vec![0; 32 + 8]);
+        let file = TextureFile::parse(&bytes).unwrap();
+        assert_eq!(file.format, vk::Format::BC1_RGBA_UNORM_BLOCK);
+        assert_eq!(
+            file.levels.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![32, 8]
+        );
+    }
+
+    #[test]
+    fn truncated_headers_are_errors() {
+        let ktx2 = ktx2(3);
+        assert_eq!(error_kind(&ktx2[..30]), io::ErrorKind::UnexpectedEof);
+        assert_eq!(error_kind(&ktx2[..100]), io::ErrorKind::UnexpectedEof);
+        assert_eq!(
+            error_kind(&ktx2[..ktx2.len() - 1]),
+            io::ErrorKind::UnexpectedEof
+        );
+
+        let header = dds(1, 0, b"DXT1");
+        assert_eq!(error_kind(&header[..60]), io::ErrorKind::UnexpectedEof);
+        assert_eq!(error_kind(&header), io::ErrorKind::UnexpectedEof);
+        assert_eq!(
+            error_kind(&dds(1, 0, b"DX10")),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn oversized_mip_counts_are_rejected() {
+        assert_eq!(error_kind(&ktx2(4)), io::ErrorKind::InvalidData);
+        assert_eq!(error_kind(&ktx2(u32::MAX)), io::ErrorKind::InvalidData);
+        assert_eq!(error_kind(&dds(5, 0, b"DXT1")), io::ErrorKind::InvalidData);
+        assert_eq!(
+            error_kind(&dds(u32::MAX, 0, b"DXT1")),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn overflowing_sizes_are_rejected() {
+        //DX10 cube array with 2^31 layers overflows the face count
+        let mut bytes = dds(1, DDSCAPS2_CUBEMAP, b"DX10");
+        for value in &[71, 3, DDS_RESOURCE_MISC_TEXTURECUBE, 1 << 31, 0] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        assert_eq!(error_kind(&bytes), io::ErrorKind::InvalidData);
+
+        //Level offset at the end of the address space
+        let mut bytes = ktx2(3);
+        bytes[80..88].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(error_kind(&bytes), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,120 @@
+use super::layout::ShaderLayout;
+use super::Buffer;
+use crate::Context;
+use ash::vk;
+use std::sync::Arc;
+
+//Uniform buffer of T elements, T has to match the GLSL std140 layout
+pub struct UniformBuffer<T: ShaderLayout> {
+    buffer: Buffer<T>,
+}
+
+impl<T: ShaderLayout> UniformBuffer<T> {
+    const LAYOUT_CHECK: () = assert!(
+        T::STD140.matches,
+        "Type does not match std140 layout, see #[layout = \"std140\"] for details"
+    );
+
+    pub fn new(data: &[T], context: Arc<Context>) -> Self {
+        let () = Self::LAYOUT_CHECK;
+
+        let buffer = Buffer::from_data(
+            data,
+            vk::BufferUsageFlags::UNIFORM_BUFFER,
+            vk_mem::MemoryUsage::CpuToGpu,
+            context,
+        );
+
+        Self { buffer }
+    }
+
+    pub fn update(&self, data: &[T]) {
+        self.buffer.write(0..data.len(), data);
+    }
+
+    pub fn update_at(&self, index: usize, value: T) {
+        self.buffer.write(index..index + 1, &[value]);
+    }
+
+    //Descriptor info covering the whole buffer
+    pub fn descriptor_info(&self) -> vk::DescriptorBufferInfo {
+        vk::DescriptorBufferInfo {
+            buffer: self.buffer.buffer,
+            offset: 0,
+            range: self.buffer.size,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    pub fn buffer(&self) -> &Buffer<T> {
+        &self.buffer
+    }
+}
+
+//Storage buffer of T elements, T has to match the GLSL std430 layout
+pub struct StorageBuffer<T: ShaderLayout> {
+    buffer: Buffer<T>,
+}
+
+impl<T: ShaderLayout> StorageBuffer<T> {
+    const LAYOUT_CHECK: () = assert!(
+        T::STD430.matches,
+        "Type does not match std430 layout, see #[layout = \"std430\"] for details"
+    );
+
+    pub fn new(data: &[T], context: Arc<Context>) -> Self {
+        let storage = Self::with_len(data.len(), context);
+        storage.update(data);
+        storage
+    }
+
+    //Uninitialized buffer, usually written by a compute shader
+    pub fn with_len(len: usize, context: Arc<Context>) -> Self {
+        let () = Self::LAYOUT_CHECK;
+
+        let buffer = Buffer::with_len(
+            len,
+            vk::BufferUsageFlags::STORAGE_BUFFER,
+            vk_mem::MemoryUsage::CpuToGpu,
+            context,
+        );
+
+        Self { buffer }
+    }
+
+    pub fn update(&self, data: &[T]) {
+        self.buffer.write(0..data.len(), data);
+    }
+
+    //Contents written by the GPU, wait for the work to finish first
+    pub fn read(&self) -> Vec<T> {
+        self.buffer.read()
+    }
+
+    pub fn descriptor_info(&self) -> vk::DescriptorBufferInfo {
+        vk::DescriptorBufferInfo {
+            buffer: self.buffer.buffer,
+            offset: 0,
+            range: self.buffer.size,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    pub fn buffer(&self) -> &Buffer<T> {
+        &self.buffer
+    }
+}
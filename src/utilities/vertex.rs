@@ -0,0 +1,16 @@
+use ash::vk;
+
+//Vertex buffer layout of a #[repr(C)] struct, usually implemented with #[derive(VertexInput)]
+pub trait VertexInput: Sized {
+    const INPUT_RATE: vk::VertexInputRate;
+
+    fn attribute_descriptions(binding: u32) -> Vec<vk::VertexInputAttributeDescription>;
+
+    fn binding_description(binding: u32) -> vk::VertexInputBindingDescription {
+        vk::VertexInputBindingDescription {
+            binding,
+            stride: std::mem::size_of::<Self>() as u32,
+            input_rate: Self::INPUT_RATE,
+        }
+    }
+}
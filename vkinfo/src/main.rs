@@ -0,0 +1,379 @@
+//Prints every physical device the way the renderer sees it, attach the output to bug reports.
+//Run with `cargo run -p vkinfo`, add `--json` for machine readable output.
+use std::fmt::Debug;
+use vulkan::DeviceReport;
+
+//Report tree shared by the text and JSON output
+enum Value {
+    Null,
+    Bool(bool),
+    Number(String),
+    Text(String),
+    List(Vec<Value>),
+    Map(Vec<(&'static str, Value)>),
+}
+
+fn number<T: ToString>(value: T) -> Value {
+    Value::Number(value.to_string())
+}
+
+//Flags and enums print their Vulkan names
+fn name<T: Debug>(value: T) -> Value {
+    Value::Text(format!("{:?}", value))
+}
+
+fn family(index: Option<u32>) -> Value {
+    index.map_or(Value::Null, number)
+}
+
+fn main() {
+    let json = std::env::args().any(|arg| arg == "--json");
+    let devices: Vec<Value> = DeviceReport::collect_all("vkinfo")
+        .iter()
+        .map(device)
+        .collect();
+
+    let mut out = String::new();
+    if json {
+        write_json(
+            &mut out,
+            &Value::Map(vec![("devices", Value::List(devices))]),
+            0,
+        );
+        out.push('\n');
+    } else if devices.is_empty() {
+        out.push_str("No Vulkan devices found\n");
+    } else {
+        for (index, device) in devices.iter().enumerate() {
+            out.push_str(&format!("Device {}\n", index));
+            write_text(&mut out, device, 1);
+            out.push('\n');
+        }
+    }
+    print!("{}", out);
+}
+
+fn device(report: &DeviceReport) -> Value {
+    let picks = report.picks;
+    let picked_for = |index: u32| {
+        [
+            ("graphics", picks.graphics),
+            ("compute", picks.compute),
+            ("present", picks.present),
+        ]
+        .iter()
+        .filter(|(_, pick)| *pick == Some(index))
+        .map(|(queue, _)| Value::Text(queue.to_string()))
+        .collect()
+    };
+
+    let limits = &report.limits;
+    let capabilities = &report.capabilities;
+    let [major, minor, patch] = report.api_version;
+
+    Value::Map(vec![
+        ("name", Value::Text(report.name.clone())),
+        ("type", name(report.device_type)),
+        (
+            "vendor_id",
+            Value::Text(format!("{:#06x}", report.vendor_id)),
+        ),
+        (
+            "device_id",
+            Value::Text(format!("{:#06x}", report.device_id)),
+        ),
+        (
+            "api_version",
+            Value::Text(format!("{}.{}.{}", major, minor, patch)),
+        ),
+        ("driver_version", number(report.driver_version)),
+        ("suitable", Value::Bool(report.suitable)),
+        (
+            "queue_families",
+            Value::List(
+                report
+                    .queue_families
+                    .iter()
+                    .map(|queue_family| {
+                        Value::Map(vec![
+                            ("index", number(queue_family.index)),
+                            ("flags", name(queue_family.flags)),
+                            ("queues", number(queue_family.queue_count)),
+                            ("timestamp_bits", number(queue_family.timestamp_valid_bits)),
+                            ("picked_for", Value::List(picked_for(queue_family.index))),
+                        ])
+                    })
+                    .collect(),
+            ),
+        ),
+        (
+            "queue_picks",
+            Value::Map(vec![
+                ("graphics", family(picks.graphics)),
+                ("compute", family(picks.compute)),
+                ("present", family(picks.present)),
+            ]),
+        ),
+        (
+            "limits",
+            Value::Map(vec![
+                (
+                    "min_uniform_buffer_offset_alignment",
+                    number(limits.min_uniform_buffer_offset_alignment),
+                ),
+                (
+                    "min_storage_buffer_offset_alignment",
+                    number(limits.min_storage_buffer_offset_alignment),
+                ),
+                (
+                    "max_push_constants_size",
+                    number(limits.max_push_constants_size),
+                ),
+                (
+                    "max_bound_descriptor_sets",
+                    number(limits.max_bound_descriptor_sets),
+                ),
+                (
+                    "max_per_stage_descriptor_uniform_buffers",
+                    number(limits.max_per_stage_descriptor_uniform_buffers),
+                ),
+                (
+                    "max_per_stage_descriptor_storage_buffers",
+                    number(limits.max_per_stage_descriptor_storage_buffers),
+                ),
+                (
+                    "max_per_stage_descriptor_sampled_images",
+                    number(limits.max_per_stage_descriptor_sampled_images),
+                ),
+                (
+                    "max_per_stage_resources",
+                    number(limits.max_per_stage_resources),
+                ),
+                (
+                    "max_descriptor_set_uniform_buffers",
+                    number(limits.max_descriptor_set_uniform_buffers),
+                ),
+                (
+                    "max_descriptor_set_storage_buffers",
+                    number(limits.max_descriptor_set_storage_buffers),
+                ),
+                (
+                    "max_descriptor_set_sampled_images",
+                    number(limits.max_descriptor_set_sampled_images),
+                ),
+                ("max_sample_count", name(report.max_sample_count)),
+                (
+                    "max_image_dimension_2d",
+                    number(limits.max_image_dimension2_d),
+                ),
+                (
+                    "max_compute_work_group_invocations",
+                    number(limits.max_compute_work_group_invocations),
+                ),
+                (
+                    "max_sampler_anisotropy",
+                    number(limits.max_sampler_anisotropy),
+                ),
+                ("timestamp_period_ns", number(limits.timestamp_period)),
+            ]),
+        ),
+        (
+            "optional_features",
+            Value::Map(vec![
+                (
+                    "sampler_anisotropy",
+                    Value::Bool(capabilities.sampler_anisotropy),
+                ),
+                ("wide_lines", Value::Bool(capabilities.wide_lines)),
+                (
+                    "fill_mode_non_solid",
+                    Value::Bool(capabilities.fill_mode_non_solid),
+                ),
+                ("geometry_shader", Value::Bool(capabilities.geometry_shader)),
+                (
+                    "multi_draw_indirect",
+                    Value::Bool(capabilities.multi_draw_indirect),
+                ),
+                (
+                    "draw_indirect_first_instance",
+                    Value::Bool(capabilities.draw_indirect_first_instance),
+                ),
+                (
+                    "draw_indirect_count",
+                    Value::Bool(capabilities.draw_indirect_count),
+                ),
+                ("memory_budget", Value::Bool(capabilities.memory_budget)),
+                (
+                    "texture_compression_bc",
+                    Value::Bool(capabilities.texture_compression_bc),
+                ),
+                (
+                    "texture_compression_etc2",
+                    Value::Bool(capabilities.texture_compression_etc2),
+                ),
+                (
+                    "texture_compression_astc_ldr",
+                    Value::Bool(capabilities.texture_compression_astc_ldr),
+                ),
+                (
+                    "pipeline_statistics_query",
+                    Value::Bool(capabilities.pipeline_statistics_query),
+                ),
+                (
+                    "occlusion_query_precise",
+                    Value::Bool(capabilities.occlusion_query_precise),
+                ),
+            ]),
+        ),
+        (
+            "memory_heaps",
+            Value::List(
+                report
+                    .memory_heaps
+                    .iter()
+                    .map(|heap| {
+                        Value::Map(vec![
+                            ("size_mib", number(heap.size / (1024 * 1024))),
+                            ("flags", name(heap.flags)),
+                            (
+                                "memory_types",
+                                Value::List(heap.memory_types.iter().map(name).collect()),
+                            ),
+                        ])
+                    })
+                    .collect(),
+            ),
+        ),
+        (
+            "formats",
+            Value::List(
+                report
+                    .formats
+                    .iter()
+                    .map(|format| {
+                        Value::Map(vec![
+                            ("format", name(format.format)),
+                            ("linear", name(format.linear_tiling)),
+                            ("optimal", name(format.optimal_tiling)),
+                            ("buffer", name(format.buffer)),
+                        ])
+                    })
+                    .collect(),
+            ),
+        ),
+        (
+            "extensions",
+            Value::List(
+                report
+                    .extensions
+                    .iter()
+                    .map(|extension| Value::Text(extension.clone()))
+                    .collect(),
+            ),
+        ),
+    ])
+}
+
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        //Empty flags print as an empty string
+        Value::Null => Some("none".to_string()),
+        Value::Text(value) if value.is_empty() => Some("none".to_string()),
+        Value::List(items) if items.is_empty() => Some("none".to_string()),
+        Value::Bool(value) => Some(if *value { "yes" } else { "no" }.to_string()),
+        Value::Number(value) | Value::Text(value) => Some(value.clone()),
+        Value::List(items) if items.iter().all(|item| scalar(item).is_some()) => Some(
+            items
+                .iter()
+                .filter_map(scalar)
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        _ => None,
+    }
+}
+
+//Maps become indented blocks, lists of maps one line per entry
+fn write_text(out: &mut String, value: &Value, depth: usize) {
+    let indent = "  ".repeat(depth);
+    if let Value::Map(fields) = value {
+        for (key, value) in fields {
+            match (value, scalar(value)) {
+                (_, Some(scalar)) => out.push_str(&format!("{}{}: {}\n", indent, key, scalar)),
+                (Value::List(items), None) => {
+                    out.push_str(&format!("{}{}:\n", indent, key));
+                    for item in items {
+                        let line = match item {
+                            Value::Map(fields) => fields
+                                .iter()
+                                .map(|(key, value)| {
+                                    format!("{}: {}", key, scalar(value).unwrap_or_default())
+                                })
+                                .collect::<Vec<_>>()
+                                .join(", "),
+                            _ => scalar(item).unwrap_or_default(),
+                        };
+                        out.push_str(&format!("{}  - {}\n", indent, line));
+                    }
+                }
+                _ => {
+                    out.push_str(&format!("{}{}:\n", indent, key));
+                    write_text(out, value, depth + 1);
+                }
+            }
+        }
+    }
+}
+
+fn write_json(out: &mut String, value: &Value, depth: usize) {
+    let indent = "  ".repeat(depth + 1);
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(value) => out.push_str(if *value { "true" } else { "false" }),
+        Value::Number(value) => out.push_str(value),
+        Value::Text(value) => write_json_string(out, value),
+        Value::List(items) if items.is_empty() => out.push_str("[]"),
+        Value::List(items) => {
+            out.push_str("[\n");
+            for (index, item) in items.iter().enumerate() {
+                out.push_str(&indent);
+                write_json(out, item, depth + 1);
+                out.push_str(if index + 1 < items.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&"  ".repeat(depth));
+            out.push(']');
+        }
+        Value::Map(fields) => {
+            out.push_str("{\n");
+            for (index, (key, value)) in fields.iter().enumerate() {
+                out.push_str(&indent);
+                write_json_string(out, key);
+                out.push_str(": ");
+                write_json(out, value, depth + 1);
+                out.push_str(if index + 1 < fields.len() {
+                    ",\n"
+                } else {
+                    "\n"
+                });
+            }
+            out.push_str(&"  ".repeat(depth));
+            out.push('}');
+        }
+    }
+}
+
+fn write_json_string(out: &mut String, value: &str) {
+    out.push('"');
+    for character in value.chars() {
+        match character {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            character if (character as u32) < 0x20 => {
+                out.push_str(&format!("\\u{:04x}", character as u32))
+            }
+            character => out.push(character),
+        }
+    }
+    out.push('"');
+}